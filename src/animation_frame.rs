@@ -7,19 +7,20 @@ use web_sys::Window;
 
 pub struct AnimationFrameHandler {
     window: Rc<web_sys::Window>,
-    closure: Rc<RefCell<Closure<dyn FnMut()>>>,
+    closure: Rc<RefCell<Closure<dyn FnMut(f64)>>>,
     handle: Rc<Cell<Option<i32>>>,
 }
 
 pub trait AnimationFrameProcessor {
-    fn process_frame(&mut self) -> bool;
+    /// `timestamp` is the time in milliseconds passed to the animation frame callback
+    fn process_frame(&mut self, timestamp: f64) -> bool;
 }
 
 impl AnimationFrameHandler {
     pub fn new(window: Window) -> Self {
         let handle = Rc::new(Cell::new(None));
         let window: Rc<web_sys::Window> = window.into();
-        let closure = Rc::new(RefCell::new(Closure::new(move || {})));
+        let closure = Rc::new(RefCell::new(Closure::new(move |_: f64| {})));
 
         Self {
             window,
@@ -37,9 +38,14 @@ impl AnimationFrameHandler {
             let window = self.window.clone();
             let closure = self.closure.clone();
             let processor = processor.clone();
-            move || {
+            move |timestamp: f64| {
                 handle.set(None);
-                if processor.clone().as_ref().borrow_mut().process_frame() {
+                if processor
+                    .clone()
+                    .as_ref()
+                    .borrow_mut()
+                    .process_frame(timestamp)
+                {
                     handle.set(
                         window
                             .request_animation_frame(closure.borrow_mut().as_ref().unchecked_ref())
@@ -65,6 +71,10 @@ impl AnimationFrameHandler {
         self.handle.set(Some(handle));
     }
 
+    pub fn is_scheduled(&self) -> bool {
+        self.handle.get().is_some()
+    }
+
     pub fn cancel(&mut self) {
         if let Some(handle) = self.handle.take() {
             self.window
@@ -1,15 +1,26 @@
-use std::{cell::RefCell, error::Error, fmt::Display, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    error::Error,
+    fmt::Display,
+    rc::{Rc, Weak},
+};
+
+use wasm_bindgen::{closure::Closure, prelude::wasm_bindgen, JsCast, JsValue};
+use web_sys::{
+    js_sys::{self, Date, Math},
+    window, WebSocket,
+};
 
-use wasm_bindgen::{closure::Closure, JsCast, JsValue};
-use web_sys::{window, WebSocket};
+use crate::sync_transport::{SyncTransport, WebsocketTransport};
 use yrs::{
     encoding::read::Cursor,
-    sync::{Awareness, Message, SyncMessage},
+    sync::{Awareness, Clock, Message, SyncMessage, Timestamp},
     updates::{
         decoder::{Decode, DecoderV1},
         encoder::Encode,
     },
-    ReadTxn, Transact, Update,
+    Origin, ReadTxn, Transact, Update,
 };
 
 #[derive(Debug)]
@@ -41,22 +52,319 @@ impl Error for WebSocketError {
     }
 }
 
+pub struct WasmPackClock;
+
+impl Clock for WasmPackClock {
+    fn now(&self) -> Timestamp {
+        let now = Date::now();
+        now as Timestamp
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    // Connected, and exchanging sync steps until the server signals that it is done
+    Syncing,
+    // Connected, and exchanging incremental updates
+    Synced,
+    // Waiting for the backoff to elapse before the next connection attempt
+    Reconnecting,
+    Closed,
+}
+
+/// Sent by the server once it has replied to the initial sync step
+pub const SYNC_DONE_MESSAGE_TAG: u8 = 10;
+
+/// Marks transactions applying updates received from the server,
+/// so that they aren't echoed back
+pub const REMOTE_SYNC_ORIGIN: &str = "wbbl-remote-sync";
+
+const RECONNECT_BASE_DELAY_MILLIS: f64 = 500.0;
+const RECONNECT_MAX_DELAY_MILLIS: f64 = 30_000.0;
+
+#[derive(Debug, Default)]
+pub struct ReconnectBackoff {
+    attempt: u32,
+}
+
+impl ReconnectBackoff {
+    /// Returns the delay before the next attempt, doubling with each consecutive failure.
+    /// `jitter` is expected in [0, 1), and spreads the delay over the upper half of the
+    /// window, so that clients dropped by the same outage don't reconnect in lockstep
+    pub fn next_delay_millis(&mut self, jitter: f64) -> i32 {
+        let window = (RECONNECT_BASE_DELAY_MILLIS * 2.0_f64.powi(self.attempt.min(16) as i32))
+            .min(RECONNECT_MAX_DELAY_MILLIS);
+        self.attempt = self.attempt.saturating_add(1);
+        (window * (0.5 + 0.5 * jitter.clamp(0.0, 1.0))) as i32
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+/// Connection state shared between an `AwarenessSync` and its document subscriptions
+struct SyncSession<T: SyncTransport> {
+    transport: T,
+    connection_state: Cell<ConnectionState>,
+    hydrated: Cell<bool>,
+    // Local updates made before the initial sync completed, or while disconnected
+    pending_updates: RefCell<VecDeque<Vec<u8>>>,
+    state_listeners: RefCell<Vec<Box<dyn Fn(ConnectionState)>>>,
+}
+
+impl<T: SyncTransport> SyncSession<T> {
+    fn send(&self, message: Message) -> bool {
+        if self.transport.send(message.encode_v1().as_slice()).is_err() {
+            self.transport.close(1001, "FAILED TO SEND");
+            return false;
+        }
+        true
+    }
+
+    fn set_connection_state(&self, state: ConnectionState) {
+        if self.connection_state.replace(state) != state {
+            for listener in self.state_listeners.borrow().iter() {
+                listener(state);
+            }
+        }
+    }
+
+    fn send_local_update(&self, update: Vec<u8>) {
+        // Until the initial sync is done, local edits are held back rather than
+        // interleaved with the sync steps
+        if !self.transport.is_open() || self.connection_state.get() != ConnectionState::Synced {
+            self.pending_updates.borrow_mut().push_back(update);
+        } else if self
+            .transport
+            .send(
+                Message::Sync(SyncMessage::Update(update.clone()))
+                    .encode_v1()
+                    .as_slice(),
+            )
+            .is_err()
+        {
+            self.pending_updates.borrow_mut().push_back(update);
+            self.transport.close(1001, "FAILED TO SEND");
+        }
+    }
+
+    fn flush_pending_updates(&self) -> bool {
+        let mut pending_updates = self.pending_updates.borrow_mut();
+        while let Some(update) = pending_updates.front() {
+            if self
+                .transport
+                .send(
+                    Message::Sync(SyncMessage::Update(update.clone()))
+                        .encode_v1()
+                        .as_slice(),
+                )
+                .is_err()
+            {
+                return false;
+            }
+            pending_updates.pop_front();
+        }
+        true
+    }
+}
+
+/// The client side of the y-sync protocol, independent of how messages reach the server.
+/// The owner of the transport reports its lifecycle through `handle_open` and `handle_message`
+pub struct AwarenessSync<T: SyncTransport + 'static> {
+    awareness: Rc<RefCell<Awareness>>,
+    session: Rc<SyncSession<T>>,
+    #[allow(unused)]
+    subscriptions: Vec<yrs::Subscription>,
+}
+
+impl<T: SyncTransport + 'static> AwarenessSync<T> {
+    pub fn new(awareness: Rc<RefCell<Awareness>>, transport: T) -> AwarenessSync<T> {
+        let session = Rc::new(SyncSession {
+            transport,
+            connection_state: Cell::new(ConnectionState::Connecting),
+            hydrated: Cell::new(false),
+            pending_updates: RefCell::new(VecDeque::new()),
+            state_listeners: RefCell::new(Vec::new()),
+        });
+
+        let doc_subscription = {
+            awareness
+                .borrow()
+                .doc()
+                .observe_update_v1({
+                    let session = session.clone();
+                    move |txn, update| {
+                        if txn.origin() != Some(&Origin::from(REMOTE_SYNC_ORIGIN)) {
+                            session.send_local_update(update.update.to_vec());
+                        }
+                    }
+                })
+                .expect("Successful Subscription")
+        };
+
+        let awareness_subscription = {
+            awareness.borrow().on_update({
+                let session = session.clone();
+                move |evt| {
+                    if session.transport.is_open() {
+                        if let Some(update) = evt.awareness_update() {
+                            session.send(Message::Awareness(update.clone()));
+                        }
+                    }
+                }
+            })
+        };
+
+        AwarenessSync {
+            awareness,
+            session,
+            subscriptions: vec![doc_subscription, awareness_subscription],
+        }
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.session.transport
+    }
+
+    pub fn awareness(&self) -> &Rc<RefCell<Awareness>> {
+        &self.awareness
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.session.connection_state.get()
+    }
+
+    pub fn set_connection_state(&self, state: ConnectionState) {
+        self.session.set_connection_state(state);
+    }
+
+    /// Whether the initial sync has completed at least once. Stays true across reconnects,
+    /// as the document has already been populated
+    pub fn is_hydrated(&self) -> bool {
+        self.session.hydrated.get()
+    }
+
+    pub fn on_connection_state_change<F: Fn(ConnectionState) + 'static>(&self, listener: F) {
+        self.session
+            .state_listeners
+            .borrow_mut()
+            .push(Box::new(listener));
+    }
+
+    /// Starts the initial sync once the transport has connected
+    pub fn handle_open(&self) {
+        self.session.set_connection_state(ConnectionState::Syncing);
+        let awareness = self.awareness.borrow();
+        let state_vector = awareness.doc().transact().state_vector();
+        if !self
+            .session
+            .send(Message::Sync(SyncMessage::SyncStep1(state_vector)))
+        {
+            return;
+        }
+        if !self
+            .session
+            .send(Message::Awareness(awareness.update().unwrap()))
+        {
+            return;
+        }
+        self.session.send(Message::AwarenessQuery);
+    }
+
+    pub fn handle_message(&self, message: &[u8]) {
+        let cursor: Cursor = Cursor::new(message);
+        let mut decoder = DecoderV1::new(cursor);
+        let reader = yrs::sync::protocol::MessageReader::new(&mut decoder);
+
+        for message in reader {
+            match message {
+                Ok(message) => match message {
+                    yrs::sync::Message::Sync(SyncMessage::SyncStep1(sv)) => {
+                        // Reply with sync step 2
+                        let update = {
+                            self.awareness
+                                .borrow()
+                                .doc()
+                                .transact()
+                                .encode_state_as_update_v1(&sv)
+                        };
+                        if !self
+                            .session
+                            .send(Message::Sync(SyncMessage::SyncStep2(update)))
+                        {
+                            break;
+                        }
+                    }
+                    yrs::sync::Message::Sync(SyncMessage::SyncStep2(update))
+                    | yrs::sync::Message::Sync(SyncMessage::Update(update)) => {
+                        let awareness = self.awareness.borrow();
+                        let mut txn = awareness.doc().transact_mut_with(REMOTE_SYNC_ORIGIN);
+                        if let Ok(update) = Update::decode_v1(&update) {
+                            txn.apply_update(update);
+                        } else {
+                            self.session.transport.close(1001, "FAILED TO APPLY UPDATE");
+                            break;
+                        }
+                    }
+                    yrs::sync::Message::Auth(_) => {
+                        self.session.transport.close(1001, "UNEXPECTED MESSAGE");
+                    }
+                    yrs::sync::Message::AwarenessQuery => {
+                        let update = self.awareness.borrow().update().unwrap();
+                        self.session.send(Message::Awareness(update));
+                    }
+                    yrs::sync::Message::Awareness(awareness_update) => {
+                        let mut awareness = self.awareness.borrow_mut();
+                        if awareness.apply_update(awareness_update).is_err() {
+                            self.session.transport.close(1001, "MALFORMED PAYLOAD");
+                            break;
+                        }
+                    }
+                    yrs::sync::Message::Custom(SYNC_DONE_MESSAGE_TAG, _) => {
+                        // The document now reflects the server's state, so edits made
+                        // in the meantime can be sent as incremental updates
+                        self.session.hydrated.set(true);
+                        self.session.set_connection_state(ConnectionState::Synced);
+                        if !self.session.flush_pending_updates() {
+                            self.session.transport.close(1001, "FAILED TO SEND");
+                            break;
+                        }
+                    }
+                    yrs::sync::Message::Custom(_, _) => {}
+                },
+                Err(_) => {
+                    self.session.transport.close(1001, "MALFORMED PAYLOAD");
+                    break;
+                }
+            }
+        }
+    }
+}
+
 type OnMessage = Closure<dyn FnMut(web_sys::MessageEvent)>;
 type OnClose = Closure<dyn FnMut(web_sys::CloseEvent)>;
 type OnOpen = Closure<dyn FnMut()>;
 type OnError = Closure<dyn FnMut()>;
+type Reconnect = Closure<dyn FnMut()>;
+type Listeners = Rc<RefCell<Vec<(u32, js_sys::Function)>>>;
 
+/// Drives an `AwarenessSync` over a browser websocket, reconnecting when the connection drops
 #[allow(unused)]
 pub struct AwarenessWebsocketSync {
-    websocket: Rc<RefCell<WebSocket>>,
+    sync: Rc<AwarenessSync<WebsocketTransport>>,
     keep_alive: Rc<RefCell<Closure<dyn FnMut()>>>,
     on_open: Rc<RefCell<OnOpen>>,
     on_error: Rc<RefCell<OnError>>,
     on_close: Rc<RefCell<OnClose>>,
     on_message: Rc<RefCell<OnMessage>>,
-    awareness: Rc<RefCell<Awareness>>,
+    reconnect: Rc<RefCell<Reconnect>>,
+    reconnect_handle: Rc<Cell<Option<i32>>>,
+    next_listener_handle: u32,
+    listeners: Listeners,
     keep_alive_handle: i32,
-    subscriptions: Vec<yrs::Subscription>,
 }
 
 fn create_websocket_connection(
@@ -98,17 +406,33 @@ impl AwarenessWebsocketSync {
         awareness: Rc<RefCell<Awareness>>,
         connect_path: &str,
     ) -> Result<AwarenessWebsocketSync, WebSocketError> {
-        let websocket = Rc::new(RefCell::new(create_websocket_connection(
-            connect_path,
-            awareness.borrow().client_id(),
-        )?));
+        let client_id = awareness.borrow().client_id();
+        let transport =
+            WebsocketTransport::new(create_websocket_connection(connect_path, client_id)?);
+        let sync = Rc::new(AwarenessSync::new(awareness, transport));
+        // Event listeners only hold weak references, so that dropping this releases the sync
+        let weak_sync: Weak<AwarenessSync<WebsocketTransport>> = Rc::downgrade(&sync);
+        let listeners: Listeners = Rc::new(RefCell::new(Vec::new()));
+        let backoff = Rc::new(RefCell::new(ReconnectBackoff::default()));
+        let reconnect_handle: Rc<Cell<Option<i32>>> = Rc::new(Cell::new(None));
+
+        sync.on_connection_state_change({
+            let listeners = listeners.clone();
+            move |state| {
+                for (_, listener) in listeners.borrow().iter() {
+                    let _ = listener.call1(&JsValue::UNDEFINED, &JsValue::from(state));
+                }
+            }
+        });
 
         let keep_alive = Rc::new(RefCell::new({
-            let websocket = websocket.clone();
+            let sync = weak_sync.clone();
             Closure::new(move || {
-                let ws = websocket.borrow();
-                if ws.ready_state() == 1 {
-                    let _ = ws.send_with_str("PING");
+                if let Some(sync) = sync.upgrade() {
+                    let ws = sync.transport().websocket();
+                    if ws.ready_state() == 1 {
+                        let _ = ws.send_with_str("PING");
+                    }
                 }
             })
         }));
@@ -122,8 +446,7 @@ impl AwarenessWebsocketSync {
             .map_err(WebSocketError::JsError)?;
 
         let on_message_processor = {
-            let websocket = websocket.clone();
-            let awareness = awareness.clone();
+            let sync = weak_sync.clone();
             Closure::wrap(Box::new(move |message: web_sys::MessageEvent| {
                 if message
                     .data()
@@ -134,102 +457,9 @@ impl AwarenessWebsocketSync {
                 } else if let Ok(array_buffer) =
                     message.data().dyn_into::<web_sys::js_sys::ArrayBuffer>()
                 {
-                    let bin = web_sys::js_sys::Uint8Array::new(&array_buffer).to_vec();
-                    let cursor: Cursor = Cursor::new(&bin);
-                    let mut decoder = DecoderV1::new(cursor);
-                    let reader = yrs::sync::protocol::MessageReader::new(&mut decoder);
-
-                    for message in reader {
-                        match message {
-                            Ok(message) => match message {
-                                yrs::sync::Message::Sync(SyncMessage::SyncStep1(sv)) => {
-                                    // Reply with sync step 2
-                                    let update = {
-                                        awareness
-                                            .borrow()
-                                            .doc()
-                                            .transact()
-                                            .encode_state_as_update_v1(&sv)
-                                    };
-                                    if websocket
-                                        .borrow()
-                                        .send_with_u8_array(
-                                            Message::Sync(SyncMessage::SyncStep2(update))
-                                                .encode_v1()
-                                                .as_slice(),
-                                        )
-                                        .is_err()
-                                    {
-                                        let _ = websocket
-                                            .borrow()
-                                            .close_with_code_and_reason(1001, "FAILED TO SEND");
-                                        break;
-                                    }
-                                }
-                                yrs::sync::Message::Sync(SyncMessage::SyncStep2(update)) => {
-                                    let awareness = awareness.borrow_mut();
-                                    let mut txn = awareness.doc().transact_mut();
-                                    if let Ok(update) = Update::decode_v1(&update) {
-                                        txn.apply_update(update);
-                                    } else {
-                                        let _ = websocket.borrow().close_with_code_and_reason(
-                                            1001,
-                                            "FAILED TO APPLY UPDATE",
-                                        );
-                                        break;
-                                    }
-                                }
-                                yrs::sync::Message::Sync(SyncMessage::Update(update)) => {
-                                    let awareness = awareness.borrow_mut();
-                                    let mut txn = awareness.doc().transact_mut();
-                                    if let Ok(update) = Update::decode_v1(&update) {
-                                        txn.apply_update(update);
-                                    } else {
-                                        let _ = websocket.borrow().close_with_code_and_reason(
-                                            1001,
-                                            "FAILED TO APPLY UPDATE",
-                                        );
-                                        break;
-                                    }
-                                }
-                                yrs::sync::Message::Auth(_) => {
-                                    let _ = websocket
-                                        .borrow()
-                                        .close_with_code_and_reason(1001, "UNEXPECTED MESSAGE");
-                                }
-                                yrs::sync::Message::AwarenessQuery => {
-                                    let awareness = awareness.borrow_mut();
-                                    let websocket = websocket.borrow();
-                                    let message = Message::Awareness(awareness.update().unwrap());
-                                    if websocket
-                                        .send_with_u8_array(message.encode_v1().as_slice())
-                                        .is_err()
-                                    {
-                                        let _ = websocket
-                                            .close_with_code_and_reason(1001, "FAILED TO SEND");
-                                    }
-                                }
-                                yrs::sync::Message::Awareness(awareness_update) => {
-                                    let mut awareness = awareness.borrow_mut();
-                                    if awareness.apply_update(awareness_update).is_err() {
-                                        let _ = websocket
-                                            .borrow()
-                                            .close_with_code_and_reason(1001, "MALFORMED PAYLOAD");
-                                        break;
-                                    }
-                                }
-                                yrs::sync::Message::Custom(10, _) => {
-                                    // TODO: Handle done message, need to transition into delta state
-                                }
-                                yrs::sync::Message::Custom(_, _) => {}
-                            },
-                            Err(_) => {
-                                let _ = websocket
-                                    .borrow()
-                                    .close_with_code_and_reason(1001, "MALFORMED PAYLOAD");
-                                break;
-                            }
-                        }
+                    if let Some(sync) = sync.upgrade() {
+                        let bin = web_sys::js_sys::Uint8Array::new(&array_buffer).to_vec();
+                        sync.handle_message(&bin);
                     }
                 }
             }) as Box<dyn FnMut(web_sys::MessageEvent)>)
@@ -248,51 +478,22 @@ impl AwarenessWebsocketSync {
         }));
 
         let on_open = Rc::new(RefCell::new({
-            let websocket = websocket.clone();
-            let awareness = awareness.clone();
+            let sync = weak_sync.clone();
+            let backoff = backoff.clone();
             Closure::wrap(Box::new(move || {
-                let ws = websocket.borrow();
-                let awareness = awareness.borrow();
-                let state_vector = awareness.doc().transact().state_vector();
-                if ws
-                    .send_with_u8_array(
-                        Message::Sync(SyncMessage::SyncStep1(state_vector))
-                            .encode_v1()
-                            .as_slice(),
-                    )
-                    .is_err()
-                {
-                    let _ = websocket
-                        .borrow()
-                        .close_with_code_and_reason(1001, "FAILED TO SEND");
-                    return;
-                }
-
-                let awareness_message = Message::Awareness(awareness.update().unwrap());
-                if ws
-                    .send_with_u8_array(awareness_message.encode_v1().as_slice())
-                    .is_err()
-                {
-                    let _ = ws.close_with_code_and_reason(1001, "FAILED TO SEND");
-                    return;
-                }
-
-                let awareness_query_message = Message::AwarenessQuery;
-                if ws
-                    .send_with_u8_array(awareness_query_message.encode_v1().as_slice())
-                    .is_err()
-                {
-                    let _ = ws.close_with_code_and_reason(1001, "FAILED TO SEND");
+                backoff.borrow_mut().reset();
+                if let Some(sync) = sync.upgrade() {
+                    sync.handle_open();
                 }
             }) as Box<dyn FnMut()>)
         }));
 
         let on_error = Rc::new(RefCell::new({
-            let websocket = websocket.clone();
+            let sync = weak_sync.clone();
             Closure::wrap(Box::new(move || {
-                let _ = websocket
-                    .borrow()
-                    .close_with_code_and_reason(1001, "WEBSOCKET ERROR");
+                if let Some(sync) = sync.upgrade() {
+                    sync.transport().close(1001, "WEBSOCKET ERROR");
+                }
             }) as Box<dyn FnMut()>)
         }));
 
@@ -300,111 +501,143 @@ impl AwarenessWebsocketSync {
             Box::new(|_: web_sys::CloseEvent| {}) as Box<dyn FnMut(web_sys::CloseEvent)>,
         )));
 
-        on_close.replace({
+        let reconnect: Rc<RefCell<Reconnect>> = Rc::new(RefCell::new(Closure::new(|| {})));
+
+        let schedule_reconnect = {
+            let sync = weak_sync.clone();
+            let reconnect = reconnect.clone();
+            let reconnect_handle = reconnect_handle.clone();
+            let backoff = backoff.clone();
+            move || {
+                if let Some(sync) = sync.upgrade() {
+                    sync.set_connection_state(ConnectionState::Reconnecting);
+                }
+                let delay = backoff.borrow_mut().next_delay_millis(Math::random());
+                reconnect_handle.set(
+                    window()
+                        .expect("EXPECTED WINDOW")
+                        .set_timeout_with_callback_and_timeout_and_arguments_0(
+                            reconnect.borrow().as_ref().unchecked_ref(),
+                            delay,
+                        )
+                        .ok(),
+                );
+            }
+        };
+
+        reconnect.replace({
             let on_open = on_open.clone();
             let on_close = on_close.clone();
             let on_error = on_error.clone();
             let on_message = on_message.clone();
+            let reconnect_handle = reconnect_handle.clone();
+            let schedule_reconnect = schedule_reconnect.clone();
 
-            let client_id = awareness.borrow().client_id();
-            let websocket = websocket.clone();
+            let sync = weak_sync.clone();
             let connect_path = connect_path.to_string();
-            Closure::wrap(Box::new(move |message: web_sys::CloseEvent| {
-                // Reopen websocket connection if not cleanly closed
-                if message.code() == 1001 {
-                    // TODO: Add some sort of backoff mechanism to prevent
-                    // outages causing bad things to happen
-                    if let Ok(new_websocket) = create_websocket_connection(&connect_path, client_id)
-                    {
-                        websocket.replace(new_websocket);
+            Closure::new(move || {
+                reconnect_handle.set(None);
+                let Some(sync) = sync.upgrade() else {
+                    return;
+                };
+                if sync.connection_state() == ConnectionState::Closed {
+                    return;
+                }
+                match create_websocket_connection(&connect_path, client_id) {
+                    Ok(new_websocket) => {
+                        sync.set_connection_state(ConnectionState::Connecting);
+                        sync.transport().replace(new_websocket);
                         install_listeners(
-                            &websocket.borrow(),
+                            &sync.transport().websocket(),
                             &on_open,
                             &on_error,
                             &on_close,
                             &on_message,
                         );
                     }
+                    Err(_) => schedule_reconnect(),
+                }
+            })
+        });
+
+        on_close.replace({
+            let sync = weak_sync.clone();
+            Closure::wrap(Box::new(move |message: web_sys::CloseEvent| {
+                // Reopen the websocket connection with a backoff, unless it was closed cleanly
+                let closed = sync
+                    .upgrade()
+                    .map(|sync| sync.connection_state() == ConnectionState::Closed)
+                    .unwrap_or(true);
+                if message.code() != 1000 && !closed {
+                    schedule_reconnect();
                 }
             }) as Box<dyn FnMut(web_sys::CloseEvent)>)
         });
 
         install_listeners(
-            &websocket.borrow(),
+            &sync.transport().websocket(),
             &on_open,
             &on_error,
             &on_close,
             &on_message,
         );
 
-        let doc_subscription = {
-            awareness
-                .borrow()
-                .doc()
-                .observe_update_v1({
-                    let websocket = websocket.clone();
-                    move |_, update| {
-                        let ws = websocket.borrow();
-                        if ws.ready_state() == 1
-                            && ws
-                                .send_with_u8_array(
-                                    yrs::sync::Message::Sync(SyncMessage::Update(
-                                        update.update.to_vec(),
-                                    ))
-                                    .encode_v1()
-                                    .as_slice(),
-                                )
-                                .is_err()
-                        {
-                            let _ = ws.close_with_code_and_reason(1001, "FAILED TO SEND");
-                        }
-                    }
-                })
-                .expect("Successful Subscription")
-        };
-
-        let awareness_subscription = {
-            awareness.borrow().on_update({
-                let websocket = websocket.clone();
-                move |evt| {
-                    let ws = websocket.borrow();
-                    if ws.ready_state() == 1 {
-                        if let Some(update) = evt.awareness_update() {
-                            let message = Message::Awareness(update.clone());
-                            if ws
-                                .send_with_u8_array(message.encode_v1().as_slice())
-                                .is_err()
-                            {
-                                let _ = ws.close_with_code_and_reason(1001, "FAILED TO SEND");
-                            }
-                        }
-                    }
-                }
-            })
-        };
-
         Ok(AwarenessWebsocketSync {
-            websocket,
-            awareness: awareness.clone(),
+            sync,
             keep_alive: keep_alive.clone(),
             on_close: on_close.clone(),
             on_message: on_message.clone(),
             on_open: on_open.clone(),
+            reconnect,
+            reconnect_handle,
+            next_listener_handle: 0,
+            listeners,
             keep_alive_handle,
             on_error: on_error.clone(),
-            subscriptions: vec![doc_subscription, awareness_subscription],
         })
     }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.sync.connection_state()
+    }
+
+    pub fn is_hydrated(&self) -> bool {
+        self.sync.is_hydrated()
+    }
+
+    /// Registers a listener that is called with the new `ConnectionState` whenever it changes
+    pub fn subscribe(&mut self, listener: js_sys::Function) -> u32 {
+        let handle = self.next_listener_handle;
+        self.listeners.borrow_mut().push((handle, listener));
+        self.next_listener_handle += 1;
+        handle
+    }
+
+    pub fn unsubscribe(&mut self, handle: u32) {
+        let mut listeners = self.listeners.borrow_mut();
+        if let Some((idx, _)) = listeners
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| *k == handle)
+        {
+            let _ = listeners.remove(idx);
+        }
+    }
 }
 
 impl Drop for AwarenessWebsocketSync {
     fn drop(&mut self) {
+        self.sync.set_connection_state(ConnectionState::Closed);
         let keep_alive_handle = self.keep_alive_handle;
+        let reconnect_handle = self.reconnect_handle.take();
         window().inspect(move |window| {
             window.clear_interval_with_handle(keep_alive_handle);
+            if let Some(reconnect_handle) = reconnect_handle {
+                window.clear_timeout_with_handle(reconnect_handle);
+            }
         });
-        let ws = self.websocket.borrow();
-        if ws.ready_state() == 1 {
+        let ws = self.sync.transport().websocket();
+        if ws.ready_state() <= 1 {
             let _ = ws.close_with_code(1000);
         }
     }
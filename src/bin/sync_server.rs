@@ -0,0 +1,313 @@
+//! A y-sync server for local development, standing in for the hosted backend that
+//! `AwarenessWebsocketSync` connects to. Each connect path is a room, and every room's
+//! updates are persisted to a log in the data directory, which is periodically compacted.
+//!
+//! Configured with `WBBL_SYNC_ADDRESS` (default `127.0.0.1:4000`) and
+//! `WBBL_SYNC_DATA_DIR` (default `.wbbl-sync`).
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{unbounded_channel, UnboundedSender},
+    task::LocalSet,
+};
+use tokio_tungstenite::{
+    accept_hdr_async,
+    tungstenite::{
+        handshake::server::{Request, Response},
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Message,
+    },
+};
+use wbbl::sync_room::{
+    decode_update_log, encode_update_log_entry, PeerId, Recipient, RoomOutput, SyncRoom,
+};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:4000";
+const DEFAULT_DATA_DIR: &str = ".wbbl-sync";
+const COMPACTION_INTERVAL: Duration = Duration::from_secs(30);
+
+struct Room {
+    sync: SyncRoom,
+    connections: HashMap<PeerId, UnboundedSender<Message>>,
+    log_path: PathBuf,
+    // Entries appended to the log since it was last compacted
+    uncompacted_updates: usize,
+}
+
+impl Room {
+    fn load(log_path: PathBuf) -> io::Result<Room> {
+        let updates = match fs::read(&log_path) {
+            Ok(log) => decode_update_log(&log),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err),
+        };
+        let sync = SyncRoom::from_updates(&updates)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err)))?;
+        Ok(Room {
+            sync,
+            connections: HashMap::new(),
+            log_path,
+            uncompacted_updates: updates.len(),
+        })
+    }
+
+    fn route(&mut self, output: RoomOutput) -> io::Result<()> {
+        for message in output.messages {
+            match message.recipient {
+                Recipient::Peer(peer) => {
+                    if let Some(connection) = self.connections.get(&peer) {
+                        let _ = connection.send(Message::Binary(message.data));
+                    }
+                }
+                Recipient::AllExcept(sender) => {
+                    for (peer, connection) in self.connections.iter() {
+                        if *peer != sender {
+                            let _ = connection.send(Message::Binary(message.data.clone()));
+                        }
+                    }
+                }
+            }
+        }
+        if output.updates.is_empty() {
+            return Ok(());
+        }
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log_path)?;
+        for update in output.updates.iter() {
+            log.write_all(&encode_update_log_entry(update))?;
+        }
+        self.uncompacted_updates += output.updates.len();
+        Ok(())
+    }
+
+    /// Replaces the log with a single update holding the whole document. The new log is
+    /// written alongside and renamed over the old one, so a crash never loses the room
+    fn compact(&mut self) -> io::Result<()> {
+        if self.uncompacted_updates <= 1 {
+            return Ok(());
+        }
+        let compacted_path = self.log_path.with_extension("compacting");
+        fs::write(
+            &compacted_path,
+            encode_update_log_entry(&self.sync.encode_state()),
+        )?;
+        fs::rename(&compacted_path, &self.log_path)?;
+        self.uncompacted_updates = 1;
+        Ok(())
+    }
+}
+
+struct Server {
+    data_dir: PathBuf,
+    rooms: RefCell<HashMap<String, Room>>,
+    next_peer_id: Cell<PeerId>,
+}
+
+impl Server {
+    fn join(
+        &self,
+        room_name: &str,
+        client_id: u64,
+        connection: UnboundedSender<Message>,
+    ) -> io::Result<PeerId> {
+        let peer = self.next_peer_id.get();
+        self.next_peer_id.set(peer + 1);
+
+        let mut rooms = self.rooms.borrow_mut();
+        if !rooms.contains_key(room_name) {
+            let room = Room::load(log_path(&self.data_dir, room_name))?;
+            rooms.insert(room_name.to_owned(), room);
+        }
+        let room = rooms.get_mut(room_name).unwrap();
+        room.connections.insert(peer, connection);
+        let output = room.sync.join(peer, client_id);
+        room.route(output)?;
+        Ok(peer)
+    }
+
+    fn handle_message(&self, room_name: &str, peer: PeerId, message: &[u8]) -> Result<(), String> {
+        let mut rooms = self.rooms.borrow_mut();
+        let Some(room) = rooms.get_mut(room_name) else {
+            return Err("UNKNOWN ROOM".to_owned());
+        };
+        let output = room
+            .sync
+            .handle_message(peer, message)
+            .map_err(|err| err.close_reason().to_owned())?;
+        room.route(output).map_err(|err| {
+            eprintln!("Failed to persist {}: {}", room_name, err);
+            "FAILED TO PERSIST UPDATE".to_owned()
+        })
+    }
+
+    fn leave(&self, room_name: &str, peer: PeerId) {
+        let mut rooms = self.rooms.borrow_mut();
+        let Some(room) = rooms.get_mut(room_name) else {
+            return;
+        };
+        room.connections.remove(&peer);
+        let output = room.sync.leave(peer);
+        if let Err(err) = room.route(output) {
+            eprintln!("Failed to persist {}: {}", room_name, err);
+        }
+        if room.sync.is_empty() {
+            if let Err(err) = room.compact() {
+                eprintln!("Failed to compact {}: {}", room_name, err);
+            }
+            rooms.remove(room_name);
+        }
+    }
+
+    fn compact_rooms(&self) {
+        for (room_name, room) in self.rooms.borrow_mut().iter_mut() {
+            if let Err(err) = room.compact() {
+                eprintln!("Failed to compact {}: {}", room_name, err);
+            }
+        }
+    }
+}
+
+/// Splits a connect path, such as `/api/projects/{project}/graphs/{graph}/connect/{client_id}`,
+/// into the room and the client's awareness id
+fn parse_connect_path(path: &str) -> Option<(String, u64)> {
+    let (room_name, client_id) = path.rsplit_once('/')?;
+    if !room_name.starts_with("/api/") || !room_name.ends_with("/connect") {
+        return None;
+    }
+    Some((room_name.to_owned(), client_id.parse().ok()?))
+}
+
+/// Room names are escaped so that distinct rooms never share a file
+fn log_path(data_dir: &Path, room_name: &str) -> PathBuf {
+    let mut file_name = String::new();
+    for byte in room_name.trim_start_matches('/').bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' {
+            file_name.push(byte as char);
+        } else {
+            file_name.push_str(&format!("_{:02x}", byte));
+        }
+    }
+    data_dir.join(file_name).with_extension("ylog")
+}
+
+fn close_message(reason: String) -> Message {
+    Message::Close(Some(CloseFrame {
+        code: CloseCode::Away,
+        reason: reason.into(),
+    }))
+}
+
+// The handshake callback's error type is fixed by tungstenite
+#[allow(clippy::result_large_err)]
+async fn handle_connection(server: Rc<Server>, stream: TcpStream) {
+    let mut path = String::new();
+    let websocket = match accept_hdr_async(stream, |request: &Request, response: Response| {
+        path = request.uri().path().to_owned();
+        Ok(response)
+    })
+    .await
+    {
+        Ok(websocket) => websocket,
+        Err(err) => {
+            eprintln!("Failed to accept connection: {}", err);
+            return;
+        }
+    };
+    let (mut sink, mut stream) = websocket.split();
+
+    let Some((room_name, client_id)) = parse_connect_path(&path) else {
+        let _ = sink.send(close_message("UNKNOWN ROOM".to_owned())).await;
+        return;
+    };
+
+    let (sender, mut receiver) = unbounded_channel::<Message>();
+    tokio::task::spawn_local(async move {
+        while let Some(message) = receiver.recv().await {
+            let closing = message.is_close();
+            if sink.send(message).await.is_err() || closing {
+                break;
+            }
+        }
+    });
+
+    let peer = match server.join(&room_name, client_id, sender.clone()) {
+        Ok(peer) => peer,
+        Err(err) => {
+            eprintln!("Failed to open {}: {}", room_name, err);
+            let _ = sender.send(close_message("FAILED TO OPEN ROOM".to_owned()));
+            return;
+        }
+    };
+
+    while let Some(Ok(message)) = stream.next().await {
+        match message {
+            Message::Text(text) if text == "PING" => {
+                let _ = sender.send(Message::Text("PONG".to_owned()));
+            }
+            Message::Binary(data) => {
+                if let Err(reason) = server.handle_message(&room_name, peer, &data) {
+                    let _ = sender.send(close_message(reason));
+                    break;
+                }
+            }
+            Message::Close(_) => break,
+            _ => {}
+        }
+    }
+    server.leave(&room_name, peer);
+}
+
+async fn serve() -> io::Result<()> {
+    let address = std::env::var("WBBL_SYNC_ADDRESS").unwrap_or(DEFAULT_ADDRESS.to_owned());
+    let data_dir =
+        PathBuf::from(std::env::var("WBBL_SYNC_DATA_DIR").unwrap_or(DEFAULT_DATA_DIR.to_owned()));
+    fs::create_dir_all(&data_dir)?;
+
+    let server = Rc::new(Server {
+        data_dir,
+        rooms: RefCell::new(HashMap::new()),
+        next_peer_id: Cell::new(0),
+    });
+
+    tokio::task::spawn_local({
+        let server = server.clone();
+        async move {
+            let mut interval = tokio::time::interval(COMPACTION_INTERVAL);
+            loop {
+                interval.tick().await;
+                server.compact_rooms();
+            }
+        }
+    });
+
+    let listener = TcpListener::bind(&address).await?;
+    println!(
+        "wbbl sync server listening on {}, persisting to {}",
+        address,
+        server.data_dir.display()
+    );
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::task::spawn_local(handle_connection(server.clone(), stream));
+    }
+}
+
+fn main() -> io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    LocalSet::new().block_on(&runtime, serve())
+}
@@ -3,15 +3,71 @@ use std::collections::{HashMap, HashSet};
 use crate::{
     compute_rasterizer::generate_compute_rasterizer,
     data_types::ComputationDomain::{self, ModelDependant},
-    graph_types::BranchedMultiGraph,
+    data_types::ConcreteDataType,
+    fragment_shader::make_graph_fragment_shader_module,
+    graph_functions::topologically_order_nodes,
+    graph_types::{BranchedMultiGraph, InputPortId, PortId},
     intermediate_compiler_types::Shader::*,
-    intermediate_compiler_types::{BaseSizeMultiplier, IntermediateOutput, Stage},
+    intermediate_compiler_types::{
+        BaseSizeMultiplier, CompilationError, IntermediateOutput, NodeDiagnostic, Stage,
+        VertexFragmentShader,
+    },
+    vertex_shader::make_vertex_shader_module,
 };
+use wgpu::naga::valid::{Capabilities, ValidationFlags, Validator};
+
+fn make_vertex_fragment_stage(
+    branched_multi_graph: &BranchedMultiGraph,
+    computation_domains: &HashMap<u128, HashSet<ComputationDomain>>,
+    concrete_types: &HashMap<PortId, ConcreteDataType>,
+) -> Result<Stage, CompilationError> {
+    let graph = &branched_multi_graph.graph;
+    let output_node = graph
+        .nodes
+        .get(&graph.id)
+        .ok_or(CompilationError::MissingNode(graph.id))?;
+    let output_port: InputPortId = output_node
+        .input_ports_ids()
+        .first()
+        .cloned()
+        .ok_or(CompilationError::MissingNode(graph.id))?;
+
+    let mut included_nodes: HashSet<u128> = HashSet::from([graph.id]);
+    if let Some(subgraph) = branched_multi_graph.subgraphs.get(&graph.id) {
+        included_nodes.extend(subgraph.nodes.iter());
+        for branch in subgraph.branches.values() {
+            included_nodes.extend(branch.iter());
+        }
+    }
+    let node_ordering: Vec<u128> = topologically_order_nodes(graph)
+        .into_iter()
+        .filter(|n| included_nodes.contains(n))
+        .collect();
+
+    let (fragment, fragment_span_origins) =
+        make_graph_fragment_shader_module(graph, &node_ordering, concrete_types, &output_port)?;
+
+    Ok(Stage {
+        id: graph.id,
+        shader: VertexFragment(VertexFragmentShader {
+            vertex: make_vertex_shader_module(),
+            fragment,
+            fragment_span_origins,
+        }),
+        domain: computation_domains
+            .get(&graph.id)
+            .cloned()
+            .unwrap_or_default(),
+        dependencies: vec![],
+        dependants: HashSet::new(),
+    })
+}
 
 pub fn compile_to_naga_ir(
     branched_multi_graph: &BranchedMultiGraph,
     computation_domains: &HashMap<u128, HashSet<ComputationDomain>>,
-) -> IntermediateOutput {
+    concrete_types: &HashMap<PortId, ConcreteDataType>,
+) -> Result<IntermediateOutput, CompilationError> {
     let mut output: Vec<Stage> = vec![];
     let empty_domain: HashSet<ComputationDomain> = HashSet::new();
     let model_dependant: ComputationDomain = ModelDependant;
@@ -21,7 +77,8 @@ pub fn compile_to_naga_ir(
         .filter(|subgraph| {
             let domains = computation_domains.get(*subgraph).unwrap_or(&empty_domain);
             domains.contains(&model_dependant)
-        }).copied()
+        })
+        .copied()
         .collect();
     if !model_dependent_subgraphs.is_empty() {
         let compute_rasterizer = generate_compute_rasterizer(BaseSizeMultiplier(2.0), true);
@@ -36,5 +93,45 @@ pub fn compile_to_naga_ir(
         // TODO: Patch up dependencies
         output.push(rasterizer_stage);
     }
-    IntermediateOutput(output)
+    output.push(make_vertex_fragment_stage(
+        branched_multi_graph,
+        computation_domains,
+        concrete_types,
+    )?);
+    Ok(IntermediateOutput(output))
+}
+
+/// Validates the shaders generated from the graph for a stage. Each error is reported on the
+/// nodes its spans point at, or on the stage's root node when they don't point into the graph
+pub fn validate_stage(stage: &Stage) -> Result<(), Vec<NodeDiagnostic>> {
+    let VertexFragment(shader) = &stage.shader else {
+        return Ok(());
+    };
+    let validation =
+        Validator::new(ValidationFlags::all(), Capabilities::default()).validate(&shader.fragment);
+    let Err(err) = validation else {
+        return Ok(());
+    };
+    let message = err.as_inner().to_string();
+    let mut diagnostics: Vec<NodeDiagnostic> = vec![];
+    for (span, _) in err.spans() {
+        if let Some(port) = shader.fragment_span_origins.get(*span) {
+            let diagnostic = NodeDiagnostic {
+                node_id: port.node_id(),
+                port: Some(port.clone()),
+                message: message.clone(),
+            };
+            if !diagnostics.contains(&diagnostic) {
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+    if diagnostics.is_empty() {
+        diagnostics.push(NodeDiagnostic {
+            node_id: stage.id,
+            port: None,
+            message,
+        });
+    }
+    Err(diagnostics)
 }
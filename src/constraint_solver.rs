@@ -1,11 +1,13 @@
 use crate::constraint_solver::ConstraintSolverError::ContradictionFound;
 use crate::constraint_solver_constraints::{
     Constraint, ConstraintApplicationResult, HasCompositeSize, HasDimensionality, HasRanking,
+    HasScalarBroadcast,
 };
 use crate::data_types::{AbstractDataType, ConcreteDataType};
+use crate::graph_transfer_types::{string_to_uuid_set, uuid_to_string_set};
 use crate::graph_types::PortId;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::rc::Rc;
@@ -13,6 +15,31 @@ use std::rc::Rc;
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ConstraintSolverError {
     ContradictionFound,
+    ConflictFound(TypeConflict),
+}
+
+/// A minimal set of constraints which can't be satisfied together, along with the ports they
+/// touch and the edges they were derived from. Removing any one of the constraints resolves the
+/// contradiction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeConflict {
+    pub ports: HashSet<PortId>,
+    #[serde(
+        serialize_with = "uuid_to_string_set",
+        deserialize_with = "string_to_uuid_set"
+    )]
+    pub edges: HashSet<u128>,
+    pub constraints: Vec<Constraint>,
+}
+
+pub fn group_constraints_by_port(constraints: &[Constraint]) -> HashMap<PortId, Vec<Constraint>> {
+    let mut result: HashMap<PortId, Vec<Constraint>> = HashMap::new();
+    for constraint in constraints {
+        for port in constraint.get_affected_ports() {
+            result.entry(port).or_default().push(constraint.clone());
+        }
+    }
+    result
 }
 
 fn propagate_constraints<Value>(
@@ -22,7 +49,8 @@ fn propagate_constraints<Value>(
     constraints: &HashMap<PortId, Vec<Constraint>>,
 ) -> Result<(), ConstraintSolverError>
 where
-    Value: Copy + Hash + Eq + HasCompositeSize + HasDimensionality + HasRanking,
+    Value:
+        Copy + Hash + Eq + HasCompositeSize + HasDimensionality + HasRanking + HasScalarBroadcast,
 {
     let mut queue: VecDeque<PortId> = VecDeque::new();
     queue.push_front(start_port);
@@ -48,14 +76,21 @@ where
 
 fn assign_types<Value>(
     topologically_ordered_ports: &[PortId],
+    assignments: im::HashMap<PortId, Value>,
     domains: im::HashMap<PortId, Rc<Vec<Value>>>,
     constraints: &HashMap<PortId, Vec<Constraint>>,
 ) -> Result<im::HashMap<PortId, Value>, ConstraintSolverError>
 where
-    Value: Debug + Copy + Hash + Eq + HasCompositeSize + HasDimensionality + HasRanking,
+    Value: Debug
+        + Copy
+        + Hash
+        + Eq
+        + HasCompositeSize
+        + HasDimensionality
+        + HasRanking
+        + HasScalarBroadcast,
 {
-    let mut assignments_stack: VecDeque<im::HashMap<PortId, Value>> =
-        VecDeque::from([im::HashMap::<PortId, Value>::new()]);
+    let mut assignments_stack: VecDeque<im::HashMap<PortId, Value>> = VecDeque::from([assignments]);
     let mut domains_stack: VecDeque<im::HashMap<PortId, Rc<Vec<Value>>>> =
         VecDeque::from([domains]);
 
@@ -108,19 +143,194 @@ where
     }
 }
 
-pub fn assign_concrete_types(
+// Solves each group of ports connected through constraints on its own. Otherwise a contradiction
+// in one group would backtrack through every choice made for the ports of the groups before it
+fn assign_types_by_component<Value>(
     topologically_ordered_ports: &[PortId],
-    port_types: &HashMap<PortId, AbstractDataType>,
+    assignments: im::HashMap<PortId, Value>,
+    domains: im::HashMap<PortId, Rc<Vec<Value>>>,
     constraints: &HashMap<PortId, Vec<Constraint>>,
-) -> Result<HashMap<PortId, ConcreteDataType>, ConstraintSolverError> {
-    let domains: im::HashMap<PortId, Rc<Vec<ConcreteDataType>>> = port_types
+) -> Result<im::HashMap<PortId, Value>, ConstraintSolverError>
+where
+    Value: Debug
+        + Copy
+        + Hash
+        + Eq
+        + HasCompositeSize
+        + HasDimensionality
+        + HasRanking
+        + HasScalarBroadcast,
+{
+    let port_order: HashMap<&PortId, usize> = topologically_ordered_ports
         .iter()
-        .map(|t| (t.0.clone(), Rc::new(t.1.get_concrete_domain())))
+        .enumerate()
+        .map(|(i, p)| (p, i))
         .collect();
-    let result: HashMap<PortId, ConcreteDataType> =
-        assign_types(topologically_ordered_ports, domains, constraints)?
+    let mut result = assignments.clone();
+    let mut visited: HashSet<PortId> = HashSet::new();
+    for start_port in topologically_ordered_ports {
+        if !visited.insert(start_port.clone()) {
+            continue;
+        }
+        let mut component: Vec<usize> = Vec::new();
+        let mut queue: VecDeque<PortId> = VecDeque::from([start_port.clone()]);
+        while let Some(port) = queue.pop_front() {
+            if let Some(i) = port_order.get(&port) {
+                component.push(*i);
+            }
+            for constraint in constraints.get(&port).into_iter().flatten() {
+                for other_port in constraint.get_affected_ports() {
+                    if visited.insert(other_port.clone()) {
+                        queue.push_back(other_port);
+                    }
+                }
+            }
+        }
+        component.sort();
+        let component_ports: Vec<PortId> = component
             .into_iter()
+            .map(|i| topologically_ordered_ports[i].clone())
             .collect();
+        result.extend(assign_types(
+            &component_ports,
+            assignments.clone(),
+            domains.clone(),
+            constraints,
+        )?);
+    }
+    Ok(result)
+}
+
+// Groups constraints that share ports, directly or through other constraints, by their indices.
+// The groups can't affect one another, so each can be solved on its own
+fn connected_constraints(constraints: &[Constraint]) -> Vec<Vec<usize>> {
+    let mut constraints_by_port: HashMap<PortId, Vec<usize>> = HashMap::new();
+    for (i, constraint) in constraints.iter().enumerate() {
+        for port in constraint.get_affected_ports() {
+            constraints_by_port.entry(port).or_default().push(i);
+        }
+    }
+    let mut visited = vec![false; constraints.len()];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for i in 0..constraints.len() {
+        if visited[i] {
+            continue;
+        }
+        visited[i] = true;
+        let mut component: Vec<usize> = Vec::new();
+        let mut queue: VecDeque<usize> = VecDeque::from([i]);
+        while let Some(current) = queue.pop_front() {
+            component.push(current);
+            for port in constraints[current].get_affected_ports() {
+                for other in constraints_by_port.get(&port).into_iter().flatten() {
+                    if !visited[*other] {
+                        visited[*other] = true;
+                        queue.push_back(*other);
+                    }
+                }
+            }
+        }
+        component.sort();
+        components.push(component);
+    }
+    components
+}
+
+// Finds the connected group of constraints which can't be solved, then shrinks it with a deletion
+// filter: drop each constraint in turn and keep it out if the remaining constraints are still
+// contradictory. What is left is minimal, as every remaining constraint is needed for the
+// contradiction. Only the failing group is searched, so the cost doesn't grow with the rest of the
+// graph
+fn find_conflict_set<Value>(
+    topologically_ordered_ports: &[PortId],
+    domains: im::HashMap<PortId, Rc<Vec<Value>>>,
+    constraints: &[Constraint],
+) -> Vec<usize>
+where
+    Value: Debug
+        + Copy
+        + Hash
+        + Eq
+        + HasCompositeSize
+        + HasDimensionality
+        + HasRanking
+        + HasScalarBroadcast,
+{
+    for component in connected_constraints(constraints) {
+        let component_ports: HashSet<PortId> = component
+            .iter()
+            .flat_map(|i| constraints[*i].get_affected_ports())
+            .collect();
+        let ordered_ports: Vec<PortId> = topologically_ordered_ports
+            .iter()
+            .filter(|p| component_ports.contains(p))
+            .cloned()
+            .collect();
+        let is_satisfiable = |conflict_set: &[usize]| {
+            let remaining: Vec<Constraint> = conflict_set
+                .iter()
+                .map(|i| constraints[*i].clone())
+                .collect();
+            assign_types_by_component(
+                &ordered_ports,
+                im::HashMap::new(),
+                domains.clone(),
+                &group_constraints_by_port(&remaining),
+            )
+            .is_ok()
+        };
+        if is_satisfiable(&component) {
+            continue;
+        }
+
+        let mut conflict_set = component;
+        let mut i = 0;
+        while i < conflict_set.len() {
+            let mut remaining = conflict_set.clone();
+            remaining.remove(i);
+            if is_satisfiable(&remaining) {
+                i += 1;
+            } else {
+                conflict_set = remaining;
+            }
+        }
+        return conflict_set;
+    }
+    Vec::new()
+}
+
+fn concrete_domains(
+    port_types: &HashMap<PortId, AbstractDataType>,
+) -> im::HashMap<PortId, Rc<Vec<ConcreteDataType>>> {
+    port_types
+        .iter()
+        .map(|t| (t.0.clone(), Rc::new(t.1.get_concrete_domain())))
+        .collect()
+}
+
+fn abstract_domains(
+    port_types: &HashMap<PortId, AbstractDataType>,
+) -> im::HashMap<PortId, Rc<Vec<AbstractDataType>>> {
+    port_types
+        .iter()
+        .map(|t| (t.0.clone(), Rc::new(t.1.get_abstract_domain())))
+        .collect()
+}
+
+pub fn assign_concrete_types(
+    topologically_ordered_ports: &[PortId],
+    port_types: &HashMap<PortId, AbstractDataType>,
+    constraints: &HashMap<PortId, Vec<Constraint>>,
+) -> Result<HashMap<PortId, ConcreteDataType>, ConstraintSolverError> {
+    let domains = concrete_domains(port_types);
+    let result: HashMap<PortId, ConcreteDataType> = assign_types_by_component(
+        topologically_ordered_ports,
+        im::HashMap::new(),
+        domains,
+        constraints,
+    )?
+    .into_iter()
+    .collect();
 
     Ok(result)
 }
@@ -130,13 +340,73 @@ pub fn narrow_abstract_types(
     port_types: &HashMap<PortId, AbstractDataType>,
     constraints: &HashMap<PortId, Vec<Constraint>>,
 ) -> Result<HashMap<PortId, AbstractDataType>, ConstraintSolverError> {
-    let domains: im::HashMap<PortId, Rc<Vec<AbstractDataType>>> = port_types
-        .iter()
-        .map(|t| (t.0.clone(), Rc::new(t.1.get_abstract_domain())))
-        .collect();
-    let result = assign_types(topologically_ordered_ports, domains, constraints)?
-        .into_iter()
-        .collect();
+    let domains = abstract_domains(port_types);
+    let result = assign_types_by_component(
+        topologically_ordered_ports,
+        im::HashMap::new(),
+        domains,
+        constraints,
+    )?
+    .into_iter()
+    .collect();
 
     Ok(result)
 }
+
+/// Narrows only `topologically_ordered_ports`, holding every other port at the type it was
+/// previously narrowed to
+pub fn renarrow_abstract_types(
+    topologically_ordered_ports: &[PortId],
+    port_types: &HashMap<PortId, AbstractDataType>,
+    previous_types: &HashMap<PortId, AbstractDataType>,
+    constraints: &HashMap<PortId, Vec<Constraint>>,
+) -> Result<HashMap<PortId, AbstractDataType>, ConstraintSolverError> {
+    let renarrowed_ports: HashSet<&PortId> = topologically_ordered_ports.iter().collect();
+    let mut assignments: im::HashMap<PortId, AbstractDataType> = im::HashMap::new();
+    let mut domains = abstract_domains(port_types);
+    for port in port_types.keys() {
+        if renarrowed_ports.contains(port) {
+            continue;
+        }
+        if let Some(previous_type) = previous_types.get(port) {
+            assignments.insert(port.clone(), *previous_type);
+            domains.insert(port.clone(), Rc::new(vec![*previous_type]));
+        }
+    }
+    let result = assign_types_by_component(
+        topologically_ordered_ports,
+        assignments,
+        domains,
+        constraints,
+    )?
+    .into_iter()
+    .collect();
+
+    Ok(result)
+}
+
+/// Indices of a minimal subset of `constraints` for which no concrete types can be assigned
+pub fn find_concrete_type_conflict(
+    topologically_ordered_ports: &[PortId],
+    port_types: &HashMap<PortId, AbstractDataType>,
+    constraints: &[Constraint],
+) -> Vec<usize> {
+    find_conflict_set(
+        topologically_ordered_ports,
+        concrete_domains(port_types),
+        constraints,
+    )
+}
+
+/// Indices of a minimal subset of `constraints` for which the abstract types can't be narrowed
+pub fn find_abstract_type_conflict(
+    topologically_ordered_ports: &[PortId],
+    port_types: &HashMap<PortId, AbstractDataType>,
+    constraints: &[Constraint],
+) -> Vec<usize> {
+    find_conflict_set(
+        topologically_ordered_ports,
+        abstract_domains(port_types),
+        constraints,
+    )
+}
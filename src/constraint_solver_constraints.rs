@@ -1,5 +1,6 @@
 use crate::constraint_solver_constraints::Constraint::{
-    SameCompositeSize, SameDimensionality, SameTypes,
+    Broadcast, ExactCompositeSize, MinCompositeSize, SameCompositeSize, SameDimensionality,
+    SameTypes, ScalarOrSameCompositeSize, SumCompositeSize,
 };
 use crate::constraint_solver_constraints::ConstraintApplicationResult::{
     Contradiction, Dirty, Unchanged,
@@ -7,6 +8,7 @@ use crate::constraint_solver_constraints::ConstraintApplicationResult::{
 use crate::data_types::{CompositeSize, Dimensionality};
 use crate::graph_types::PortId;
 use im::HashMap;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashSet, LinkedList};
 use std::hash::Hash;
 use std::rc::Rc;
@@ -29,24 +31,39 @@ pub trait HasRanking {
     fn get_rank(&self) -> i32;
 }
 
+pub trait HasScalarBroadcast {
+    /// Whether a value of this type can be used where `other` is expected, either by being the
+    /// same type or by being a scalar that is splatted across each of its components
+    fn broadcasts_to(&self, other: &Self) -> bool;
+}
+
 pub trait PortConstraint {
     fn get_affected_ports(&self) -> HashSet<PortId>;
-    fn apply<Value: Copy + Hash + Eq + HasCompositeSize + HasDimensionality + HasRanking>(
+    fn apply<
+        Value: Copy + Hash + Eq + HasCompositeSize + HasDimensionality + HasRanking + HasScalarBroadcast,
+    >(
         &self,
         assignments: &mut HashMap<PortId, Value>,
         domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
     ) -> ConstraintApplicationResult;
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Constraint {
     SameTypes(SameTypesConstraint),
     SameDimensionality(SameDimensionalityConstraint),
     SameCompositeSize(SameCompositeSizeConstraint),
+    SumCompositeSize(SumCompositeSizeConstraint),
+    MinCompositeSize(MinCompositeSizeConstraint),
+    ExactCompositeSize(ExactCompositeSizeConstraint),
+    ScalarOrSameCompositeSize(ScalarOrSameCompositeSizeConstraint),
+    Broadcast(BroadcastConstraint),
 }
 
 impl Constraint {
-    pub fn apply<Value: Copy + Hash + Eq + HasCompositeSize + HasDimensionality + HasRanking>(
+    pub fn apply<
+        Value: Copy + Hash + Eq + HasCompositeSize + HasDimensionality + HasRanking + HasScalarBroadcast,
+    >(
         &self,
         assignments: &mut HashMap<PortId, Value>,
         domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
@@ -55,6 +72,11 @@ impl Constraint {
             SameDimensionality(sd) => sd.apply(assignments, domains),
             SameTypes(st) => st.apply(assignments, domains),
             SameCompositeSize(scs) => scs.apply(assignments, domains),
+            SumCompositeSize(scs) => scs.apply(assignments, domains),
+            MinCompositeSize(mcs) => mcs.apply(assignments, domains),
+            ExactCompositeSize(ecs) => ecs.apply(assignments, domains),
+            ScalarOrSameCompositeSize(socs) => socs.apply(assignments, domains),
+            Broadcast(b) => b.apply(assignments, domains),
         }
     }
 
@@ -63,11 +85,16 @@ impl Constraint {
             SameDimensionality(sd) => sd.get_affected_ports(),
             SameTypes(st) => st.get_affected_ports(),
             SameCompositeSize(scs) => scs.get_affected_ports(),
+            SumCompositeSize(scs) => scs.get_affected_ports(),
+            MinCompositeSize(mcs) => mcs.get_affected_ports(),
+            ExactCompositeSize(ecs) => ecs.get_affected_ports(),
+            ScalarOrSameCompositeSize(socs) => socs.get_affected_ports(),
+            Broadcast(b) => b.get_affected_ports(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SameTypesConstraint {
     pub ports: HashSet<PortId>,
 }
@@ -157,7 +184,7 @@ impl PortConstraint for SameTypesConstraint {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SameDimensionalityConstraint {
     ports: HashSet<PortId>,
 }
@@ -265,7 +292,7 @@ impl PortConstraint for SameDimensionalityConstraint {
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct SameCompositeSizeConstraint {
     ports: HashSet<PortId>,
 }
@@ -372,3 +399,376 @@ impl PortConstraint for SameCompositeSizeConstraint {
         }
     }
 }
+
+const COMPOSITE_SIZES: [CompositeSize; 4] = [
+    CompositeSize::S1,
+    CompositeSize::S2,
+    CompositeSize::S3,
+    CompositeSize::S4,
+];
+
+fn component_count(composite_size: CompositeSize) -> usize {
+    composite_size as usize + 1
+}
+
+// Values without a known composite size, such as abstract types, could still take on any size
+fn possible_component_counts<Value: HasCompositeSize>(value: &Value) -> Vec<usize> {
+    match value.get_composite_size() {
+        Some(c) => vec![component_count(c)],
+        None => COMPOSITE_SIZES
+            .iter()
+            .map(|c| component_count(*c))
+            .collect(),
+    }
+}
+
+fn possible_port_component_counts<Value: HasCompositeSize>(
+    port: &PortId,
+    domains: &HashMap<PortId, Rc<Vec<Value>>>,
+) -> HashSet<usize> {
+    domains
+        .get(port)
+        .map(|d| d.iter().flat_map(possible_component_counts).collect())
+        .unwrap_or_default()
+}
+
+fn narrow_domain<Value: Copy + Hash + Eq, F: Fn(&Value) -> bool>(
+    port: &PortId,
+    predicate: F,
+    assignments: &mut HashMap<PortId, Value>,
+    domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
+    changed: &mut LinkedList<PortId>,
+) -> Result<(), ()> {
+    let empty_vec: Rc<Vec<Value>> = Rc::new(Vec::new());
+    let old_domains = domains.get(port).unwrap_or(&empty_vec);
+    let new_domains: Rc<Vec<Value>> = Rc::new(
+        old_domains
+            .iter()
+            .filter(|v| predicate(v))
+            .copied()
+            .collect(),
+    );
+    let new_domains_count = new_domains.len();
+    if new_domains_count == 0 {
+        return Err(());
+    }
+    if new_domains_count != old_domains.len() {
+        changed.push_back(port.clone());
+        if new_domains_count == 1 {
+            assignments.insert(port.clone(), *new_domains.first().unwrap());
+        }
+        domains.insert(port.clone(), new_domains);
+    }
+    Ok(())
+}
+
+/// The output has as many components as all of the inputs combined
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SumCompositeSizeConstraint {
+    pub inputs: Vec<PortId>,
+    pub output: PortId,
+}
+
+impl SumCompositeSizeConstraint {
+    fn possible_sums(counts: &[HashSet<usize>]) -> HashSet<usize> {
+        counts.iter().fold(HashSet::from([0]), |sums, counts| {
+            sums.iter()
+                .flat_map(|s| counts.iter().map(move |c| s + c))
+                .filter(|s| *s <= COMPOSITE_SIZES.len())
+                .collect()
+        })
+    }
+}
+
+impl PortConstraint for SumCompositeSizeConstraint {
+    fn get_affected_ports(&self) -> HashSet<PortId> {
+        let mut ports: HashSet<PortId> = self.inputs.iter().cloned().collect();
+        ports.insert(self.output.clone());
+        ports
+    }
+
+    fn apply<Value: Copy + Hash + Eq + HasCompositeSize>(
+        &self,
+        assignments: &mut HashMap<PortId, Value>,
+        domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
+    ) -> ConstraintApplicationResult {
+        let mut changed: LinkedList<PortId> = LinkedList::new();
+
+        let input_counts: Vec<HashSet<usize>> = self
+            .inputs
+            .iter()
+            .map(|p| possible_port_component_counts(p, domains))
+            .collect();
+        let sums = Self::possible_sums(&input_counts);
+        if narrow_domain(
+            &self.output,
+            |v| {
+                possible_component_counts(v)
+                    .iter()
+                    .any(|c| sums.contains(c))
+            },
+            assignments,
+            domains,
+            &mut changed,
+        )
+        .is_err()
+        {
+            return Contradiction;
+        }
+
+        let output_counts = possible_port_component_counts(&self.output, domains);
+        for (i, input) in self.inputs.iter().enumerate() {
+            let other_counts: Vec<HashSet<usize>> = self
+                .inputs
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, p)| possible_port_component_counts(p, domains))
+                .collect();
+            let other_sums = Self::possible_sums(&other_counts);
+            if narrow_domain(
+                input,
+                |v| {
+                    possible_component_counts(v).iter().any(|c| {
+                        other_sums
+                            .iter()
+                            .any(|other| output_counts.contains(&(c + other)))
+                    })
+                },
+                assignments,
+                domains,
+                &mut changed,
+            )
+            .is_err()
+            {
+                return Contradiction;
+            }
+        }
+
+        if !changed.is_empty() {
+            return Dirty(changed);
+        }
+        Unchanged
+    }
+}
+
+/// The port has at least the given number of components
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MinCompositeSizeConstraint {
+    pub port: PortId,
+    pub composite_size: CompositeSize,
+}
+
+impl PortConstraint for MinCompositeSizeConstraint {
+    fn get_affected_ports(&self) -> HashSet<PortId> {
+        HashSet::from([self.port.clone()])
+    }
+
+    fn apply<Value: Copy + Hash + Eq + HasCompositeSize>(
+        &self,
+        assignments: &mut HashMap<PortId, Value>,
+        domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
+    ) -> ConstraintApplicationResult {
+        let mut changed: LinkedList<PortId> = LinkedList::new();
+        let min_count = component_count(self.composite_size);
+        if narrow_domain(
+            &self.port,
+            |v| possible_component_counts(v).iter().any(|c| *c >= min_count),
+            assignments,
+            domains,
+            &mut changed,
+        )
+        .is_err()
+        {
+            return Contradiction;
+        }
+        if !changed.is_empty() {
+            return Dirty(changed);
+        }
+        Unchanged
+    }
+}
+
+/// The port has exactly the given number of components, as with the output of a swizzle
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ExactCompositeSizeConstraint {
+    pub port: PortId,
+    pub composite_size: CompositeSize,
+}
+
+impl PortConstraint for ExactCompositeSizeConstraint {
+    fn get_affected_ports(&self) -> HashSet<PortId> {
+        HashSet::from([self.port.clone()])
+    }
+
+    fn apply<Value: Copy + Hash + Eq + HasCompositeSize>(
+        &self,
+        assignments: &mut HashMap<PortId, Value>,
+        domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
+    ) -> ConstraintApplicationResult {
+        let mut changed: LinkedList<PortId> = LinkedList::new();
+        if narrow_domain(
+            &self.port,
+            |v| v.get_composite_size() == Some(self.composite_size),
+            assignments,
+            domains,
+            &mut changed,
+        )
+        .is_err()
+        {
+            return Contradiction;
+        }
+        if !changed.is_empty() {
+            return Dirty(changed);
+        }
+        Unchanged
+    }
+}
+
+/// The port is either a scalar or has the same number of components as the reference port, as
+/// with the interpolant of a mix
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ScalarOrSameCompositeSizeConstraint {
+    pub port: PortId,
+    pub reference: PortId,
+}
+
+impl PortConstraint for ScalarOrSameCompositeSizeConstraint {
+    fn get_affected_ports(&self) -> HashSet<PortId> {
+        HashSet::from([self.port.clone(), self.reference.clone()])
+    }
+
+    fn apply<Value: Copy + Hash + Eq + HasCompositeSize>(
+        &self,
+        assignments: &mut HashMap<PortId, Value>,
+        domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
+    ) -> ConstraintApplicationResult {
+        let mut changed: LinkedList<PortId> = LinkedList::new();
+
+        let reference_counts = possible_port_component_counts(&self.reference, domains);
+        if narrow_domain(
+            &self.port,
+            |v| {
+                possible_component_counts(v)
+                    .iter()
+                    .any(|c| *c == 1 || reference_counts.contains(c))
+            },
+            assignments,
+            domains,
+            &mut changed,
+        )
+        .is_err()
+        {
+            return Contradiction;
+        }
+
+        // Only once the port can't be a scalar does it constrain the reference
+        let port_counts = possible_port_component_counts(&self.port, domains);
+        if !port_counts.contains(&1)
+            && narrow_domain(
+                &self.reference,
+                |v| {
+                    possible_component_counts(v)
+                        .iter()
+                        .any(|c| port_counts.contains(c))
+                },
+                assignments,
+                domains,
+                &mut changed,
+            )
+            .is_err()
+        {
+            return Contradiction;
+        }
+
+        if !changed.is_empty() {
+            return Dirty(changed);
+        }
+        Unchanged
+    }
+}
+
+/// Each input either has the output's type or is a scalar broadcast to it, and the output takes
+/// the type of the largest input
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastConstraint {
+    pub inputs: Vec<PortId>,
+    pub output: PortId,
+}
+
+impl PortConstraint for BroadcastConstraint {
+    fn get_affected_ports(&self) -> HashSet<PortId> {
+        let mut ports: HashSet<PortId> = self.inputs.iter().cloned().collect();
+        ports.insert(self.output.clone());
+        ports
+    }
+
+    fn apply<Value: Copy + Hash + Eq + HasScalarBroadcast>(
+        &self,
+        assignments: &mut HashMap<PortId, Value>,
+        domains: &mut HashMap<PortId, Rc<Vec<Value>>>,
+    ) -> ConstraintApplicationResult {
+        let mut changed: LinkedList<PortId> = LinkedList::new();
+        let empty_vec: Rc<Vec<Value>> = Rc::new(Vec::new());
+
+        let input_domains: Vec<Rc<Vec<Value>>> = self
+            .inputs
+            .iter()
+            .map(|p| domains.get(p).unwrap_or(&empty_vec).clone())
+            .collect();
+        if narrow_domain(
+            &self.output,
+            |o| {
+                input_domains
+                    .iter()
+                    .all(|d| d.iter().any(|v| v.broadcasts_to(o)))
+                    && input_domains.iter().any(|d| d.contains(o))
+            },
+            assignments,
+            domains,
+            &mut changed,
+        )
+        .is_err()
+        {
+            return Contradiction;
+        }
+
+        let output_domain = domains.get(&self.output).unwrap_or(&empty_vec).clone();
+        for (i, input) in self.inputs.iter().enumerate() {
+            // Unless another input can supply the output's type, this one has to
+            let others_can_match = |o: &Value| {
+                self.inputs
+                    .iter()
+                    .enumerate()
+                    .any(|(j, p)| j != i && domains.get(p).is_some_and(|d| d.contains(o)))
+            };
+            let allowed: HashSet<Value> = domains
+                .get(input)
+                .unwrap_or(&empty_vec)
+                .iter()
+                .filter(|v| {
+                    output_domain
+                        .iter()
+                        .any(|o| v.broadcasts_to(o) && (*v == o || others_can_match(o)))
+                })
+                .copied()
+                .collect();
+            if narrow_domain(
+                input,
+                |v| allowed.contains(v),
+                assignments,
+                domains,
+                &mut changed,
+            )
+            .is_err()
+            {
+                return Contradiction;
+            }
+        }
+
+        if !changed.is_empty() {
+            return Dirty(changed);
+        }
+        Unchanged
+    }
+}
@@ -108,6 +108,12 @@ impl HasRanking for ConcreteDataType {
     }
 }
 
+impl HasScalarBroadcast for ConcreteDataType {
+    fn broadcasts_to(&self, other: &Self) -> bool {
+        self == other || (*self == Float(S1) && matches!(other, Float(_)))
+    }
+}
+
 impl HasCompositeSize for AbstractDataType {
     fn get_composite_size(&self) -> Option<CompositeSize> {
         match self {
@@ -177,6 +183,21 @@ impl HasRanking for AbstractDataType {
     }
 }
 
+impl HasScalarBroadcast for AbstractDataType {
+    fn broadcasts_to(&self, other: &Self) -> bool {
+        self == other
+            || (*self == ConcreteType(Float(S1))
+                && matches!(
+                    other,
+                    Any | AnyVectorOrScalar
+                        | AnyNumber
+                        | AnyFloat
+                        | AnyFloat123
+                        | ConcreteType(Float(_))
+                ))
+    }
+}
+
 impl AbstractDataType {
     pub fn get_concrete_domain(&self) -> Vec<ConcreteDataType> {
         match self {
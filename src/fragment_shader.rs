@@ -0,0 +1,1221 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use glam::Vec3;
+use wgpu::naga::{
+    AddressSpace, Arena, BinaryOperator, Binding, EntryPoint, Expression, Function,
+    FunctionArgument, FunctionResult, GlobalVariable, Handle, Literal, MathFunction, Module, Range,
+    RelationalFunction, ResourceBinding, Scalar, ScalarKind, Span, Statement, StorageAccess,
+    SwizzleComponent, Type, TypeInner, UnaryOperator, VectorSize,
+};
+
+use crate::{
+    compiler_constants::{FRAME_BINDING, FRAME_GROUP},
+    data_types::{CompositeSize, ConcreteDataType},
+    graph_types::{
+        slab_inputs, BinaryOperation, BuiltIn, ConstantValue, Graph, InputPortId, NaryOperation,
+        Node, NodeType, OutputPortId, PortId, UnaryOperation, VectorComponent,
+    },
+    intermediate_compiler_types::{CompilationError, PreviewVisualisation, SpanOrigins},
+    shader_layouts::{frame, vertex_out},
+    utils::make_span,
+};
+
+struct FragmentShaderBuilder<'a> {
+    graph: &'a Graph,
+    concrete_types: &'a HashMap<PortId, ConcreteDataType>,
+    shader: Module,
+    function: Function,
+    vertex_out: Handle<Expression>,
+    frame: Option<Handle<Expression>>,
+    output_port_expressions: HashMap<OutputPortId, Handle<Expression>>,
+    span_origins: SpanOrigins,
+    // The port that expressions appended now are attributed to
+    origin: Option<PortId>,
+}
+
+// Fixed lighting environment used to shade slabs
+const LIGHT_DIRECTION: Vec3 = Vec3::new(0.4, 0.8, 0.6);
+const LIGHT_INTENSITY: f32 = 3.0;
+const AMBIENT_INTENSITY: f32 = 0.3;
+const DIELECTRIC_REFLECTANCE: f32 = 0.04;
+const MIN_ROUGHNESS: f32 = 0.045;
+const CLEARCOAT_ROUGHNESS: f32 = 0.1;
+
+// Number of checkerboard cells across the texture coordinates of boolean previews
+const PREVIEW_CHECKER_SCALE: f32 = 8.0;
+const PREVIEW_CHECKER_DARK: f32 = 0.25;
+const PREVIEW_CHECKER_LIGHT: f32 = 0.5;
+
+fn float_type(size: Option<VectorSize>) -> Type {
+    let scalar = Scalar {
+        kind: ScalarKind::Float,
+        width: 4,
+    };
+    Type {
+        name: None,
+        inner: match size {
+            Some(size) => TypeInner::Vector { size, scalar },
+            None => TypeInner::Scalar(scalar),
+        },
+    }
+}
+
+fn vector_size(composite_size: CompositeSize) -> Option<VectorSize> {
+    match composite_size {
+        CompositeSize::S1 => None,
+        CompositeSize::S2 => Some(VectorSize::Bi),
+        CompositeSize::S3 => Some(VectorSize::Tri),
+        CompositeSize::S4 => Some(VectorSize::Quad),
+    }
+}
+
+impl<'a> FragmentShaderBuilder<'a> {
+    fn new(graph: &'a Graph, concrete_types: &'a HashMap<PortId, ConcreteDataType>) -> Self {
+        let mut shader: Module = Default::default();
+        let type_float32_2 = shader
+            .types
+            .insert(float_type(Some(VectorSize::Bi)), make_span(line!()));
+        let type_float32_3 = shader
+            .types
+            .insert(float_type(Some(VectorSize::Tri)), make_span(line!()));
+        let type_float32_4 = shader
+            .types
+            .insert(float_type(Some(VectorSize::Quad)), make_span(line!()));
+        let type_vertex_out_data = shader.types.insert(
+            vertex_out::make_naga_type(type_float32_4, type_float32_3, type_float32_2),
+            make_span(line!()),
+        );
+
+        let mut function = Function {
+            name: Some("fragmentMain".to_owned()),
+            arguments: vec![FunctionArgument {
+                name: Some("vertex_out".to_owned()),
+                ty: type_vertex_out_data,
+                binding: None,
+            }],
+            result: Some(FunctionResult {
+                ty: type_float32_4,
+                binding: Some(Binding::Location {
+                    location: 0,
+                    second_blend_source: false,
+                    interpolation: None,
+                    sampling: None,
+                }),
+            }),
+            local_variables: Arena::new(),
+            expressions: Arena::new(),
+            named_expressions: Default::default(),
+            body: Default::default(),
+        };
+
+        let vertex_out = function
+            .expressions
+            .append(Expression::FunctionArgument(0), make_span(line!()));
+
+        FragmentShaderBuilder {
+            graph,
+            concrete_types,
+            shader,
+            function,
+            vertex_out,
+            frame: None,
+            output_port_expressions: HashMap::new(),
+            span_origins: SpanOrigins::default(),
+            origin: None,
+        }
+    }
+
+    fn append(&mut self, expression: Expression, span: Span) -> Handle<Expression> {
+        let span = match &self.origin {
+            Some(port) => self.span_origins.span_for(port),
+            None => span,
+        };
+        let needs_emit = !expression.needs_pre_emit();
+        let handle = self.function.expressions.append(expression, span);
+        if needs_emit {
+            self.function.body.push(
+                Statement::Emit(Range::new_from_bounds(handle, handle)),
+                span,
+            );
+        }
+        handle
+    }
+
+    fn get_naga_type(&mut self, t: ConcreteDataType) -> Result<Handle<Type>, CompilationError> {
+        let naga_type = match t {
+            ConcreteDataType::Float(size) => float_type(vector_size(size)),
+            ConcreteDataType::Int => Type {
+                name: None,
+                inner: TypeInner::Scalar(Scalar {
+                    kind: ScalarKind::Sint,
+                    width: 4,
+                }),
+            },
+            ConcreteDataType::Bool => Type {
+                name: None,
+                inner: TypeInner::Scalar(Scalar {
+                    kind: ScalarKind::Bool,
+                    width: wgpu::naga::BOOL_WIDTH,
+                }),
+            },
+            // Materials are represented by the colour they resolve to
+            ConcreteDataType::SlabMaterial => float_type(Some(VectorSize::Quad)),
+            // Textures and fields are represented by their sample at the fragment's texture
+            // coordinate
+            ConcreteDataType::Texture(_, size) | ConcreteDataType::ProceduralField(_, size) => {
+                float_type(vector_size(size))
+            }
+        };
+        Ok(self.shader.types.insert(naga_type, make_span(line!())))
+    }
+
+    fn get_concrete_type(&self, port_id: PortId) -> Result<ConcreteDataType, CompilationError> {
+        self.concrete_types
+            .get(&port_id)
+            .copied()
+            .ok_or(CompilationError::MissingConcreteType(port_id))
+    }
+
+    fn get_connected_input_expression(
+        &self,
+        port_id: &InputPortId,
+    ) -> Result<Option<Handle<Expression>>, CompilationError> {
+        let incoming_edge = self
+            .graph
+            .input_ports
+            .get(port_id)
+            .and_then(|p| p.incoming_edge)
+            .and_then(|e| self.graph.edges.get(&e));
+        match incoming_edge {
+            Some(edge) => self
+                .output_port_expressions
+                .get(&edge.output_port)
+                .copied()
+                .map(Some)
+                .ok_or(CompilationError::MissingExpression(PortId::Output(
+                    edge.output_port.clone(),
+                ))),
+            None => Ok(None),
+        }
+    }
+
+    fn get_input_expression(
+        &mut self,
+        port_id: &InputPortId,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        match self.get_connected_input_expression(port_id)? {
+            Some(expression) => Ok(expression),
+            None => {
+                // Unconnected ports fall back to the zero value of their type
+                let t = self.get_concrete_type(PortId::Input(port_id.clone()))?;
+                let ty = self.get_naga_type(t)?;
+                let origin = self.origin.replace(PortId::Input(port_id.clone()));
+                let value = self.append(Expression::ZeroValue(ty), make_span(line!()));
+                self.origin = origin;
+                Ok(value)
+            }
+        }
+    }
+
+    fn get_input_expressions(
+        &mut self,
+        node: &Node,
+    ) -> Result<Vec<Handle<Expression>>, CompilationError> {
+        node.input_ports_ids()
+            .iter()
+            .map(|p| self.get_input_expression(p))
+            .collect()
+    }
+
+    fn set_output_expression(&mut self, node: &Node, index: u8, expression: Handle<Expression>) {
+        self.output_port_expressions.insert(
+            OutputPortId {
+                node_id: node.id,
+                port_index: index,
+            },
+            expression,
+        );
+    }
+
+    fn lower_node(&mut self, node: &Node) -> Result<(), CompilationError> {
+        match &node.node_type {
+            NodeType::Output | NodeType::Preview | NodeType::Frame => Ok(()),
+            NodeType::Slab => {
+                let material = self.lower_slab(node)?;
+                self.set_output_expression(node, 0, material);
+                Ok(())
+            }
+            NodeType::Junction => {
+                let inputs = self.get_input_expressions(node)?;
+                self.set_output_expression(node, 0, inputs[0]);
+                Ok(())
+            }
+            NodeType::BuiltIn(built_in) => {
+                let expression = self.lower_built_in(built_in);
+                self.set_output_expression(node, 0, expression);
+                Ok(())
+            }
+            NodeType::Constant(value) => {
+                let expression = self.lower_constant(value)?;
+                self.set_output_expression(node, 0, expression);
+                Ok(())
+            }
+            NodeType::Time => {
+                let time = self.get_frame_member(frame::TIME_INDEX)?;
+                for index in [
+                    frame::TIME_SECONDS_INDEX,
+                    frame::TIME_DELTA_INDEX,
+                    frame::TIME_SINE_INDEX,
+                ] {
+                    let expression = self.access_index(time, index);
+                    self.set_output_expression(node, index as u8, expression);
+                }
+                Ok(())
+            }
+            NodeType::Split => {
+                let components = self.lower_split(node)?;
+                for (index, component) in components.into_iter().enumerate() {
+                    self.set_output_expression(node, index as u8, component);
+                }
+                Ok(())
+            }
+            NodeType::Append => {
+                let expression = self.lower_append(node)?;
+                self.set_output_expression(node, 0, expression);
+                Ok(())
+            }
+            NodeType::Swizzle(pattern) => {
+                let expression = self.lower_swizzle(node, pattern)?;
+                self.set_output_expression(node, 0, expression);
+                Ok(())
+            }
+            NodeType::BinaryOperation(op) => {
+                let expression = self.lower_binary_operation(node, op)?;
+                self.set_output_expression(node, 0, expression);
+                Ok(())
+            }
+            NodeType::UnaryOperation(op) => {
+                let expression = self.lower_unary_operation(node, op)?;
+                self.set_output_expression(node, 0, expression);
+                Ok(())
+            }
+            NodeType::NaryOperation(op) => {
+                let expression = self.lower_nary_operation(node, op)?;
+                self.set_output_expression(node, 0, expression);
+                Ok(())
+            }
+        }
+    }
+
+    fn literal(&mut self, value: f32) -> Handle<Expression> {
+        self.append(Expression::Literal(Literal::F32(value)), make_span(line!()))
+    }
+
+    fn splat(&mut self, value: Handle<Expression>) -> Handle<Expression> {
+        self.append(
+            Expression::Splat {
+                size: VectorSize::Tri,
+                value,
+            },
+            make_span(line!()),
+        )
+    }
+
+    /// Splats a scalar float to the size of a vector float, leaving any other value untouched
+    fn broadcast(
+        &mut self,
+        value: Handle<Expression>,
+        value_type: ConcreteDataType,
+        target_type: ConcreteDataType,
+    ) -> Handle<Expression> {
+        match (value_type, target_type) {
+            (ConcreteDataType::Float(CompositeSize::S1), ConcreteDataType::Float(target_size)) => {
+                match vector_size(target_size) {
+                    Some(size) => {
+                        self.append(Expression::Splat { size, value }, make_span(line!()))
+                    }
+                    None => value,
+                }
+            }
+            _ => value,
+        }
+    }
+
+    fn binary(
+        &mut self,
+        op: BinaryOperator,
+        left: Handle<Expression>,
+        right: Handle<Expression>,
+    ) -> Handle<Expression> {
+        self.append(Expression::Binary { op, left, right }, make_span(line!()))
+    }
+
+    fn math(
+        &mut self,
+        fun: MathFunction,
+        arg: Handle<Expression>,
+        arg1: Option<Handle<Expression>>,
+        arg2: Option<Handle<Expression>>,
+    ) -> Handle<Expression> {
+        self.append(
+            Expression::Math {
+                fun,
+                arg,
+                arg1,
+                arg2,
+                arg3: None,
+            },
+            make_span(line!()),
+        )
+    }
+
+    fn get_frame(&mut self) -> Result<Handle<Expression>, CompilationError> {
+        if let Some(frame) = self.frame {
+            return Ok(frame);
+        }
+        let type_matrix_4 = self.shader.types.insert(
+            Type {
+                name: None,
+                inner: TypeInner::Matrix {
+                    columns: VectorSize::Quad,
+                    rows: VectorSize::Quad,
+                    scalar: Scalar {
+                        kind: ScalarKind::Float,
+                        width: 4,
+                    },
+                },
+            },
+            make_span(line!()),
+        );
+        let type_float32_2 = self.get_naga_type(ConcreteDataType::Float(CompositeSize::S2))?;
+        let type_float32_3 = self.get_naga_type(ConcreteDataType::Float(CompositeSize::S3))?;
+        let type_frame_data = self.shader.types.insert(
+            frame::make_naga_type(type_matrix_4, type_float32_2, type_float32_3),
+            make_span(line!()),
+        );
+        let global_variable_frame_data = self.shader.global_variables.append(
+            GlobalVariable {
+                name: Some("frame".to_owned()),
+                space: AddressSpace::Storage {
+                    access: StorageAccess::LOAD,
+                },
+                binding: Some(ResourceBinding {
+                    group: FRAME_GROUP,
+                    binding: FRAME_BINDING,
+                }),
+                ty: type_frame_data,
+                init: None,
+            },
+            make_span(line!()),
+        );
+        let frame_pointer = self.append(
+            Expression::GlobalVariable(global_variable_frame_data),
+            make_span(line!()),
+        );
+        let frame = self.append(
+            Expression::Load {
+                pointer: frame_pointer,
+            },
+            make_span(line!()),
+        );
+        self.function
+            .named_expressions
+            .insert(frame, "frame_data".to_owned());
+        self.frame = Some(frame);
+        Ok(frame)
+    }
+
+    fn get_frame_member(&mut self, index: u32) -> Result<Handle<Expression>, CompilationError> {
+        let frame = self.get_frame()?;
+        Ok(self.access_index(frame, index))
+    }
+
+    fn get_camera_position(&mut self) -> Result<Handle<Expression>, CompilationError> {
+        let view_matrix_inv = self.get_frame_member(frame::VIEW_MATRIX_INV_INDEX)?;
+        let translation = self.append(
+            Expression::AccessIndex {
+                base: view_matrix_inv,
+                index: 3,
+            },
+            make_span(line!()),
+        );
+        Ok(self.append(
+            Expression::Swizzle {
+                size: VectorSize::Tri,
+                vector: translation,
+                pattern: [
+                    SwizzleComponent::X,
+                    SwizzleComponent::Y,
+                    SwizzleComponent::Z,
+                    SwizzleComponent::W,
+                ],
+            },
+            make_span(line!()),
+        ))
+    }
+
+    fn get_slab_input(
+        &mut self,
+        node: &Node,
+        index: u8,
+        default: impl FnOnce(&mut Self) -> Handle<Expression>,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        let port_id = InputPortId {
+            node_id: node.id,
+            port_index: index,
+        };
+        Ok(match self.get_connected_input_expression(&port_id)? {
+            Some(expression) => expression,
+            None => default(self),
+        })
+    }
+
+    /// Trowbridge-Reitz GGX normal distribution
+    fn distribution_ggx(
+        &mut self,
+        n_dot_h: Handle<Expression>,
+        roughness: Handle<Expression>,
+    ) -> Handle<Expression> {
+        let a = self.binary(BinaryOperator::Multiply, roughness, roughness);
+        let a2 = self.binary(BinaryOperator::Multiply, a, a);
+        let one = self.literal(1.0);
+        let a2_minus_one = self.binary(BinaryOperator::Subtract, a2, one);
+        let n_dot_h2 = self.binary(BinaryOperator::Multiply, n_dot_h, n_dot_h);
+        let t = self.binary(BinaryOperator::Multiply, n_dot_h2, a2_minus_one);
+        let t = self.binary(BinaryOperator::Add, t, one);
+        let t2 = self.binary(BinaryOperator::Multiply, t, t);
+        let pi = self.literal(PI);
+        let denominator = self.binary(BinaryOperator::Multiply, pi, t2);
+        self.binary(BinaryOperator::Divide, a2, denominator)
+    }
+
+    /// Smith geometry term using the Schlick-GGX approximation
+    fn geometry_smith(
+        &mut self,
+        n_dot_v: Handle<Expression>,
+        n_dot_l: Handle<Expression>,
+        roughness: Handle<Expression>,
+    ) -> Handle<Expression> {
+        let one = self.literal(1.0);
+        let r = self.binary(BinaryOperator::Add, roughness, one);
+        let r2 = self.binary(BinaryOperator::Multiply, r, r);
+        let eighth = self.literal(0.125);
+        let k = self.binary(BinaryOperator::Multiply, r2, eighth);
+        let one_minus_k = self.binary(BinaryOperator::Subtract, one, k);
+        let schlick = |builder: &mut Self, n_dot_x: Handle<Expression>| {
+            let t = builder.binary(BinaryOperator::Multiply, n_dot_x, one_minus_k);
+            let t = builder.binary(BinaryOperator::Add, t, k);
+            builder.binary(BinaryOperator::Divide, n_dot_x, t)
+        };
+        let g_v = schlick(self, n_dot_v);
+        let g_l = schlick(self, n_dot_l);
+        self.binary(BinaryOperator::Multiply, g_v, g_l)
+    }
+
+    /// Shades the slab's inputs with a Cook-Torrance BRDF under a single directional light and
+    /// a constant ambient term
+    fn lower_slab(&mut self, node: &Node) -> Result<Handle<Expression>, CompilationError> {
+        let base_colour = self.get_slab_input(node, slab_inputs::BASE_COLOUR_INDEX, |b| {
+            let grey = b.literal(0.8);
+            b.splat(grey)
+        })?;
+        let metallic =
+            self.get_slab_input(node, slab_inputs::METALLIC_INDEX, |b| b.literal(0.0))?;
+        let roughness =
+            self.get_slab_input(node, slab_inputs::ROUGHNESS_INDEX, |b| b.literal(0.5))?;
+        let normal = self.get_slab_input(node, slab_inputs::NORMAL_INDEX, |b| {
+            b.lower_built_in(&BuiltIn::WorldNormal)
+        })?;
+        let emission = self.get_slab_input(node, slab_inputs::EMISSION_INDEX, |b| {
+            let zero = b.literal(0.0);
+            b.splat(zero)
+        })?;
+        let occlusion =
+            self.get_slab_input(node, slab_inputs::OCCLUSION_INDEX, |b| b.literal(1.0))?;
+        let alpha = self.get_slab_input(node, slab_inputs::ALPHA_INDEX, |b| b.literal(1.0))?;
+        let clearcoat =
+            self.get_slab_input(node, slab_inputs::CLEARCOAT_INDEX, |b| b.literal(0.0))?;
+
+        let zero = self.literal(0.0);
+        let one = self.literal(1.0);
+        let epsilon = self.literal(1e-4);
+        let four = self.literal(4.0);
+
+        // Surface and lighting vectors
+        let n = self.math(MathFunction::Normalize, normal, None, None);
+        let position = self.lower_built_in(&BuiltIn::WorldPosition);
+        let camera_position = self.get_camera_position()?;
+        let to_camera = self.binary(BinaryOperator::Subtract, camera_position, position);
+        let v = self.math(MathFunction::Normalize, to_camera, None, None);
+        let light_direction = LIGHT_DIRECTION.normalize();
+        let l = self.lower_float_vector(&light_direction.to_array(), CompositeSize::S3)?;
+        let l_plus_v = self.binary(BinaryOperator::Add, l, v);
+        let h = self.math(MathFunction::Normalize, l_plus_v, None, None);
+
+        let n_dot_l = self.math(MathFunction::Dot, n, Some(l), None);
+        let n_dot_l = self.math(MathFunction::Max, n_dot_l, Some(zero), None);
+        let n_dot_v = self.math(MathFunction::Dot, n, Some(v), None);
+        let n_dot_v = self.math(MathFunction::Max, n_dot_v, Some(epsilon), None);
+        let n_dot_h = self.math(MathFunction::Dot, n, Some(h), None);
+        let n_dot_h = self.math(MathFunction::Max, n_dot_h, Some(zero), None);
+        let v_dot_h = self.math(MathFunction::Dot, v, Some(h), None);
+        let v_dot_h = self.math(MathFunction::Max, v_dot_h, Some(zero), None);
+
+        // Schlick's approximation of the fresnel term
+        let one_minus_v_dot_h = self.binary(BinaryOperator::Subtract, one, v_dot_h);
+        let five = self.literal(5.0);
+        let fresnel_weight = self.math(MathFunction::Pow, one_minus_v_dot_h, Some(five), None);
+        let dielectric_reflectance = self.literal(DIELECTRIC_REFLECTANCE);
+        let dielectric_f0 = self.splat(dielectric_reflectance);
+        let metallic_splat = self.splat(metallic);
+        let f0 = self.math(
+            MathFunction::Mix,
+            dielectric_f0,
+            Some(base_colour),
+            Some(metallic_splat),
+        );
+        let ones = self.splat(one);
+        let one_minus_f0 = self.binary(BinaryOperator::Subtract, ones, f0);
+        let fresnel_offset = self.binary(BinaryOperator::Multiply, one_minus_f0, fresnel_weight);
+        let f = self.binary(BinaryOperator::Add, f0, fresnel_offset);
+
+        // Specular lobe
+        let min_roughness = self.literal(MIN_ROUGHNESS);
+        let roughness = self.math(
+            MathFunction::Clamp,
+            roughness,
+            Some(min_roughness),
+            Some(one),
+        );
+        let d = self.distribution_ggx(n_dot_h, roughness);
+        let g = self.geometry_smith(n_dot_v, n_dot_l, roughness);
+        let n_dot_v_n_dot_l = self.binary(BinaryOperator::Multiply, n_dot_v, n_dot_l);
+        let specular_denominator = self.binary(BinaryOperator::Multiply, four, n_dot_v_n_dot_l);
+        let specular_denominator = self.binary(BinaryOperator::Add, specular_denominator, epsilon);
+        let d_g = self.binary(BinaryOperator::Multiply, d, g);
+        let specular_scale = self.binary(BinaryOperator::Divide, d_g, specular_denominator);
+        let specular = self.binary(BinaryOperator::Multiply, f, specular_scale);
+
+        // Diffuse lobe, which metals lack
+        let one_minus_metallic = self.binary(BinaryOperator::Subtract, one, metallic);
+        let one_minus_f = self.binary(BinaryOperator::Subtract, ones, f);
+        let k_d = self.binary(BinaryOperator::Multiply, one_minus_f, one_minus_metallic);
+        let diffuse_colour = self.binary(BinaryOperator::Multiply, k_d, base_colour);
+        let inverse_pi = self.literal(1.0 / PI);
+        let diffuse = self.binary(BinaryOperator::Multiply, diffuse_colour, inverse_pi);
+
+        let light_intensity = self.literal(LIGHT_INTENSITY);
+        let radiance = self.binary(BinaryOperator::Multiply, n_dot_l, light_intensity);
+        let brdf = self.binary(BinaryOperator::Add, diffuse, specular);
+        let direct = self.binary(BinaryOperator::Multiply, brdf, radiance);
+
+        // The clearcoat is a smooth dielectric layer which attenuates the layers below it
+        let fresnel_range = self.literal(1.0 - DIELECTRIC_REFLECTANCE);
+        let coat_fresnel = self.binary(BinaryOperator::Multiply, fresnel_range, fresnel_weight);
+        let coat_fresnel = self.binary(BinaryOperator::Add, dielectric_reflectance, coat_fresnel);
+        let coat = self.binary(BinaryOperator::Multiply, clearcoat, coat_fresnel);
+        let clearcoat_roughness = self.literal(CLEARCOAT_ROUGHNESS);
+        let coat_d = self.distribution_ggx(n_dot_h, clearcoat_roughness);
+        let coat_g = self.geometry_smith(n_dot_v, n_dot_l, clearcoat_roughness);
+        let coat_d_g = self.binary(BinaryOperator::Multiply, coat_d, coat_g);
+        let coat_specular = self.binary(BinaryOperator::Divide, coat_d_g, specular_denominator);
+        let coat_specular = self.binary(BinaryOperator::Multiply, coat_specular, coat);
+        let coat_specular = self.binary(BinaryOperator::Multiply, coat_specular, radiance);
+        let coat_specular = self.splat(coat_specular);
+        let one_minus_coat = self.binary(BinaryOperator::Subtract, one, coat);
+        let direct = self.binary(BinaryOperator::Multiply, direct, one_minus_coat);
+        let direct = self.binary(BinaryOperator::Add, direct, coat_specular);
+
+        // Ambient light, darkened by occlusion
+        let ambient_diffuse =
+            self.binary(BinaryOperator::Multiply, base_colour, one_minus_metallic);
+        let ambient_colour = self.binary(BinaryOperator::Add, ambient_diffuse, f0);
+        let ambient_intensity = self.literal(AMBIENT_INTENSITY);
+        let ambient_scale = self.binary(BinaryOperator::Multiply, ambient_intensity, occlusion);
+        let ambient = self.binary(BinaryOperator::Multiply, ambient_colour, ambient_scale);
+
+        let colour = self.binary(BinaryOperator::Add, ambient, direct);
+        let colour = self.binary(BinaryOperator::Add, colour, emission);
+        let ty = self.get_naga_type(ConcreteDataType::SlabMaterial)?;
+        Ok(self.append(
+            Expression::Compose {
+                ty,
+                components: vec![colour, alpha],
+            },
+            make_span(line!()),
+        ))
+    }
+
+    fn lower_float_vector(
+        &mut self,
+        values: &[f32],
+        size: CompositeSize,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        let ty = self.get_naga_type(ConcreteDataType::Float(size))?;
+        let components = values
+            .iter()
+            .map(|v| self.append(Expression::Literal(Literal::F32(*v)), make_span(line!())))
+            .collect();
+        Ok(self.append(Expression::Compose { ty, components }, make_span(line!())))
+    }
+
+    fn lower_constant(
+        &mut self,
+        value: &ConstantValue,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        match value {
+            ConstantValue::Float(v) => {
+                Ok(self.append(Expression::Literal(Literal::F32(*v)), make_span(line!())))
+            }
+            ConstantValue::Int(v) => {
+                Ok(self.append(Expression::Literal(Literal::I32(*v)), make_span(line!())))
+            }
+            ConstantValue::Bool(v) => {
+                Ok(self.append(Expression::Literal(Literal::Bool(*v)), make_span(line!())))
+            }
+            ConstantValue::Vector2(v) => self.lower_float_vector(v, CompositeSize::S2),
+            ConstantValue::Vector3(v) => self.lower_float_vector(v, CompositeSize::S3),
+            ConstantValue::Vector4(v) | ConstantValue::Color(v) => {
+                self.lower_float_vector(v, CompositeSize::S4)
+            }
+        }
+    }
+
+    fn get_input_component_count(&self, node: &Node) -> Result<u32, CompilationError> {
+        match self.get_concrete_type(PortId::Input(node.input_ports_ids()[0].clone()))? {
+            ConcreteDataType::Float(size) => Ok(size as u32 + 1),
+            t => Err(CompilationError::UnsupportedType(t)),
+        }
+    }
+
+    fn access_index(&mut self, base: Handle<Expression>, index: u32) -> Handle<Expression> {
+        self.append(Expression::AccessIndex { base, index }, make_span(line!()))
+    }
+
+    fn lower_split(&mut self, node: &Node) -> Result<Vec<Handle<Expression>>, CompilationError> {
+        let inputs = self.get_input_expressions(node)?;
+        let component_count = self.get_input_component_count(node)?;
+        // Outputs past the input's width can't be connected, so they're left without an expression
+        Ok((0..component_count.min(node.output_port_count as u32))
+            .map(|index| self.access_index(inputs[0], index))
+            .collect())
+    }
+
+    fn lower_append(&mut self, node: &Node) -> Result<Handle<Expression>, CompilationError> {
+        let components = self.get_input_expressions(node)?;
+        let t = self.get_concrete_type(PortId::Output(node.output_ports_ids()[0].clone()))?;
+        let ty = self.get_naga_type(t)?;
+        Ok(self.append(Expression::Compose { ty, components }, make_span(line!())))
+    }
+
+    fn lower_swizzle(
+        &mut self,
+        node: &Node,
+        pattern: &[VectorComponent],
+    ) -> Result<Handle<Expression>, CompilationError> {
+        let inputs = self.get_input_expressions(node)?;
+        let component_count = self.get_input_component_count(node)?;
+        let size = vector_size(VectorComponent::output_composite_size(pattern));
+        match (size, component_count) {
+            (None, 1) => Ok(inputs[0]),
+            (None, _) => Ok(self.access_index(inputs[0], pattern[0].index())),
+            // A scalar can only be swizzled as `xx...`, which is a splat
+            (Some(size), 1) => Ok(self.append(
+                Expression::Splat {
+                    size,
+                    value: inputs[0],
+                },
+                make_span(line!()),
+            )),
+            (Some(size), _) => {
+                let mut components = [SwizzleComponent::X; 4];
+                for (component, c) in components.iter_mut().zip(pattern.iter()) {
+                    *component = match c {
+                        VectorComponent::X => SwizzleComponent::X,
+                        VectorComponent::Y => SwizzleComponent::Y,
+                        VectorComponent::Z => SwizzleComponent::Z,
+                        VectorComponent::W => SwizzleComponent::W,
+                    };
+                }
+                Ok(self.append(
+                    Expression::Swizzle {
+                        size,
+                        vector: inputs[0],
+                        pattern: components,
+                    },
+                    make_span(line!()),
+                ))
+            }
+        }
+    }
+
+    fn lower_built_in(&mut self, built_in: &BuiltIn) -> Handle<Expression> {
+        let index = match built_in {
+            BuiltIn::WorldPosition => vertex_out::WORLD_POSITION_INDEX,
+            BuiltIn::ClipPosition => vertex_out::POSITION_INDEX,
+            BuiltIn::WorldNormal => vertex_out::NORMAL_INDEX,
+            BuiltIn::WorldBitangent => vertex_out::BITANGENT_INDEX,
+            BuiltIn::WorldTangent => vertex_out::TANGENT_INDEX,
+            BuiltIn::TextureCoordinate => vertex_out::TEX_COORD_INDEX,
+            BuiltIn::TextureCoordinate2 => vertex_out::TEX_COORD_2_INDEX,
+        };
+        let value = self.append(
+            Expression::AccessIndex {
+                base: self.vertex_out,
+                index,
+            },
+            make_span(line!()),
+        );
+        match built_in {
+            // The world position is interpolated as homogenous coordinates
+            BuiltIn::WorldPosition => self.append(
+                Expression::Swizzle {
+                    size: VectorSize::Tri,
+                    vector: value,
+                    pattern: [
+                        SwizzleComponent::X,
+                        SwizzleComponent::Y,
+                        SwizzleComponent::Z,
+                        SwizzleComponent::X,
+                    ],
+                },
+                make_span(line!()),
+            ),
+            _ => value,
+        }
+    }
+
+    fn lower_binary_operation(
+        &mut self,
+        node: &Node,
+        op: &BinaryOperation,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        let mut inputs = self.get_input_expressions(node)?;
+        let input_types = node
+            .input_ports_ids()
+            .into_iter()
+            .map(|p| self.get_concrete_type(PortId::Input(p)))
+            .collect::<Result<Vec<ConcreteDataType>, CompilationError>>()?;
+        let output_type =
+            self.get_concrete_type(PortId::Output(node.output_ports_ids()[0].clone()))?;
+
+        // Scalars combined with vectors are splatted to the vector's size
+        for (input, input_type) in inputs.iter_mut().zip(input_types.iter()) {
+            *input = self.broadcast(*input, *input_type, output_type);
+        }
+        let (left, right) = (inputs[0], inputs[1]);
+        let left_type = input_types[0];
+
+        let binary_operator = match (op, left_type) {
+            (BinaryOperation::Add, _) => BinaryOperator::Add,
+            (BinaryOperation::Subtract, _) => BinaryOperator::Subtract,
+            (BinaryOperation::Multiply, _) => BinaryOperator::Multiply,
+            (BinaryOperation::Divide, _) => BinaryOperator::Divide,
+            (BinaryOperation::Modulo, _) => BinaryOperator::Modulo,
+            (BinaryOperation::Equal, _) => BinaryOperator::Equal,
+            (BinaryOperation::NotEqual, _) => BinaryOperator::NotEqual,
+            (BinaryOperation::Less, _) => BinaryOperator::Less,
+            (BinaryOperation::LessEqual, _) => BinaryOperator::LessEqual,
+            (BinaryOperation::Greater, _) => BinaryOperator::Greater,
+            (BinaryOperation::GreaterEqual, _) => BinaryOperator::GreaterEqual,
+            (BinaryOperation::And, ConcreteDataType::Bool) => BinaryOperator::LogicalAnd,
+            (BinaryOperation::Or, ConcreteDataType::Bool) => BinaryOperator::LogicalOr,
+            (BinaryOperation::And, ConcreteDataType::Int) => BinaryOperator::And,
+            (BinaryOperation::Or, ConcreteDataType::Int) => BinaryOperator::InclusiveOr,
+            (BinaryOperation::ShiftLeft, ConcreteDataType::Int) => BinaryOperator::ShiftLeft,
+            (BinaryOperation::ShiftRight, ConcreteDataType::Int) => BinaryOperator::ShiftRight,
+            (_, t) => return Err(CompilationError::UnsupportedType(t)),
+        };
+
+        // Shift amounts are required to be unsigned
+        let right = match op {
+            BinaryOperation::ShiftLeft | BinaryOperation::ShiftRight => self.append(
+                Expression::As {
+                    expr: right,
+                    kind: ScalarKind::Uint,
+                    convert: Some(4),
+                },
+                make_span(line!()),
+            ),
+            _ => right,
+        };
+
+        let result = self.append(
+            Expression::Binary {
+                op: binary_operator,
+                left,
+                right,
+            },
+            make_span(line!()),
+        );
+
+        // Comparing vectors yields a vector of booleans, which is reduced to a single boolean
+        let is_vector = !matches!(
+            left_type,
+            ConcreteDataType::Float(CompositeSize::S1)
+                | ConcreteDataType::Int
+                | ConcreteDataType::Bool
+        );
+        let reduction = match op {
+            BinaryOperation::Equal
+            | BinaryOperation::Less
+            | BinaryOperation::LessEqual
+            | BinaryOperation::Greater
+            | BinaryOperation::GreaterEqual => Some(RelationalFunction::All),
+            BinaryOperation::NotEqual => Some(RelationalFunction::Any),
+            _ => None,
+        };
+        match reduction {
+            Some(fun) if is_vector => Ok(self.append(
+                Expression::Relational {
+                    fun,
+                    argument: result,
+                },
+                make_span(line!()),
+            )),
+            _ => Ok(result),
+        }
+    }
+
+    fn lower_unary_operation(
+        &mut self,
+        node: &Node,
+        op: &UnaryOperation,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        let inputs = self.get_input_expressions(node)?;
+        let expr = inputs[0];
+        let input_type = self.get_concrete_type(PortId::Input(InputPortId {
+            node_id: node.id,
+            port_index: 0,
+        }))?;
+
+        let fun = match (op, input_type) {
+            (UnaryOperation::Negate, _) => {
+                return Ok(self.append(
+                    Expression::Unary {
+                        op: UnaryOperator::Negate,
+                        expr,
+                    },
+                    make_span(line!()),
+                ))
+            }
+            (UnaryOperation::Not, ConcreteDataType::Bool) => {
+                return Ok(self.append(
+                    Expression::Unary {
+                        op: UnaryOperator::LogicalNot,
+                        expr,
+                    },
+                    make_span(line!()),
+                ))
+            }
+            (UnaryOperation::Not, t) => return Err(CompilationError::UnsupportedType(t)),
+            (UnaryOperation::Abs, _) => MathFunction::Abs,
+            (UnaryOperation::Sign, _) => MathFunction::Sign,
+            (UnaryOperation::Floor, _) => MathFunction::Floor,
+            (UnaryOperation::Ceil, _) => MathFunction::Ceil,
+            (UnaryOperation::Round, _) => MathFunction::Round,
+            (UnaryOperation::Fract, _) => MathFunction::Fract,
+            (UnaryOperation::Trunc, _) => MathFunction::Trunc,
+            (UnaryOperation::Sqrt, _) => MathFunction::Sqrt,
+            (UnaryOperation::Exp, _) => MathFunction::Exp,
+            (UnaryOperation::Log, _) => MathFunction::Log,
+            (UnaryOperation::Sin, _) => MathFunction::Sin,
+            (UnaryOperation::Cos, _) => MathFunction::Cos,
+            (UnaryOperation::Tan, _) => MathFunction::Tan,
+            (UnaryOperation::Asin, _) => MathFunction::Asin,
+            (UnaryOperation::Acos, _) => MathFunction::Acos,
+            (UnaryOperation::Atan, _) => MathFunction::Atan,
+            (UnaryOperation::Saturate, _) => MathFunction::Saturate,
+            (UnaryOperation::Length, _) => MathFunction::Length,
+            // Normalizing a scalar is equivalent to taking its sign
+            (UnaryOperation::Normalize, ConcreteDataType::Float(CompositeSize::S1)) => {
+                MathFunction::Sign
+            }
+            (UnaryOperation::Normalize, _) => MathFunction::Normalize,
+        };
+
+        Ok(self.append(
+            Expression::Math {
+                fun,
+                arg: expr,
+                arg1: None,
+                arg2: None,
+                arg3: None,
+            },
+            make_span(line!()),
+        ))
+    }
+
+    fn lower_nary_operation(
+        &mut self,
+        node: &Node,
+        op: &NaryOperation,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        let mut inputs = self.get_input_expressions(node)?;
+        let output_type =
+            self.get_concrete_type(PortId::Output(node.output_ports_ids()[0].clone()))?;
+        // Scalars passed to vector operations are splatted to the output's size
+        for index in op.broadcast_input_indices() {
+            let input_type = self.get_concrete_type(PortId::Input(InputPortId {
+                node_id: node.id,
+                port_index: *index as u8,
+            }))?;
+            inputs[*index] = self.broadcast(inputs[*index], input_type, output_type);
+        }
+
+        let fun = match op {
+            NaryOperation::Mix => MathFunction::Mix,
+            NaryOperation::Clamp => MathFunction::Clamp,
+            NaryOperation::Smoothstep => MathFunction::SmoothStep,
+            NaryOperation::Step => MathFunction::Step,
+            NaryOperation::Fma => MathFunction::Fma,
+            NaryOperation::Select => {
+                return Ok(self.append(
+                    Expression::Select {
+                        condition: inputs[2],
+                        accept: inputs[1],
+                        reject: inputs[0],
+                    },
+                    make_span(line!()),
+                ))
+            }
+        };
+        Ok(self.math(
+            fun,
+            inputs[0],
+            inputs.get(1).copied(),
+            inputs.get(2).copied(),
+        ))
+    }
+
+    fn make_colour(
+        &mut self,
+        value: Handle<Expression>,
+        t: ConcreteDataType,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        let type_float32_4 = self.get_naga_type(ConcreteDataType::Float(CompositeSize::S4))?;
+        match t {
+            ConcreteDataType::Float(CompositeSize::S4) | ConcreteDataType::SlabMaterial => {
+                Ok(value)
+            }
+            ConcreteDataType::Float(CompositeSize::S3) => {
+                let one = self.append(Expression::Literal(Literal::F32(1.0)), make_span(line!()));
+                Ok(self.append(
+                    Expression::Compose {
+                        ty: type_float32_4,
+                        components: vec![value, one],
+                    },
+                    make_span(line!()),
+                ))
+            }
+            ConcreteDataType::Float(CompositeSize::S2) => {
+                let zero = self.append(Expression::Literal(Literal::F32(0.0)), make_span(line!()));
+                let one = self.append(Expression::Literal(Literal::F32(1.0)), make_span(line!()));
+                Ok(self.append(
+                    Expression::Compose {
+                        ty: type_float32_4,
+                        components: vec![value, zero, one],
+                    },
+                    make_span(line!()),
+                ))
+            }
+            ConcreteDataType::Float(CompositeSize::S1) => {
+                let one = self.append(Expression::Literal(Literal::F32(1.0)), make_span(line!()));
+                Ok(self.append(
+                    Expression::Compose {
+                        ty: type_float32_4,
+                        components: vec![value, value, value, one],
+                    },
+                    make_span(line!()),
+                ))
+            }
+            ConcreteDataType::Int => {
+                let value = self.append(
+                    Expression::As {
+                        expr: value,
+                        kind: ScalarKind::Float,
+                        convert: Some(4),
+                    },
+                    make_span(line!()),
+                );
+                self.make_colour(value, ConcreteDataType::Float(CompositeSize::S1))
+            }
+            ConcreteDataType::Bool => {
+                let zero = self.append(Expression::Literal(Literal::F32(0.0)), make_span(line!()));
+                let one = self.append(Expression::Literal(Literal::F32(1.0)), make_span(line!()));
+                let value = self.append(
+                    Expression::Select {
+                        condition: value,
+                        accept: one,
+                        reject: zero,
+                    },
+                    make_span(line!()),
+                );
+                self.make_colour(value, ConcreteDataType::Float(CompositeSize::S1))
+            }
+            ConcreteDataType::Texture(_, size) | ConcreteDataType::ProceduralField(_, size) => {
+                self.make_colour(value, ConcreteDataType::Float(size))
+            }
+        }
+    }
+
+    // Alpha would make the preview see-through, so only the first three components of a vec4
+    // are shown
+    fn make_opaque_colour(
+        &mut self,
+        value: Handle<Expression>,
+        size: CompositeSize,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        match size {
+            CompositeSize::S4 => {
+                let rgb = self.append(
+                    Expression::Swizzle {
+                        size: VectorSize::Tri,
+                        vector: value,
+                        pattern: [
+                            SwizzleComponent::X,
+                            SwizzleComponent::Y,
+                            SwizzleComponent::Z,
+                            SwizzleComponent::X,
+                        ],
+                    },
+                    make_span(line!()),
+                );
+                self.make_colour(rgb, ConcreteDataType::Float(CompositeSize::S3))
+            }
+            _ => self.make_colour(value, ConcreteDataType::Float(size)),
+        }
+    }
+
+    fn make_checker(&mut self) -> Handle<Expression> {
+        let tex_coord = self.lower_built_in(&BuiltIn::TextureCoordinate);
+        let scale = self.literal(PREVIEW_CHECKER_SCALE);
+        let scale = self.broadcast(
+            scale,
+            ConcreteDataType::Float(CompositeSize::S1),
+            ConcreteDataType::Float(CompositeSize::S2),
+        );
+        let scaled = self.binary(BinaryOperator::Multiply, tex_coord, scale);
+        let cell = self.math(MathFunction::Floor, scaled, None, None);
+        let x = self.access_index(cell, 0);
+        let y = self.access_index(cell, 1);
+        let sum = self.binary(BinaryOperator::Add, x, y);
+        let two = self.literal(2.0);
+        let parity = self.binary(BinaryOperator::Modulo, sum, two);
+        let dark = self.literal(PREVIEW_CHECKER_DARK);
+        let light = self.literal(PREVIEW_CHECKER_LIGHT);
+        self.math(MathFunction::Mix, dark, Some(light), Some(parity))
+    }
+
+    /// Picks a colour for the value flowing into a preview, based on its type
+    fn make_preview_colour(
+        &mut self,
+        value: Handle<Expression>,
+        t: ConcreteDataType,
+    ) -> Result<Handle<Expression>, CompilationError> {
+        match PreviewVisualisation::for_type(t) {
+            PreviewVisualisation::Greyscale | PreviewVisualisation::Material => {
+                self.make_colour(value, t)
+            }
+            PreviewVisualisation::RemappedColour => {
+                let half = self.literal(0.5);
+                let half = self.broadcast(half, ConcreteDataType::Float(CompositeSize::S1), t);
+                let remapped = self.math(MathFunction::Fma, value, Some(half), Some(half));
+                match t {
+                    ConcreteDataType::Float(size) => self.make_opaque_colour(remapped, size),
+                    _ => self.make_colour(remapped, t),
+                }
+            }
+            PreviewVisualisation::Checker => {
+                let flat = self.literal(1.0);
+                let checker = self.make_checker();
+                let value = self.append(
+                    Expression::Select {
+                        condition: value,
+                        accept: flat,
+                        reject: checker,
+                    },
+                    make_span(line!()),
+                );
+                self.make_colour(value, ConcreteDataType::Float(CompositeSize::S1))
+            }
+            // Samples are already colours, so they're shown as they are
+            PreviewVisualisation::Flat2D => match t {
+                ConcreteDataType::Texture(_, size) | ConcreteDataType::ProceduralField(_, size) => {
+                    self.make_opaque_colour(value, size)
+                }
+                _ => Err(CompilationError::UnsupportedType(t)),
+            },
+        }
+    }
+
+    fn finish(
+        mut self,
+        output_port: &InputPortId,
+        is_preview: bool,
+    ) -> Result<(Module, SpanOrigins), CompilationError> {
+        self.origin = Some(PortId::Input(output_port.clone()));
+        let value = self.get_input_expression(output_port)?;
+        let t = self.get_concrete_type(PortId::Input(output_port.clone()))?;
+        let colour = if is_preview {
+            self.make_preview_colour(value, t)?
+        } else {
+            self.make_colour(value, t)?
+        };
+        self.function
+            .named_expressions
+            .insert(colour, "result".to_owned());
+        self.function.body.push(
+            Statement::Return {
+                value: Some(colour),
+            },
+            make_span(line!()),
+        );
+
+        self.shader.entry_points.push(EntryPoint {
+            name: "fragmentMain".to_owned(),
+            stage: wgpu::naga::ShaderStage::Fragment,
+            early_depth_test: None,
+            workgroup_size: [0, 0, 0],
+            function: self.function,
+        });
+        Ok((self.shader, self.span_origins))
+    }
+}
+
+// Everything generated for a node is attributed to its first output, or its first input when
+// it has no outputs
+fn node_origin(node: &Node) -> Option<PortId> {
+    node.output_ports_ids()
+        .first()
+        .cloned()
+        .map(PortId::Output)
+        .or_else(|| node.input_ports_ids().first().cloned().map(PortId::Input))
+}
+
+/// Generates a fragment shader which writes the value flowing into `output_port` to the
+/// colour target. `node_ordering` must be topologically ordered, and only the nodes it
+/// contains are lowered. When `output_port` belongs to a preview node, the value is
+/// visualised according to its type, rather than written as is. Expressions are given spans
+/// from the returned `SpanOrigins`, naming the port they were generated for.
+pub fn make_graph_fragment_shader_module(
+    graph: &Graph,
+    node_ordering: &[u128],
+    concrete_types: &HashMap<PortId, ConcreteDataType>,
+    output_port: &InputPortId,
+) -> Result<(Module, SpanOrigins), CompilationError> {
+    let mut builder = FragmentShaderBuilder::new(graph, concrete_types);
+    for node_id in node_ordering.iter() {
+        let node = graph
+            .nodes
+            .get(node_id)
+            .ok_or(CompilationError::MissingNode(*node_id))?;
+        builder.origin = node_origin(node);
+        builder.lower_node(node)?;
+    }
+    let is_preview = graph
+        .nodes
+        .get(&output_port.node_id)
+        .is_some_and(|node| node.node_type == NodeType::Preview);
+    builder.finish(output_port, is_preview)
+}
@@ -1,9 +1,12 @@
-use crate::constraint_solver::{self, assign_concrete_types, ConstraintSolverError};
+use crate::constraint_solver::ConstraintSolverError::ConflictFound;
+use crate::constraint_solver::{
+    self, assign_concrete_types, group_constraints_by_port, ConstraintSolverError, TypeConflict,
+};
 use crate::constraint_solver_constraints::Constraint::SameTypes;
 use crate::constraint_solver_constraints::{Constraint, SameTypesConstraint};
 use crate::data_types::{AbstractDataType, ComputationDomain, ConcreteDataType};
 use crate::graph_types::{
-    BranchedMultiGraph, BranchedSubgraph, Graph, InputPort, InputPortId, MultiGraph, PortId,
+    BranchedMultiGraph, BranchedSubgraph, Edge, Graph, InputPort, InputPortId, MultiGraph, PortId,
     Subgraph,
 };
 
@@ -24,6 +27,132 @@ fn has_no_dependencies(node: u128, graph: &Graph, visited_nodes: &HashSet<u128>)
     })
 }
 
+fn successor_nodes(graph: &Graph, node_id: u128) -> Vec<u128> {
+    let node = graph.nodes.get(&node_id).unwrap();
+    node.output_ports_ids()
+        .iter()
+        .map(|p| graph.output_ports.get(p).unwrap())
+        .flat_map(|p| p.outgoing_edges.clone())
+        .map(|e| graph.edges.get(&e).unwrap().input_port.clone())
+        .map(|p| graph.input_ports.get(&p).unwrap().id.node_id)
+        .collect()
+}
+
+fn predecessor_nodes(graph: &Graph, node_id: u128) -> Vec<u128> {
+    let node = graph.nodes.get(&node_id).unwrap();
+    node.input_ports_ids()
+        .iter()
+        .filter_map(|p| graph.input_ports.get(p).unwrap().incoming_edge)
+        .map(|e| graph.edges.get(&e).unwrap().output_port.node_id)
+        .collect()
+}
+
+/// Whether `to` can be reached from `from` by repeatedly following `successors`
+pub fn is_reachable<Successors, Iter>(from: u128, to: u128, successors: Successors) -> bool
+where
+    Successors: Fn(u128) -> Iter,
+    Iter: IntoIterator<Item = u128>,
+{
+    let mut visited: HashSet<u128> = HashSet::from([from]);
+    let mut stack: Vec<u128> = vec![from];
+    while let Some(node) = stack.pop() {
+        if node == to {
+            return true;
+        }
+        for successor in successors(node) {
+            if visited.insert(successor) {
+                stack.push(successor);
+            }
+        }
+    }
+    false
+}
+
+/// Whether an edge from `source` to `target` would make the graph cyclic
+pub fn would_create_cycle(graph: &Graph, source: u128, target: u128) -> bool {
+    is_reachable(target, source, |n| successor_nodes(graph, n))
+}
+
+/// Whether the edges, given as (source, target) node pairs, contain a cycle
+pub fn edges_contain_cycle(edges: &[(u128, u128)]) -> bool {
+    let mut in_degrees: HashMap<u128, usize> = HashMap::new();
+    let mut successors: HashMap<u128, Vec<u128>> = HashMap::new();
+    for (source, target) in edges.iter() {
+        in_degrees.entry(*source).or_default();
+        *in_degrees.entry(*target).or_default() += 1;
+        successors.entry(*source).or_default().push(*target);
+    }
+    let mut queue: VecDeque<u128> = in_degrees
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(node, _)| *node)
+        .collect();
+    let mut visited_count: usize = 0;
+    while let Some(node) = queue.pop_front() {
+        visited_count += 1;
+        for successor in successors.get(&node).into_iter().flatten() {
+            let degree = in_degrees.get_mut(successor).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                queue.push_back(*successor);
+            }
+        }
+    }
+    visited_count < in_degrees.len()
+}
+
+/// Copies the part of the graph that `root` depends on, using `root` as the copy's id so that
+/// it can be compiled on its own. Returns `None` if `root` isn't in the graph
+pub fn get_upstream_subgraph(graph: &Graph, root: u128) -> Option<Graph> {
+    if !graph.nodes.contains_key(&root) {
+        return None;
+    }
+    let mut included: HashSet<u128> = HashSet::from([root]);
+    let mut stack: Vec<u128> = vec![root];
+    while let Some(node_id) = stack.pop() {
+        for predecessor in predecessor_nodes(graph, node_id) {
+            if included.insert(predecessor) {
+                stack.push(predecessor);
+            }
+        }
+    }
+
+    let edges: HashMap<u128, Edge> = graph
+        .edges
+        .iter()
+        .filter(|(_, e)| included.contains(&e.input_port.node_id))
+        .map(|(id, e)| (*id, e.clone()))
+        .collect();
+    let output_ports = graph
+        .output_ports
+        .iter()
+        .filter(|(id, _)| included.contains(&id.node_id))
+        .map(|(id, p)| {
+            let mut port = p.clone();
+            port.outgoing_edges.retain(|e| edges.contains_key(e));
+            (id.clone(), port)
+        })
+        .collect();
+    Some(Graph {
+        id: root,
+        nodes: graph
+            .nodes
+            .iter()
+            .filter(|(id, _)| included.contains(id))
+            .map(|(id, n)| (*id, n.clone()))
+            .collect(),
+        edges,
+        dirty: false,
+        input_ports: graph
+            .input_ports
+            .iter()
+            .filter(|(id, _)| included.contains(&id.node_id))
+            .map(|(id, p)| (id.clone(), p.clone()))
+            .collect(),
+        output_ports,
+    })
+}
+
 pub fn topologically_order_nodes(graph: &Graph) -> Vec<u128> {
     let mut visited_nodes: HashSet<u128> = HashSet::new();
     let mut results: Vec<u128> = graph
@@ -36,16 +165,7 @@ pub fn topologically_order_nodes(graph: &Graph) -> Vec<u128> {
     while i < results.len() {
         let node_id = results[i];
         visited_nodes.insert(node_id);
-        let node = graph.nodes.get(&node_id).unwrap();
-        let successor_nodes: Vec<u128> = node
-            .output_ports_ids()
-            .iter()
-            .map(|p| graph.output_ports.get(p).unwrap())
-            .flat_map(|p| p.outgoing_edges.clone())
-            .map(|e| graph.edges.get(&e).unwrap().input_port.clone())
-            .map(|p| graph.input_ports.get(&p).unwrap().id.node_id)
-            .collect();
-        for successor in successor_nodes {
+        for successor in successor_nodes(graph, node_id) {
             if has_no_dependencies(successor, graph, &visited_nodes) {
                 results.push(successor);
             }
@@ -65,6 +185,39 @@ pub fn topologically_order_ports(graph: &Graph, ordered_nodes: &[u128]) -> Vec<P
         .collect()
 }
 
+// Ports of the given nodes, ordered by the edges between them. As with topologically_order_nodes,
+// nodes on a cycle are left out
+fn topologically_order_region(graph: &Graph, node_ids: &HashSet<u128>) -> Vec<PortId> {
+    let mut dependency_counts: HashMap<u128, usize> = node_ids
+        .iter()
+        .map(|n| {
+            let count = predecessor_nodes(graph, *n)
+                .iter()
+                .filter(|p| node_ids.contains(p))
+                .count();
+            (*n, count)
+        })
+        .collect();
+    let mut queue: VecDeque<u128> = dependency_counts
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(n, _)| *n)
+        .collect();
+    let mut ordered_nodes: Vec<u128> = Vec::new();
+    while let Some(node_id) = queue.pop_front() {
+        ordered_nodes.push(node_id);
+        for successor in successor_nodes(graph, node_id) {
+            if let Some(count) = dependency_counts.get_mut(&successor) {
+                *count -= 1;
+                if *count == 0 {
+                    queue.push_back(successor);
+                }
+            }
+        }
+    }
+    topologically_order_ports(graph, &ordered_nodes)
+}
+
 fn label_nodes<Selector>(graph: &Graph, label_selector: &Selector) -> HashMap<u128, HashSet<u128>>
 where
     Selector: Fn(&InputPort) -> Option<u128>,
@@ -76,7 +229,7 @@ where
         .iter()
         .filter_map(|p| graph.input_ports.get(p))
         .filter(|p| p.incoming_edge.is_some())
-        .map(|p| (label_selector(p).unwrap(), p.id.clone()))
+        .map(|p| (label_selector(p).unwrap_or(start_node_id), p.id.clone()))
         .collect();
 
     let mut result: HashMap<u128, HashSet<u128>> = HashMap::new();
@@ -142,64 +295,164 @@ pub fn label_computation_domains(
     result
 }
 
-fn map_constraints_to_ports(graph: &Graph) -> HashMap<PortId, Vec<Constraint>> {
-    let mut constraints_with_edges: Vec<Constraint> =
-        graph.nodes.values().flat_map(|x| x.constraints()).collect();
+// Node constraints along with a same types constraint for each edge, tagged with the id of the
+// edge they came from so conflicts can be traced back to it
+fn collect_constraints(graph: &Graph) -> Vec<(Constraint, Option<u128>)> {
+    let mut constraints: Vec<(Constraint, Option<u128>)> = graph
+        .nodes
+        .values()
+        .flat_map(|x| x.constraints())
+        .map(|c| (c, None))
+        .collect();
     let mut edge_constraints = graph
         .edges
         .values()
-        .map(|e| {
-            SameTypes(SameTypesConstraint {
-                ports: HashSet::from([
-                    PortId::Input(e.input_port.clone()),
-                    PortId::Output(e.output_port.clone()),
-                ]),
-            })
+        .flat_map(|e| {
+            edge_constraints(graph, e)
+                .into_iter()
+                .map(|c| (c, Some(e.id)))
         })
         .collect();
-    constraints_with_edges.append(&mut edge_constraints);
-    let constraints_list: Vec<(PortId, Constraint)> = constraints_with_edges
+    constraints.append(&mut edge_constraints);
+    constraints
+}
+
+// The ports at either end of an edge have the same type, and the node it leaves may constrain
+// its inputs further while that output is in use
+fn edge_constraints(graph: &Graph, edge: &Edge) -> Vec<Constraint> {
+    let mut constraints = vec![SameTypes(SameTypesConstraint {
+        ports: HashSet::from([
+            PortId::Input(edge.input_port.clone()),
+            PortId::Output(edge.output_port.clone()),
+        ]),
+    })];
+    if let Some(node) = graph.nodes.get(&edge.output_port.node_id) {
+        constraints.extend(node.connected_output_constraints(&edge.output_port));
+    }
+    constraints
+}
+
+// As with collect_constraints, but only for the given nodes and the edges attached to them
+fn collect_node_constraints(
+    graph: &Graph,
+    node_ids: &HashSet<u128>,
+) -> Vec<(Constraint, Option<u128>)> {
+    let mut constraints: Vec<(Constraint, Option<u128>)> = node_ids
         .iter()
-        .flat_map(|c| -> Vec<(PortId, Constraint)> {
-            c.get_affected_ports()
-                .into_iter()
-                .map(|p| (p, c.clone()))
-                .collect()
-        })
+        .filter_map(|n| graph.nodes.get(n))
+        .flat_map(|n| n.constraints())
+        .map(|c| (c, None))
         .collect();
-    let constraints: HashMap<PortId, Vec<Constraint>> = constraints_list.iter().fold(
-        HashMap::new(),
-        |mut map: HashMap<PortId, Vec<Constraint>>, kv: &(PortId, Constraint)| {
-            let clone = kv.1.clone();
-            let entry = map.entry(kv.0.clone()).or_default();
-            entry.push(clone);
-            map
-        },
-    );
+    let edges: HashMap<u128, &Edge> = node_ids
+        .iter()
+        .flat_map(|n| node_edges(graph, *n))
+        .map(|e| (e.id, e))
+        .collect();
+    constraints.extend(edges.values().flat_map(|e| {
+        edge_constraints(graph, e)
+            .into_iter()
+            .map(|c| (c, Some(e.id)))
+    }));
     constraints
 }
 
+// The nodes that can be reached from any of the given nodes by following edges in either direction
+fn connected_nodes(graph: &Graph, node_ids: &HashSet<u128>) -> HashSet<u128> {
+    let mut connected: HashSet<u128> = node_ids
+        .iter()
+        .filter(|n| graph.nodes.contains_key(n))
+        .copied()
+        .collect();
+    let mut stack: Vec<u128> = connected.iter().copied().collect();
+    while let Some(node_id) = stack.pop() {
+        for edge in node_edges(graph, node_id) {
+            for neighbour in [edge.input_port.node_id, edge.output_port.node_id] {
+                if connected.insert(neighbour) {
+                    stack.push(neighbour);
+                }
+            }
+        }
+    }
+    connected
+}
+
+fn node_edges(graph: &Graph, node_id: u128) -> Vec<&Edge> {
+    let Some(node) = graph.nodes.get(&node_id) else {
+        return vec![];
+    };
+    let mut edge_ids: Vec<u128> = node
+        .input_ports_ids()
+        .iter()
+        .filter_map(|p| graph.input_ports.get(p))
+        .filter_map(|p| p.incoming_edge)
+        .collect();
+    for port_id in node.output_ports_ids() {
+        if let Some(port) = graph.output_ports.get(&port_id) {
+            edge_ids.extend(port.outgoing_edges.iter().copied());
+        }
+    }
+    edge_ids.iter().filter_map(|e| graph.edges.get(e)).collect()
+}
+
+fn explain_conflict(
+    constraints: &[(Constraint, Option<u128>)],
+    conflict_set: &[usize],
+) -> ConstraintSolverError {
+    let conflicting: Vec<&(Constraint, Option<u128>)> =
+        conflict_set.iter().map(|i| &constraints[*i]).collect();
+    ConflictFound(TypeConflict {
+        ports: conflicting
+            .iter()
+            .flat_map(|(c, _)| c.get_affected_ports())
+            .collect(),
+        edges: conflicting.iter().filter_map(|(_, e)| *e).collect(),
+        constraints: conflicting.iter().map(|(c, _)| c.clone()).collect(),
+    })
+}
+
+fn get_port_type(graph: &Graph, port: &PortId) -> Option<AbstractDataType> {
+    match port {
+        PortId::Input(id) => graph.input_ports.get(id).map(|p| p.abstract_data_type),
+        PortId::Output(id) => graph.output_ports.get(id).map(|p| p.abstract_data_type),
+    }
+}
+
+fn get_port_types(graph: &Graph) -> HashMap<PortId, AbstractDataType> {
+    let mut port_types: Vec<(PortId, AbstractDataType)> = graph
+        .input_ports
+        .values()
+        .map(|p| (PortId::Input(p.id.clone()), p.abstract_data_type))
+        .collect();
+    let mut output_port_types: Vec<(PortId, AbstractDataType)> = graph
+        .output_ports
+        .values()
+        .map(|p| (PortId::Output(p.id.clone()), p.abstract_data_type))
+        .collect();
+    port_types.append(&mut output_port_types);
+    port_types.into_iter().collect()
+}
+
 pub fn concretise_types_in_graph(
     graph: &Graph,
     node_ordering: &[u128],
 ) -> Result<HashMap<PortId, ConcreteDataType>, ConstraintSolverError> {
     let ordered_ports = topologically_order_ports(graph, node_ordering);
-    let constraints = map_constraints_to_ports(graph);
-    let mut port_domains_vec: Vec<(PortId, AbstractDataType)> = graph
-        .input_ports
-        .iter()
-        .map(|t| (PortId::Input(t.0.clone()), t.1.abstract_data_type))
-        .collect();
-    let mut output_port_domains_vec: Vec<(PortId, AbstractDataType)> = graph
-        .output_ports
+    let constraints_with_edges = collect_constraints(graph);
+    let constraints_list: Vec<Constraint> = constraints_with_edges
         .iter()
-        .map(|t| (PortId::Output(t.0.clone()), t.1.abstract_data_type))
+        .map(|(c, _)| c.clone())
         .collect();
-    port_domains_vec.append(&mut output_port_domains_vec);
-    let port_types: HashMap<PortId, AbstractDataType> = port_domains_vec.into_iter().collect();
-    assign_concrete_types(&ordered_ports, &port_types, &constraints)
+    let constraints = group_constraints_by_port(&constraints_list);
+    let port_types = get_port_types(graph);
+    assign_concrete_types(&ordered_ports, &port_types, &constraints).map_err(|_| {
+        let conflict_set = constraint_solver::find_concrete_type_conflict(
+            &ordered_ports,
+            &port_types,
+            &constraints_list,
+        );
+        explain_conflict(&constraints_with_edges, &conflict_set)
+    })
 }
-
 pub fn prune_graph(graph: &mut Graph, subgraph_tags: &HashMap<u128, Vec<u128>>) {
     let node_ids: Vec<u128> = graph.nodes.keys().copied().collect();
     for node_id in node_ids {
@@ -331,22 +584,184 @@ pub fn decompose_branches(
 pub fn narrow_abstract_types(
     graph: &Graph,
 ) -> Result<HashMap<PortId, AbstractDataType>, ConstraintSolverError> {
-    // TODO: Make this a gradual algorithm
     let ordered_nodes = topologically_order_nodes(graph);
     let ordered_ports = topologically_order_ports(graph, &ordered_nodes);
-    let mut port_types: Vec<(PortId, AbstractDataType)> = graph
-        .input_ports
-        .values()
-        .map(|p| (PortId::Input(p.id.clone()), p.abstract_data_type))
-        .collect();
-    let mut output_port_types: Vec<(PortId, AbstractDataType)> = graph
-        .output_ports
-        .values()
-        .map(|p| (PortId::Output(p.id.clone()), p.abstract_data_type))
+    let port_types = get_port_types(graph);
+    let constraints_with_edges = collect_constraints(graph);
+    let constraints_list: Vec<Constraint> = constraints_with_edges
+        .iter()
+        .map(|(c, _)| c.clone())
         .collect();
-    port_types.append(&mut output_port_types);
-    let port_types: HashMap<PortId, AbstractDataType> = port_types.into_iter().collect();
-    let constraints = map_constraints_to_ports(graph);
+    let constraints = group_constraints_by_port(&constraints_list);
+
+    constraint_solver::narrow_abstract_types(&ordered_ports, &port_types, &constraints).map_err(
+        |_| {
+            let conflict_set = constraint_solver::find_abstract_type_conflict(
+                &ordered_ports,
+                &port_types,
+                &constraints_list,
+            );
+            explain_conflict(&constraints_with_edges, &conflict_set)
+        },
+    )
+}
+
+/// Narrows the types of a graph as it is edited. Changed nodes are invalidated as the edits come in,
+/// and only they and their neighbours are re-solved, with the ports beyond those held at the types
+/// found previously. The re-solved region only grows past a neighbour whose type changed.
+/// Removing edges and changing nodes can loosen types anywhere they're connected to, so those
+/// release every node connected to the change and re-solve them from their declared types
+#[derive(Default)]
+pub struct IncrementalTypeNarrower {
+    graph_id: Option<u128>,
+    types: HashMap<PortId, AbstractDataType>,
+    node_ports: HashMap<u128, Vec<PortId>>,
+    invalidated_nodes: HashSet<u128>,
+    released_nodes: HashSet<u128>,
+}
+
+impl IncrementalTypeNarrower {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks a node, along with everything it's connected to, as changed. Call this before a node
+    /// is updated or removed, so the nodes it loses edges to are marked too
+    pub fn invalidate_node(&mut self, graph: &Graph, node_id: u128) {
+        self.invalidated_nodes.insert(node_id);
+        self.released_nodes.insert(node_id);
+        for edge in node_edges(graph, node_id) {
+            self.invalidate_removed_edge(edge);
+        }
+    }
+
+    /// Marks the nodes at both ends of an added edge as changed
+    pub fn invalidate_edge(&mut self, edge: &Edge) {
+        self.invalidated_nodes.insert(edge.input_port.node_id);
+        self.invalidated_nodes.insert(edge.output_port.node_id);
+    }
 
-    constraint_solver::narrow_abstract_types(&ordered_ports, &port_types, &constraints)
+    /// Marks the nodes at both ends of a removed edge as changed, along with everything that's
+    /// still connected to them
+    pub fn invalidate_removed_edge(&mut self, edge: &Edge) {
+        self.invalidate_edge(edge);
+        self.released_nodes.insert(edge.input_port.node_id);
+        self.released_nodes.insert(edge.output_port.node_id);
+    }
+
+    pub fn narrow(
+        &mut self,
+        graph: &Graph,
+    ) -> Result<HashMap<PortId, AbstractDataType>, ConstraintSolverError> {
+        if self.graph_id != Some(graph.id) {
+            self.types = narrow_abstract_types(graph)?;
+            self.graph_id = Some(graph.id);
+            self.node_ports = graph.nodes.values().map(|n| (n.id, n.port_ids())).collect();
+            self.invalidated_nodes.clear();
+            self.released_nodes.clear();
+        } else if !self.invalidated_nodes.is_empty() {
+            self.renarrow(graph)?;
+            self.invalidated_nodes.clear();
+            self.released_nodes.clear();
+        }
+        Ok(self.types.clone())
+    }
+
+    fn renarrow(&mut self, graph: &Graph) -> Result<(), ConstraintSolverError> {
+        let mut region: HashSet<u128> = self
+            .invalidated_nodes
+            .iter()
+            .filter(|n| graph.nodes.contains_key(n))
+            .copied()
+            .collect();
+        // Ports the types were held at may have only been that narrow because of what was
+        // released, so nothing connected to it is held
+        region.extend(connected_nodes(graph, &self.released_nodes));
+        loop {
+            let neighbours: HashSet<u128> = region
+                .iter()
+                .flat_map(|n| node_edges(graph, *n))
+                .flat_map(|e| [e.input_port.node_id, e.output_port.node_id])
+                .filter(|n| !region.contains(n))
+                .collect();
+            let solved_nodes: HashSet<u128> = region.union(&neighbours).copied().collect();
+            let constraints_with_edges = collect_node_constraints(graph, &solved_nodes);
+            let constraints_list: Vec<Constraint> = constraints_with_edges
+                .iter()
+                .map(|(c, _)| c.clone())
+                .collect();
+            let constraints = group_constraints_by_port(&constraints_list);
+            let ordered_ports = topologically_order_region(graph, &solved_nodes);
+            let port_types: HashMap<PortId, AbstractDataType> = constraints
+                .keys()
+                .chain(ordered_ports.iter())
+                .filter_map(|p| get_port_type(graph, p).map(|t| (p.clone(), t)))
+                .collect();
+            let held_types: HashMap<PortId, AbstractDataType> = port_types
+                .keys()
+                .filter(|p| !solved_nodes.contains(&p.node_id()))
+                .filter_map(|p| self.types.get(p).map(|t| (p.clone(), *t)))
+                .collect();
+
+            match constraint_solver::renarrow_abstract_types(
+                &ordered_ports,
+                &port_types,
+                &held_types,
+                &constraints,
+            ) {
+                Ok(types) => {
+                    let changed_neighbours: Vec<u128> =
+                        neighbours
+                            .into_iter()
+                            .filter(|n| {
+                                self.node_ports.get(n).into_iter().flatten().any(|p| {
+                                    types.contains_key(p) && types.get(p) != self.types.get(p)
+                                })
+                            })
+                            .collect();
+                    if changed_neighbours.is_empty() {
+                        self.store_types(graph, &solved_nodes, &types);
+                        return Ok(());
+                    }
+                    region.extend(changed_neighbours);
+                }
+                // Nothing outside the region is holding it back, so this is a genuine conflict
+                Err(_) if held_types.is_empty() => {
+                    let conflict_set = constraint_solver::find_abstract_type_conflict(
+                        &ordered_ports,
+                        &port_types,
+                        &constraints_list,
+                    );
+                    return Err(explain_conflict(&constraints_with_edges, &conflict_set));
+                }
+                // The held ports may have to change as well, so take them into the region
+                Err(_) => region = solved_nodes,
+            }
+        }
+    }
+
+    fn store_types(
+        &mut self,
+        graph: &Graph,
+        solved_nodes: &HashSet<u128>,
+        types: &HashMap<PortId, AbstractDataType>,
+    ) {
+        for node_id in self.invalidated_nodes.iter() {
+            for port in self.node_ports.remove(node_id).into_iter().flatten() {
+                self.types.remove(&port);
+            }
+        }
+        for node_id in solved_nodes {
+            let Some(node) = graph.nodes.get(node_id) else {
+                continue;
+            };
+            let ports = node.port_ids();
+            for port in ports.iter() {
+                if let Some(t) = types.get(port) {
+                    self.types.insert(port.clone(), *t);
+                }
+            }
+            self.node_ports.insert(*node_id, ports);
+        }
+    }
 }
@@ -0,0 +1,565 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::Vec2;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    graph_functions::topologically_order_nodes,
+    graph_transfer_types::{WbblWebappGraphSnapshot, WbblWebappNode},
+    graph_types::Graph,
+    node_display_data::{
+        get_in_port_position, get_node_dimensions, get_out_port_position, PORT_SIZE,
+    },
+    store_errors::WbblWebappStoreError,
+};
+
+const CROSSING_REDUCTION_SWEEPS: usize = 8;
+const COORDINATE_SWEEPS: usize = 4;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutDirection {
+    LeftToRight,
+    TopToBottom,
+}
+
+impl LayoutDirection {
+    fn primary(self, value: Vec2) -> f32 {
+        match self {
+            LayoutDirection::LeftToRight => value.x,
+            LayoutDirection::TopToBottom => value.y,
+        }
+    }
+
+    fn secondary(self, value: Vec2) -> f32 {
+        match self {
+            LayoutDirection::LeftToRight => value.y,
+            LayoutDirection::TopToBottom => value.x,
+        }
+    }
+
+    fn compose(self, primary: f32, secondary: f32) -> Vec2 {
+        match self {
+            LayoutDirection::LeftToRight => Vec2::new(primary, secondary),
+            LayoutDirection::TopToBottom => Vec2::new(secondary, primary),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutOptions {
+    pub direction: LayoutDirection,
+    /// Gap between neighbouring nodes of the same rank
+    pub node_spacing: f32,
+    /// Gap between consecutive ranks
+    pub rank_spacing: f32,
+    /// Extra gap between neighbouring nodes that belong to different groups
+    pub group_spacing: f32,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        LayoutOptions {
+            direction: LayoutDirection::LeftToRight,
+            node_spacing: 50.0,
+            rank_spacing: 100.0,
+            group_spacing: 50.0,
+        }
+    }
+}
+
+// A node being laid out, or a dummy standing in for an edge where it passes through a rank
+struct LayoutItem {
+    node_id: Option<u128>,
+    group_id: Option<u128>,
+    rank: usize,
+    size: Vec2,
+    in_ports: Vec<Vec2>,
+    out_ports: Vec<Vec2>,
+}
+
+impl LayoutItem {
+    fn port(&self, index: usize, output: bool) -> Vec2 {
+        let ports = if output {
+            &self.out_ports
+        } else {
+            &self.in_ports
+        };
+        ports.get(index).copied().unwrap_or(self.size * 0.5)
+    }
+
+    // Where a port sits along the item, as a fraction of the gap to the next item in its rank
+    fn port_fraction(&self, index: usize, output: bool) -> f32 {
+        let count = if output {
+            self.out_ports.len()
+        } else {
+            self.in_ports.len()
+        };
+        (index.min(count) as f32 + 1.0) / (count as f32 + 2.0)
+    }
+}
+
+// A piece of an edge between two items in consecutive ranks
+struct Segment {
+    upper: usize,
+    upper_port: usize,
+    lower: usize,
+    lower_port: usize,
+}
+
+fn assign_ranks(
+    ordering: &[u128],
+    predecessors: &HashMap<u128, Vec<u128>>,
+    successors: &HashMap<u128, Vec<u128>>,
+) -> HashMap<u128, usize> {
+    let mut ranks: HashMap<u128, usize> = HashMap::new();
+    for node_id in ordering {
+        let rank = predecessors
+            .get(node_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|p| ranks.get(p))
+            .map(|r| r + 1)
+            .max()
+            .unwrap_or(0);
+        ranks.insert(*node_id, rank);
+    }
+    // Pull sources up to their nearest successor, so that inputs sit next to where they are used
+    for node_id in ordering.iter().rev() {
+        if predecessors.get(node_id).is_some_and(|p| !p.is_empty()) {
+            continue;
+        }
+        if let Some(rank) = successors
+            .get(node_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|s| ranks.get(s))
+            .min()
+        {
+            ranks.insert(*node_id, rank.saturating_sub(1));
+        }
+    }
+    ranks
+}
+
+fn count_crossings(segments: &[&Segment], items: &[LayoutItem], positions: &[f32]) -> usize {
+    let mut crossings = 0;
+    for (i, a) in segments.iter().enumerate() {
+        let a_upper = positions[a.upper] + items[a.upper].port_fraction(a.upper_port, true);
+        let a_lower = positions[a.lower] + items[a.lower].port_fraction(a.lower_port, false);
+        for b in segments[i + 1..].iter() {
+            let b_upper = positions[b.upper] + items[b.upper].port_fraction(b.upper_port, true);
+            let b_lower = positions[b.lower] + items[b.lower].port_fraction(b.lower_port, false);
+            if (a_upper - b_upper) * (a_lower - b_lower) < 0.0 {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+fn total_crossings(
+    layers: &[Vec<usize>],
+    segments_by_rank: &[Vec<&Segment>],
+    items: &[LayoutItem],
+) -> usize {
+    let positions = layer_positions(layers, items.len());
+    segments_by_rank
+        .iter()
+        .map(|segments| count_crossings(segments, items, &positions))
+        .sum()
+}
+
+fn layer_positions(layers: &[Vec<usize>], item_count: usize) -> Vec<f32> {
+    let mut positions = vec![0.0; item_count];
+    for layer in layers.iter() {
+        for (index, item) in layer.iter().enumerate() {
+            positions[*item] = index as f32;
+        }
+    }
+    positions
+}
+
+// Orders a rank by the barycenters of each item's neighbours, keeping groups together
+fn order_layer(layer: &mut [usize], barycenters: &[f32], items: &[LayoutItem]) {
+    let mut group_barycenters: HashMap<u128, (f32, usize)> = HashMap::new();
+    for item in layer.iter() {
+        if let Some(group_id) = items[*item].group_id {
+            let entry = group_barycenters.entry(group_id).or_insert((0.0, 0));
+            entry.0 += barycenters[*item];
+            entry.1 += 1;
+        }
+    }
+    let key = |item: usize| -> (f32, f32) {
+        let group_barycenter = match items[item].group_id {
+            Some(group_id) => {
+                let (sum, count) = group_barycenters[&group_id];
+                sum / count as f32
+            }
+            None => barycenters[item],
+        };
+        (group_barycenter, barycenters[item])
+    };
+    layer.sort_by(|a, b| {
+        let (a_group, a_item) = key(*a);
+        let (b_group, b_item) = key(*b);
+        a_group
+            .total_cmp(&b_group)
+            .then_with(|| items[*a].group_id.cmp(&items[*b].group_id))
+            .then_with(|| a_item.total_cmp(&b_item))
+    });
+}
+
+fn reduce_crossings(layers: &mut Vec<Vec<usize>>, segments: &[Segment], items: &[LayoutItem]) {
+    let rank_count = layers.len();
+    let mut segments_by_rank: Vec<Vec<&Segment>> = (0..rank_count).map(|_| vec![]).collect();
+    for segment in segments.iter() {
+        segments_by_rank[items[segment.upper].rank].push(segment);
+    }
+
+    let mut best_layers = layers.clone();
+    let mut best_crossings = total_crossings(layers, &segments_by_rank, items);
+    for sweep in 0..CROSSING_REDUCTION_SWEEPS {
+        let downwards = sweep % 2 == 0;
+        let ranks: Vec<usize> = if downwards {
+            (1..rank_count).collect()
+        } else {
+            (0..rank_count.saturating_sub(1)).rev().collect()
+        };
+        for rank in ranks {
+            let positions = layer_positions(layers, items.len());
+            let mut sums: Vec<(f32, usize)> = vec![(0.0, 0); items.len()];
+            for segment in segments.iter() {
+                let (item, neighbour_position) = if downwards {
+                    (
+                        segment.lower,
+                        positions[segment.upper]
+                            + items[segment.upper].port_fraction(segment.upper_port, true),
+                    )
+                } else {
+                    (
+                        segment.upper,
+                        positions[segment.lower]
+                            + items[segment.lower].port_fraction(segment.lower_port, false),
+                    )
+                };
+                if items[item].rank == rank {
+                    sums[item].0 += neighbour_position;
+                    sums[item].1 += 1;
+                }
+            }
+            let barycenters: Vec<f32> = sums
+                .iter()
+                .enumerate()
+                .map(|(item, (sum, count))| {
+                    if *count == 0 {
+                        positions[item]
+                    } else {
+                        sum / *count as f32
+                    }
+                })
+                .collect();
+            order_layer(&mut layers[rank], &barycenters, items);
+        }
+        let crossings = total_crossings(layers, &segments_by_rank, items);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best_layers = layers.clone();
+        }
+    }
+    *layers = best_layers;
+}
+
+// Spreads the items of each rank along the secondary axis, pulling connected ports into line
+fn assign_secondary_coordinates(
+    layers: &[Vec<usize>],
+    segments: &[Segment],
+    items: &[LayoutItem],
+    options: &LayoutOptions,
+) -> Vec<f32> {
+    let direction = options.direction;
+    let gap = |a: usize, b: usize| -> f32 {
+        if items[a].node_id.is_none() || items[b].node_id.is_none() {
+            options.node_spacing * 0.5
+        } else if items[a].group_id != items[b].group_id {
+            options.node_spacing + options.group_spacing
+        } else {
+            options.node_spacing
+        }
+    };
+
+    let mut coordinates = vec![0.0; items.len()];
+    for layer in layers.iter() {
+        let mut cursor = 0.0;
+        for (index, item) in layer.iter().enumerate() {
+            if index > 0 {
+                cursor += gap(layer[index - 1], *item);
+            }
+            coordinates[*item] = cursor;
+            cursor += direction.secondary(items[*item].size);
+        }
+    }
+
+    for sweep in 0..COORDINATE_SWEEPS {
+        let downwards = sweep % 2 == 0;
+        let ranks: Vec<usize> = if downwards {
+            (1..layers.len()).collect()
+        } else {
+            (0..layers.len().saturating_sub(1)).rev().collect()
+        };
+        for rank in ranks {
+            let layer = &layers[rank];
+            let mut sums: Vec<(f32, usize)> = vec![(0.0, 0); items.len()];
+            for segment in segments.iter() {
+                let upper_port =
+                    direction.secondary(items[segment.upper].port(segment.upper_port, true));
+                let lower_port =
+                    direction.secondary(items[segment.lower].port(segment.lower_port, false));
+                let (item, desired) = if downwards {
+                    (
+                        segment.lower,
+                        coordinates[segment.upper] + upper_port - lower_port,
+                    )
+                } else {
+                    (
+                        segment.upper,
+                        coordinates[segment.lower] + lower_port - upper_port,
+                    )
+                };
+                if items[item].rank == rank {
+                    sums[item].0 += desired;
+                    sums[item].1 += 1;
+                }
+            }
+            let desired: Vec<f32> = layer
+                .iter()
+                .map(|item| match sums[*item] {
+                    (_, 0) => coordinates[*item],
+                    (sum, count) => sum / count as f32,
+                })
+                .collect();
+
+            // Place the rank as close to the desired coordinates as it can go without overlaps,
+            // once packing forwards and once backwards, and settle on the average of the two
+            let size = |index: usize| direction.secondary(items[layer[index]].size);
+            let mut forwards = desired.clone();
+            for index in 1..layer.len() {
+                let min =
+                    forwards[index - 1] + size(index - 1) + gap(layer[index - 1], layer[index]);
+                forwards[index] = forwards[index].max(min);
+            }
+            let mut backwards = desired.clone();
+            for index in (0..layer.len().saturating_sub(1)).rev() {
+                let max = backwards[index + 1] - size(index) - gap(layer[index], layer[index + 1]);
+                backwards[index] = backwards[index].min(max);
+            }
+            for (index, item) in layer.iter().enumerate() {
+                coordinates[*item] = (forwards[index] + backwards[index]) * 0.5;
+            }
+        }
+    }
+    coordinates
+}
+
+fn make_node_item(node: &WbblWebappNode, graph: &Graph, rank: usize) -> LayoutItem {
+    let (input_count, output_count) = graph
+        .nodes
+        .get(&node.id)
+        .map(|n| (n.input_port_count, n.output_port_count))
+        .unwrap_or((0, 0));
+    let (width, height) = get_node_dimensions(node.node_type, None, None);
+    let in_ports = (0..input_count)
+        .map(|index| {
+            let (x, y) = get_in_port_position(node.node_type, index);
+            Vec2::new(x as f32, y as f32)
+        })
+        .collect();
+    let out_ports = (0..output_count)
+        .map(|index| {
+            let (x, y) = get_out_port_position(node.node_type, index, None, None);
+            Vec2::new(x as f32, y as f32)
+        })
+        .collect();
+    LayoutItem {
+        node_id: Some(node.id),
+        group_id: node.group_id,
+        rank,
+        size: Vec2::new(width as f32, height as f32),
+        in_ports,
+        out_ports,
+    }
+}
+
+/// Lays out the nodes in `node_ids` as a layered graph: nodes are ranked by their topological
+/// order, ordered within ranks to reduce edge crossings, and then spaced out so that connected
+/// ports line up. Nodes outside `node_ids`, and the edges to them, are ignored.
+/// Returns the new top left position of each node, placed so that the layout starts where the
+/// nodes used to be.
+pub fn layout_nodes(
+    snapshot: &WbblWebappGraphSnapshot,
+    node_ids: &HashSet<u128>,
+    options: &LayoutOptions,
+) -> Result<HashMap<u128, Vec2>, WbblWebappStoreError> {
+    let graph = Graph::try_from(snapshot)?;
+    let nodes: HashMap<u128, &WbblWebappNode> = snapshot
+        .nodes
+        .iter()
+        .filter(|n| node_ids.contains(&n.id))
+        .map(|n| (n.id, n))
+        .collect();
+    if nodes.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut edges: Vec<(u128, u128, usize, usize, u128)> = snapshot
+        .edges
+        .iter()
+        .filter(|e| {
+            graph.edges.contains_key(&e.id)
+                && nodes.contains_key(&e.source)
+                && nodes.contains_key(&e.target)
+        })
+        .map(|e| {
+            (
+                e.source,
+                e.target,
+                e.source_handle as usize,
+                e.target_handle as usize,
+                e.id,
+            )
+        })
+        .collect();
+    edges.sort_by_key(|e| e.4);
+
+    let mut predecessors: HashMap<u128, Vec<u128>> = HashMap::new();
+    let mut successors: HashMap<u128, Vec<u128>> = HashMap::new();
+    for (source, target, _, _, _) in edges.iter() {
+        predecessors.entry(*target).or_default().push(*source);
+        successors.entry(*source).or_default().push(*target);
+    }
+    let mut ordering: Vec<u128> = topologically_order_nodes(&graph)
+        .into_iter()
+        .filter(|n| nodes.contains_key(n))
+        .collect();
+    let ordered: HashSet<u128> = ordering.iter().copied().collect();
+    let mut unordered: Vec<u128> = nodes
+        .keys()
+        .filter(|n| !ordered.contains(n))
+        .copied()
+        .collect();
+    unordered.sort();
+    ordering.extend(unordered);
+    let ranks = assign_ranks(&ordering, &predecessors, &successors);
+
+    let direction = options.direction;
+    let mut items: Vec<LayoutItem> = vec![];
+    let mut item_indices: HashMap<u128, usize> = HashMap::new();
+    // Where each item was before, used to seed the order of each rank
+    let mut previous_coordinates: Vec<(f32, u128)> = vec![];
+    for node_id in ordering.iter() {
+        let node = nodes[node_id];
+        item_indices.insert(*node_id, items.len());
+        items.push(make_node_item(node, &graph, ranks[node_id]));
+        previous_coordinates.push((
+            direction.secondary(Vec2::new(node.position.x as f32, node.position.y as f32)),
+            *node_id,
+        ));
+    }
+
+    let dummy_size = direction.compose(0.0, PORT_SIZE as f32);
+    let mut segments: Vec<Segment> = vec![];
+    for (source, target, source_handle, target_handle, edge_id) in edges.iter() {
+        let source_item = item_indices[source];
+        let target_item = item_indices[target];
+        let group_id = match (items[source_item].group_id, items[target_item].group_id) {
+            (Some(a), Some(b)) if a == b => Some(a),
+            _ => None,
+        };
+        let mut upper = source_item;
+        let mut upper_port = *source_handle;
+        for rank in ranks[source] + 1..ranks[target] {
+            let dummy = items.len();
+            items.push(LayoutItem {
+                node_id: None,
+                group_id,
+                rank,
+                size: dummy_size,
+                in_ports: vec![],
+                out_ports: vec![],
+            });
+            previous_coordinates.push((previous_coordinates[source_item].0, *edge_id));
+            segments.push(Segment {
+                upper,
+                upper_port,
+                lower: dummy,
+                lower_port: 0,
+            });
+            upper = dummy;
+            upper_port = 0;
+        }
+        segments.push(Segment {
+            upper,
+            upper_port,
+            lower: target_item,
+            lower_port: *target_handle,
+        });
+    }
+
+    let rank_count = items.iter().map(|i| i.rank + 1).max().unwrap_or(0);
+    let mut layers: Vec<Vec<usize>> = (0..rank_count).map(|_| vec![]).collect();
+    for (index, item) in items.iter().enumerate() {
+        layers[item.rank].push(index);
+    }
+    for layer in layers.iter_mut() {
+        layer.sort_by(|a, b| {
+            let (a_coordinate, a_id) = previous_coordinates[*a];
+            let (b_coordinate, b_id) = previous_coordinates[*b];
+            a_coordinate
+                .total_cmp(&b_coordinate)
+                .then_with(|| a_id.cmp(&b_id))
+        });
+    }
+    let barycenters = layer_positions(&layers, items.len());
+    for layer in layers.iter_mut() {
+        order_layer(layer, &barycenters, &items);
+    }
+
+    reduce_crossings(&mut layers, &segments, &items);
+    let secondary = assign_secondary_coordinates(&layers, &segments, &items, options);
+
+    let mut rank_starts: Vec<f32> = vec![];
+    let mut cursor = 0.0;
+    for layer in layers.iter() {
+        rank_starts.push(cursor);
+        let extent = layer
+            .iter()
+            .map(|item| direction.primary(items[*item].size))
+            .fold(0.0, f32::max);
+        cursor += extent + options.rank_spacing;
+    }
+
+    let mut positions: HashMap<u128, Vec2> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            item.node_id.map(|id| {
+                (
+                    id,
+                    direction.compose(rank_starts[item.rank], secondary[index]),
+                )
+            })
+        })
+        .collect();
+
+    let previous_min = nodes
+        .values()
+        .map(|n| Vec2::new(n.position.x as f32, n.position.y as f32))
+        .fold(Vec2::splat(f32::INFINITY), Vec2::min);
+    let new_min = positions
+        .values()
+        .fold(Vec2::splat(f32::INFINITY), |a, b| a.min(*b));
+    let offset = previous_min - new_min;
+    for position in positions.values_mut() {
+        *position += offset;
+    }
+    Ok(positions)
+}
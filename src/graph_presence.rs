@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+use crate::graph_transfer_types::{WbblWebappGraphEntityIds, WbblePosition};
+
+#[derive(Debug, PartialEq, Default, Copy, Clone, Serialize, Deserialize)]
+pub struct WbblWebappViewport {
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+}
+
+/// The state each client publishes about itself through the graph's awareness
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize)]
+pub struct WbblWebappPresence {
+    pub cursor: Option<WbblePosition>,
+    pub viewport: Option<WbblWebappViewport>,
+    pub dragging: WbblWebappGraphEntityIds,
+}
+
+impl WbblWebappPresence {
+    pub fn encode(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn decode(json: &str) -> Option<WbblWebappPresence> {
+        serde_json::from_str(json).ok()
+    }
+}
+
+/// A remote client's presence, as returned to the webapp
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct WbblWebappCollaborator {
+    pub client_id: String,
+    pub colour: String,
+    pub cursor: Option<WbblePosition>,
+    pub viewport: Option<WbblWebappViewport>,
+    pub dragging: WbblWebappGraphEntityIds,
+    pub selections: WbblWebappGraphEntityIds,
+}
+
+impl WbblWebappCollaborator {
+    pub fn new(
+        client_id: u64,
+        presence: WbblWebappPresence,
+        selections: WbblWebappGraphEntityIds,
+    ) -> WbblWebappCollaborator {
+        WbblWebappCollaborator {
+            client_id: client_id.to_string(),
+            colour: get_collaborator_colour(client_id),
+            cursor: presence.cursor,
+            viewport: presence.viewport,
+            dragging: presence.dragging,
+            selections,
+        }
+    }
+}
+
+/// Picks a colour for a client that every peer agrees on, without any coordination.
+/// Client ids are mixed first, as sequential ids would otherwise get near identical hues
+pub fn get_collaborator_colour(client_id: u64) -> String {
+    let mut hash = client_id.wrapping_add(0x9e3779b97f4a7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^= hash >> 31;
+    let hue = hash % 360;
+    format!("hsl({}, 70%, 55%)", hue)
+}
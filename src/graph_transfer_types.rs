@@ -157,6 +157,49 @@ pub enum WbblWebappNodeType {
     ShiftLeft,
     ShiftRight,
 
+    Negate,
+    Not,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Round,
+    Fract,
+    Trunc,
+    Sqrt,
+    Exp,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Saturate,
+    Length,
+    Normalize,
+
+    Mix,
+    Clamp,
+    Smoothstep,
+    Step,
+    Fma,
+    Select,
+
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Int,
+    Bool,
+    Color,
+
+    Split,
+    Append,
+    Swizzle,
+
+    Time,
+
     WorldPosition,
     ClipPosition,
     WorldNormal,
@@ -188,6 +231,44 @@ pub fn get_type_name(node_type: WbblWebappNodeType) -> String {
         WbblWebappNodeType::ShiftLeft => "<<".to_owned(),
         WbblWebappNodeType::ShiftRight => ">>".to_owned(),
         WbblWebappNodeType::Or => "or".to_owned(),
+        WbblWebappNodeType::Negate => "negate".to_owned(),
+        WbblWebappNodeType::Not => "not".to_owned(),
+        WbblWebappNodeType::Abs => "abs".to_owned(),
+        WbblWebappNodeType::Sign => "sign".to_owned(),
+        WbblWebappNodeType::Floor => "floor".to_owned(),
+        WbblWebappNodeType::Ceil => "ceil".to_owned(),
+        WbblWebappNodeType::Round => "round".to_owned(),
+        WbblWebappNodeType::Fract => "fract".to_owned(),
+        WbblWebappNodeType::Trunc => "trunc".to_owned(),
+        WbblWebappNodeType::Sqrt => "sqrt".to_owned(),
+        WbblWebappNodeType::Exp => "exp".to_owned(),
+        WbblWebappNodeType::Log => "log".to_owned(),
+        WbblWebappNodeType::Sin => "sin".to_owned(),
+        WbblWebappNodeType::Cos => "cos".to_owned(),
+        WbblWebappNodeType::Tan => "tan".to_owned(),
+        WbblWebappNodeType::Asin => "asin".to_owned(),
+        WbblWebappNodeType::Acos => "acos".to_owned(),
+        WbblWebappNodeType::Atan => "atan".to_owned(),
+        WbblWebappNodeType::Saturate => "saturate".to_owned(),
+        WbblWebappNodeType::Length => "length".to_owned(),
+        WbblWebappNodeType::Normalize => "normalize".to_owned(),
+        WbblWebappNodeType::Mix => "mix".to_owned(),
+        WbblWebappNodeType::Clamp => "clamp".to_owned(),
+        WbblWebappNodeType::Smoothstep => "smoothstep".to_owned(),
+        WbblWebappNodeType::Step => "step".to_owned(),
+        WbblWebappNodeType::Fma => "fma".to_owned(),
+        WbblWebappNodeType::Select => "select".to_owned(),
+        WbblWebappNodeType::Float => "float".to_owned(),
+        WbblWebappNodeType::Vec2 => "vec2".to_owned(),
+        WbblWebappNodeType::Vec3 => "vec3".to_owned(),
+        WbblWebappNodeType::Vec4 => "vec4".to_owned(),
+        WbblWebappNodeType::Int => "int".to_owned(),
+        WbblWebappNodeType::Bool => "bool".to_owned(),
+        WbblWebappNodeType::Color => "color".to_owned(),
+        WbblWebappNodeType::Split => "split".to_owned(),
+        WbblWebappNodeType::Append => "append".to_owned(),
+        WbblWebappNodeType::Swizzle => "swizzle".to_owned(),
+        WbblWebappNodeType::Time => "time".to_owned(),
         WbblWebappNodeType::WorldPosition => "position".to_owned(),
         WbblWebappNodeType::ClipPosition => "clip_pos".to_owned(),
         WbblWebappNodeType::WorldNormal => "normal".to_owned(),
@@ -220,6 +301,44 @@ pub fn from_type_name(type_name: &str) -> Option<WbblWebappNodeType> {
         "<<" => Some(WbblWebappNodeType::ShiftLeft),
         ">>" => Some(WbblWebappNodeType::ShiftRight),
         "or" => Some(WbblWebappNodeType::Or),
+        "negate" => Some(WbblWebappNodeType::Negate),
+        "not" => Some(WbblWebappNodeType::Not),
+        "abs" => Some(WbblWebappNodeType::Abs),
+        "sign" => Some(WbblWebappNodeType::Sign),
+        "floor" => Some(WbblWebappNodeType::Floor),
+        "ceil" => Some(WbblWebappNodeType::Ceil),
+        "round" => Some(WbblWebappNodeType::Round),
+        "fract" => Some(WbblWebappNodeType::Fract),
+        "trunc" => Some(WbblWebappNodeType::Trunc),
+        "sqrt" => Some(WbblWebappNodeType::Sqrt),
+        "exp" => Some(WbblWebappNodeType::Exp),
+        "log" => Some(WbblWebappNodeType::Log),
+        "sin" => Some(WbblWebappNodeType::Sin),
+        "cos" => Some(WbblWebappNodeType::Cos),
+        "tan" => Some(WbblWebappNodeType::Tan),
+        "asin" => Some(WbblWebappNodeType::Asin),
+        "acos" => Some(WbblWebappNodeType::Acos),
+        "atan" => Some(WbblWebappNodeType::Atan),
+        "saturate" => Some(WbblWebappNodeType::Saturate),
+        "length" => Some(WbblWebappNodeType::Length),
+        "normalize" => Some(WbblWebappNodeType::Normalize),
+        "mix" => Some(WbblWebappNodeType::Mix),
+        "clamp" => Some(WbblWebappNodeType::Clamp),
+        "smoothstep" => Some(WbblWebappNodeType::Smoothstep),
+        "step" => Some(WbblWebappNodeType::Step),
+        "fma" => Some(WbblWebappNodeType::Fma),
+        "select" => Some(WbblWebappNodeType::Select),
+        "float" => Some(WbblWebappNodeType::Float),
+        "vec2" => Some(WbblWebappNodeType::Vec2),
+        "vec3" => Some(WbblWebappNodeType::Vec3),
+        "vec4" => Some(WbblWebappNodeType::Vec4),
+        "int" => Some(WbblWebappNodeType::Int),
+        "bool" => Some(WbblWebappNodeType::Bool),
+        "color" => Some(WbblWebappNodeType::Color),
+        "split" => Some(WbblWebappNodeType::Split),
+        "append" => Some(WbblWebappNodeType::Append),
+        "swizzle" => Some(WbblWebappNodeType::Swizzle),
+        "time" => Some(WbblWebappNodeType::Time),
         "position" => Some(WbblWebappNodeType::WorldPosition),
         "clip_pos" => Some(WbblWebappNodeType::ClipPosition),
         "normal" => Some(WbblWebappNodeType::WorldNormal),
@@ -271,14 +390,14 @@ where
     }
 }
 
-fn uuid_to_string<S>(id: &u128, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn uuid_to_string<S>(id: &u128, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
     serializer.serialize_str(&uuid::Uuid::from_u128(*id).to_string())
 }
 
-fn string_to_uuid<'de, D>(deserializer: D) -> Result<u128, D::Error>
+pub(crate) fn string_to_uuid<'de, D>(deserializer: D) -> Result<u128, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -289,7 +408,7 @@ where
     }
 }
 
-fn uuid_to_string_set<S>(ids: &HashSet<u128>, serializer: S) -> Result<S::Ok, S::Error>
+pub(crate) fn uuid_to_string_set<S>(ids: &HashSet<u128>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
@@ -300,7 +419,7 @@ where
     seq.end()
 }
 
-fn string_to_uuid_set<'de, D>(deserializer: D) -> Result<HashSet<u128>, D::Error>
+pub(crate) fn string_to_uuid_set<'de, D>(deserializer: D) -> Result<HashSet<u128>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -719,6 +838,34 @@ pub enum WbblWebappGraphEntityId {
     GroupId(u128),
 }
 
+/// Entity ids split by kind, as returned to the webapp by spatial queries
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WbblWebappGraphEntityIds {
+    pub nodes: Vec<String>,
+    pub edges: Vec<String>,
+    pub groups: Vec<String>,
+}
+
+impl From<Vec<WbblWebappGraphEntityId>> for WbblWebappGraphEntityIds {
+    fn from(value: Vec<WbblWebappGraphEntityId>) -> Self {
+        let mut result = WbblWebappGraphEntityIds::default();
+        for id in value {
+            match id {
+                WbblWebappGraphEntityId::NodeId(id) => {
+                    result.nodes.push(uuid::Uuid::from_u128(id).to_string())
+                }
+                WbblWebappGraphEntityId::EdgeId(id) => {
+                    result.edges.push(uuid::Uuid::from_u128(id).to_string())
+                }
+                WbblWebappGraphEntityId::GroupId(id) => {
+                    result.groups.push(uuid::Uuid::from_u128(id).to_string())
+                }
+            }
+        }
+        result
+    }
+}
+
 impl WbblWebappGraphEntity {
     pub fn id(&self) -> WbblWebappGraphEntityId {
         match self {
@@ -1,12 +1,17 @@
 use crate::{
-    constraint_solver_constraints::{Constraint, SameTypesConstraint},
+    constraint_solver_constraints::{
+        BroadcastConstraint, Constraint, ConstraintApplicationResult, ExactCompositeSizeConstraint,
+        MinCompositeSizeConstraint, SameTypesConstraint, ScalarOrSameCompositeSizeConstraint,
+        SumCompositeSizeConstraint,
+    },
     data_types::{AbstractDataType, CompositeSize, ComputationDomain, ConcreteDataType},
-    graph_transfer_types::{from_type_name, Any, WbblWebappNodeType},
+    graph_transfer_types::{from_type_name, Any, WbblWebappGraphSnapshot, WbblWebappNodeType},
     store_errors::WbblWebappStoreError,
     yrs_utils::{get_atomic_bigint, get_atomic_string, get_atomic_u128_from_string, get_map},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use yrs::{Map, MapRef, ReadTxn};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -103,7 +108,33 @@ impl Node {
             NodeType::Slab => vec![],
             NodeType::Preview => vec![],
             NodeType::BuiltIn(_) => vec![],
+            NodeType::Constant(_) => vec![],
+            NodeType::Time => vec![],
             NodeType::BinaryOperation(op) => op.constraints(self),
+            NodeType::UnaryOperation(op) => op.constraints(self),
+            NodeType::NaryOperation(op) => op.constraints(self),
+            NodeType::Split => vec![Constraint::MinCompositeSize(MinCompositeSizeConstraint {
+                port: PortId::Input(self.input_ports_ids()[0].clone()),
+                composite_size: CompositeSize::S2,
+            })],
+            NodeType::Append => vec![Constraint::SumCompositeSize(SumCompositeSizeConstraint {
+                inputs: self
+                    .input_ports_ids()
+                    .into_iter()
+                    .map(PortId::Input)
+                    .collect(),
+                output: PortId::Output(self.output_ports_ids()[0].clone()),
+            })],
+            NodeType::Swizzle(pattern) => vec![
+                Constraint::MinCompositeSize(MinCompositeSizeConstraint {
+                    port: PortId::Input(self.input_ports_ids()[0].clone()),
+                    composite_size: VectorComponent::min_composite_size(pattern),
+                }),
+                Constraint::ExactCompositeSize(ExactCompositeSizeConstraint {
+                    port: PortId::Output(self.output_ports_ids()[0].clone()),
+                    composite_size: VectorComponent::output_composite_size(pattern),
+                }),
+            ],
             NodeType::Junction => vec![Constraint::SameTypes(SameTypesConstraint {
                 ports: self.port_ids().iter().cloned().collect(),
             })],
@@ -111,14 +142,33 @@ impl Node {
         }
     }
 
+    /// Constraints that only apply while the given output port has outgoing edges, such as a
+    /// split needing an input with a z component before its z output can be used
+    pub fn connected_output_constraints(&self, port: &OutputPortId) -> Vec<Constraint> {
+        let composite_size = match (&self.node_type, port.port_index) {
+            (NodeType::Split, 2) => CompositeSize::S3,
+            (NodeType::Split, 3) => CompositeSize::S4,
+            _ => return vec![],
+        };
+        vec![Constraint::MinCompositeSize(MinCompositeSizeConstraint {
+            port: PortId::Input(self.input_ports_ids()[0].clone()),
+            composite_size,
+        })]
+    }
+
     pub fn input_ports(&self, incoming_edges: &[&Edge]) -> Vec<InputPort> {
         match &self.node_type {
             NodeType::Output => self.make_input_ports(
                 incoming_edges,
                 &[(AbstractDataType::AnyMaterial, None, None)],
             ),
-            // TODO
-            NodeType::Slab => vec![],
+            NodeType::Slab => self.make_input_ports(
+                incoming_edges,
+                &slab_inputs::PORT_TYPES
+                    .iter()
+                    .map(|t| (AbstractDataType::ConcreteType(*t), None, None))
+                    .collect::<Vec<(AbstractDataType, Option<u128>, Option<u128>)>>(),
+            ),
             NodeType::Preview => {
                 self.make_input_ports(incoming_edges, &[(AbstractDataType::Any, None, None)])
             }
@@ -129,7 +179,29 @@ impl Node {
                     .map(|t| (*t, None, None))
                     .collect::<Vec<(AbstractDataType, Option<u128>, Option<u128>)>>(),
             ),
+            NodeType::UnaryOperation(op) => {
+                self.make_input_ports(incoming_edges, &[(op.input_port_type(), None, None)])
+            }
+            NodeType::NaryOperation(op) => self.make_input_ports(
+                incoming_edges,
+                &op.input_port_types()
+                    .iter()
+                    .map(|t| (*t, None, None))
+                    .collect::<Vec<(AbstractDataType, Option<u128>, Option<u128>)>>(),
+            ),
             NodeType::BuiltIn(_) => vec![],
+            NodeType::Constant(_) => vec![],
+            NodeType::Time => vec![],
+            NodeType::Split | NodeType::Swizzle(_) => {
+                self.make_input_ports(incoming_edges, &[(AbstractDataType::AnyFloat, None, None)])
+            }
+            NodeType::Append => self.make_input_ports(
+                incoming_edges,
+                &[
+                    (AbstractDataType::AnyFloat, None, None),
+                    (AbstractDataType::AnyFloat, None, None),
+                ],
+            ),
             NodeType::Junction => {
                 self.make_input_ports(incoming_edges, &[(AbstractDataType::Any, None, None)])
             }
@@ -150,7 +222,29 @@ impl Node {
             NodeType::BinaryOperation(op) => {
                 self.make_output_ports(outgoing_edges, &[op.output_port_type()])
             }
+            NodeType::UnaryOperation(op) => {
+                self.make_output_ports(outgoing_edges, &[op.output_port_type()])
+            }
+            NodeType::NaryOperation(op) => {
+                self.make_output_ports(outgoing_edges, &[op.output_port_type()])
+            }
             NodeType::BuiltIn(b) => self.make_output_ports(outgoing_edges, &[b.output_port_type()]),
+            NodeType::Constant(c) => {
+                self.make_output_ports(outgoing_edges, &[c.output_port_type()])
+            }
+            NodeType::Time => self.make_output_ports(
+                outgoing_edges,
+                &[AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S1)); 3],
+            ),
+            NodeType::Split => self.make_output_ports(
+                outgoing_edges,
+                &[AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S1)); 4],
+            ),
+            // A swizzle's width is narrowed by the solver, so editing its pattern doesn't change
+            // the type of its output port
+            NodeType::Append | NodeType::Swizzle(_) => {
+                self.make_output_ports(outgoing_edges, &[AbstractDataType::AnyFloat])
+            }
             NodeType::Junction => self.make_output_ports(outgoing_edges, &[AbstractDataType::Any]),
             NodeType::Frame => vec![],
         }
@@ -176,29 +270,42 @@ pub enum PortId {
     Input(InputPortId),
 }
 
+impl PortId {
+    pub fn node_id(&self) -> u128 {
+        match self {
+            PortId::Output(port) => port.node_id,
+            PortId::Input(port) => port.node_id,
+        }
+    }
+}
+
 impl Node {
     pub fn get_computation_domain(&self) -> Option<HashSet<ComputationDomain>> {
         match self.node_type {
             NodeType::Output => Some(HashSet::from([
-                ComputationDomain::TimeVarying,
                 ComputationDomain::ModelDependant,
                 ComputationDomain::TransformDependant,
             ])),
             NodeType::Slab => Some(HashSet::from([
-                ComputationDomain::TimeVarying,
                 ComputationDomain::ModelDependant,
                 ComputationDomain::TransformDependant,
             ])),
             NodeType::Preview => Some(HashSet::from([
-                ComputationDomain::TimeVarying,
                 ComputationDomain::ModelDependant,
                 ComputationDomain::TransformDependant,
             ])),
             NodeType::BinaryOperation(_) => None,
+            NodeType::UnaryOperation(_) => None,
+            NodeType::NaryOperation(_) => None,
             NodeType::BuiltIn(_) => Some(HashSet::from([
                 ComputationDomain::ModelDependant,
                 ComputationDomain::TransformDependant,
             ])),
+            NodeType::Constant(_) => None,
+            NodeType::Time => Some(HashSet::from([ComputationDomain::TimeVarying])),
+            NodeType::Split => None,
+            NodeType::Append => None,
+            NodeType::Swizzle(_) => None,
             NodeType::Junction => None,
             NodeType::Frame => None,
         }
@@ -231,6 +338,194 @@ pub struct Graph {
     pub output_ports: HashMap<OutputPortId, OutputPort>,
 }
 
+impl Graph {
+    /// Removes the edge along with its references from both of the ports it connects
+    pub fn remove_edge(&mut self, edge_id: u128) -> Option<Edge> {
+        let edge = self.edges.remove(&edge_id)?;
+        if let Some(input_port) = self.input_ports.get_mut(&edge.input_port) {
+            if input_port.incoming_edge == Some(edge_id) {
+                input_port.incoming_edge = None;
+            }
+        }
+        if let Some(output_port) = self.output_ports.get_mut(&edge.output_port) {
+            output_port.outgoing_edges.retain(|x| *x != edge_id);
+        }
+        self.dirty = true;
+        Some(edge)
+    }
+
+    /// Replaces the type of an existing node, keeping the edges of any ports whose abstract type
+    /// is unchanged and removing the rest
+    pub fn update_node_type(
+        &mut self,
+        id: u128,
+        node_type: NodeType,
+    ) -> Result<(), WbblWebappStoreError> {
+        if let Some(prev_node) = self.nodes.get(&id) {
+            let prev_input_port_count = prev_node.input_port_count;
+            let prev_output_port_count = prev_node.output_port_count;
+            let mut incoming_edges: Vec<&Edge> = Vec::new();
+            let mut outgoing_edges: Vec<&Edge> = Vec::new();
+
+            for i in 0..prev_input_port_count {
+                if let Some(input_port) = self.input_ports.get(&InputPortId {
+                    node_id: prev_node.id,
+                    port_index: i,
+                }) {
+                    if let Some(Some(incoming_edge)) =
+                        input_port.incoming_edge.map(|x| self.edges.get(&x))
+                    {
+                        incoming_edges.push(incoming_edge);
+                    }
+                }
+            }
+            for i in 0..prev_output_port_count {
+                if let Some(output_port) = self.output_ports.get(&OutputPortId {
+                    node_id: prev_node.id,
+                    port_index: i,
+                }) {
+                    for edge_id in output_port.outgoing_edges.iter() {
+                        if let Some(outgoing_edge) = self.edges.get(edge_id) {
+                            outgoing_edges.push(outgoing_edge);
+                        }
+                    }
+                }
+            }
+
+            let new_input_port_count = node_type.input_port_count(&incoming_edges, &outgoing_edges);
+            let new_output_port_count =
+                node_type.output_port_count(&incoming_edges, &outgoing_edges);
+
+            if new_input_port_count < prev_input_port_count {
+                for i in new_input_port_count..prev_input_port_count {
+                    let port_id = InputPortId {
+                        node_id: id,
+                        port_index: i,
+                    };
+                    if let Some(port) = self.input_ports.remove(&port_id) {
+                        if let Some(edge_id) = port.incoming_edge {
+                            self.remove_edge(edge_id);
+                        }
+                    }
+                }
+            }
+
+            if new_output_port_count < prev_output_port_count {
+                for i in new_output_port_count..prev_output_port_count {
+                    let port_id = OutputPortId {
+                        node_id: id,
+                        port_index: i,
+                    };
+                    if let Some(port) = self.output_ports.remove(&port_id) {
+                        for edge_id in port.outgoing_edges {
+                            self.remove_edge(edge_id);
+                        }
+                    }
+                }
+            }
+
+            let node = Node {
+                id,
+                input_port_count: new_input_port_count,
+                output_port_count: new_output_port_count,
+                node_type,
+            };
+            for mut port in node.input_ports(&[]) {
+                if let Some(prev_port) = self.input_ports.remove(&port.id) {
+                    if prev_port.abstract_data_type == port.abstract_data_type {
+                        // If abstract data type is same, keep edge
+                        port.incoming_edge = prev_port.incoming_edge;
+                    } else if let Some(edge_id) = prev_port.incoming_edge {
+                        self.remove_edge(edge_id);
+                    }
+                }
+                self.input_ports.insert(port.id.clone(), port);
+            }
+            for mut port in node.output_ports(&[]) {
+                if let Some(prev_port) = self.output_ports.remove(&port.id) {
+                    if prev_port.abstract_data_type == port.abstract_data_type {
+                        // If abstract data type is same, keep edges
+                        port.outgoing_edges = prev_port.outgoing_edges;
+                    } else {
+                        for edge_id in prev_port.outgoing_edges {
+                            self.remove_edge(edge_id);
+                        }
+                    }
+                }
+                self.output_ports.insert(port.id.clone(), port);
+            }
+            self.nodes.insert(node.id, node);
+            self.dirty = true;
+            Ok(())
+        } else {
+            Err(WbblWebappStoreError::NotFound)
+        }
+    }
+}
+
+impl TryFrom<&WbblWebappGraphSnapshot> for Graph {
+    type Error = WbblWebappStoreError;
+
+    fn try_from(snapshot: &WbblWebappGraphSnapshot) -> Result<Self, Self::Error> {
+        let mut graph = Graph {
+            id: snapshot.id,
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
+            dirty: true,
+            input_ports: HashMap::new(),
+            output_ports: HashMap::new(),
+        };
+        for node in snapshot.nodes.iter() {
+            let node_type = Node::node_type_from_webapp_node(node.node_type, &node.data)?;
+            let node = Node {
+                id: node.id,
+                input_port_count: node_type.input_port_count(&[], &[]),
+                output_port_count: node_type.output_port_count(&[], &[]),
+                node_type,
+            };
+            for port in node.input_ports(&[]) {
+                graph.input_ports.insert(port.id.clone(), port);
+            }
+            for port in node.output_ports(&[]) {
+                graph.output_ports.insert(port.id.clone(), port);
+            }
+            graph.nodes.insert(node.id, node);
+        }
+        for edge in snapshot.edges.iter() {
+            let edge = Edge {
+                id: edge.id,
+                input_port: InputPortId {
+                    node_id: edge.target,
+                    port_index: edge.target_handle as u8,
+                },
+                output_port: OutputPortId {
+                    node_id: edge.source,
+                    port_index: edge.source_handle as u8,
+                },
+            };
+            // Edges between ports that don't exist can't be traversed, so they are left out
+            if !graph.input_ports.contains_key(&edge.input_port)
+                || !graph.output_ports.contains_key(&edge.output_port)
+            {
+                continue;
+            }
+            graph
+                .output_ports
+                .get_mut(&edge.output_port)
+                .unwrap()
+                .outgoing_edges
+                .push(edge.id);
+            graph
+                .input_ports
+                .get_mut(&edge.input_port)
+                .unwrap()
+                .incoming_edge = Some(edge.id);
+            graph.edges.insert(edge.id, edge);
+        }
+        Ok(graph)
+    }
+}
+
 #[derive(Clone)]
 pub struct Subgraph {
     pub id: u128,
@@ -260,6 +555,32 @@ pub struct BranchedMultiGraph {
     pub dependencies: HashMap<u128, HashSet<u128>>,
 }
 
+/// Input ports of the Slab node. Unconnected inputs fall back to a default value when compiled.
+pub mod slab_inputs {
+    use crate::data_types::{CompositeSize, ConcreteDataType};
+
+    pub const BASE_COLOUR_INDEX: u8 = 0;
+    pub const METALLIC_INDEX: u8 = 1;
+    pub const ROUGHNESS_INDEX: u8 = 2;
+    /// World space normal, defaulting to the interpolated surface normal
+    pub const NORMAL_INDEX: u8 = 3;
+    pub const EMISSION_INDEX: u8 = 4;
+    pub const OCCLUSION_INDEX: u8 = 5;
+    pub const ALPHA_INDEX: u8 = 6;
+    pub const CLEARCOAT_INDEX: u8 = 7;
+
+    pub const PORT_TYPES: [ConcreteDataType; 8] = [
+        ConcreteDataType::Float(CompositeSize::S3),
+        ConcreteDataType::Float(CompositeSize::S1),
+        ConcreteDataType::Float(CompositeSize::S1),
+        ConcreteDataType::Float(CompositeSize::S3),
+        ConcreteDataType::Float(CompositeSize::S3),
+        ConcreteDataType::Float(CompositeSize::S1),
+        ConcreteDataType::Float(CompositeSize::S1),
+        ConcreteDataType::Float(CompositeSize::S1),
+    ];
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BinaryOperation {
     Add,
@@ -338,10 +659,14 @@ impl BinaryOperation {
             | BinaryOperation::Subtract
             | BinaryOperation::Multiply
             | BinaryOperation::Divide
-            | BinaryOperation::Modulo => {
-                let ports: HashSet<PortId> = node.port_ids().iter().cloned().collect();
-                vec![Constraint::SameTypes(SameTypesConstraint { ports })]
-            }
+            | BinaryOperation::Modulo => vec![Constraint::Broadcast(BroadcastConstraint {
+                inputs: node
+                    .input_ports_ids()
+                    .into_iter()
+                    .map(PortId::Input)
+                    .collect(),
+                output: PortId::Output(node.output_ports_ids()[0].clone()),
+            })],
             BinaryOperation::Equal
             | BinaryOperation::NotEqual
             | BinaryOperation::Less
@@ -372,6 +697,160 @@ impl BinaryOperation {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnaryOperation {
+    Negate,
+    /// Logical negation for booleans and bitwise complement for integers
+    Not,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Round,
+    Fract,
+    Trunc,
+    Sqrt,
+    Exp,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    /// Clamps the value between 0 and 1
+    Saturate,
+    Length,
+    Normalize,
+}
+
+impl UnaryOperation {
+    pub fn input_port_type(&self) -> AbstractDataType {
+        match self {
+            UnaryOperation::Negate | UnaryOperation::Abs | UnaryOperation::Sign => {
+                AbstractDataType::AnyNumber
+            }
+            UnaryOperation::Not => AbstractDataType::ConcreteType(ConcreteDataType::Bool),
+            UnaryOperation::Floor
+            | UnaryOperation::Ceil
+            | UnaryOperation::Round
+            | UnaryOperation::Fract
+            | UnaryOperation::Trunc
+            | UnaryOperation::Sqrt
+            | UnaryOperation::Exp
+            | UnaryOperation::Log
+            | UnaryOperation::Sin
+            | UnaryOperation::Cos
+            | UnaryOperation::Tan
+            | UnaryOperation::Asin
+            | UnaryOperation::Acos
+            | UnaryOperation::Atan
+            | UnaryOperation::Saturate
+            | UnaryOperation::Length
+            | UnaryOperation::Normalize => AbstractDataType::AnyFloat,
+        }
+    }
+
+    pub fn output_port_type(&self) -> AbstractDataType {
+        match self {
+            UnaryOperation::Length => {
+                AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S1))
+            }
+            _ => self.input_port_type(),
+        }
+    }
+
+    pub fn constraints(&self, node: &Node) -> Vec<Constraint> {
+        match self {
+            UnaryOperation::Length => vec![],
+            _ => {
+                let ports: HashSet<PortId> = node.port_ids().iter().cloned().collect();
+                vec![Constraint::SameTypes(SameTypesConstraint { ports })]
+            }
+        }
+    }
+}
+
+/// Operations taking more than two inputs, along with step, whose inputs are ordered as in WGSL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NaryOperation {
+    /// Linearly interpolates from `a` to `b` by `t`
+    Mix,
+    Clamp,
+    Smoothstep,
+    Step,
+    /// Fused multiply-add, `a * b + c`
+    Fma,
+    /// Picks `b` when the condition is true and `a` otherwise
+    Select,
+}
+
+impl NaryOperation {
+    pub fn input_port_types(&self) -> Vec<AbstractDataType> {
+        match self {
+            NaryOperation::Mix
+            | NaryOperation::Clamp
+            | NaryOperation::Smoothstep
+            | NaryOperation::Fma => vec![AbstractDataType::AnyFloat; 3],
+            NaryOperation::Step => vec![AbstractDataType::AnyFloat; 2],
+            NaryOperation::Select => vec![
+                AbstractDataType::AnyVectorOrScalar,
+                AbstractDataType::AnyVectorOrScalar,
+                AbstractDataType::ConcreteType(ConcreteDataType::Bool),
+            ],
+        }
+    }
+
+    pub fn output_port_type(&self) -> AbstractDataType {
+        match self {
+            NaryOperation::Select => AbstractDataType::AnyVectorOrScalar,
+            _ => AbstractDataType::AnyFloat,
+        }
+    }
+
+    /// Indices of the inputs which take the output's type
+    pub fn value_input_indices(&self) -> &'static [usize] {
+        match self {
+            NaryOperation::Mix => &[0, 1],
+            NaryOperation::Clamp => &[0],
+            NaryOperation::Smoothstep => &[2],
+            NaryOperation::Step => &[1],
+            NaryOperation::Fma => &[0, 1, 2],
+            NaryOperation::Select => &[0, 1],
+        }
+    }
+
+    /// Indices of the inputs which can either be a scalar or take the output's type
+    pub fn broadcast_input_indices(&self) -> &'static [usize] {
+        match self {
+            NaryOperation::Mix => &[2],
+            NaryOperation::Clamp => &[1, 2],
+            NaryOperation::Smoothstep => &[0, 1],
+            NaryOperation::Step => &[0],
+            NaryOperation::Fma | NaryOperation::Select => &[],
+        }
+    }
+
+    pub fn constraints(&self, node: &Node) -> Vec<Constraint> {
+        let input_ports = node.input_ports_ids();
+        let output_port = PortId::Output(node.output_ports_ids()[0].clone());
+        let mut ports: HashSet<PortId> = self
+            .value_input_indices()
+            .iter()
+            .map(|i| PortId::Input(input_ports[*i].clone()))
+            .collect();
+        ports.insert(output_port.clone());
+        let mut constraints = vec![Constraint::SameTypes(SameTypesConstraint { ports })];
+        constraints.extend(self.broadcast_input_indices().iter().map(|i| {
+            Constraint::ScalarOrSameCompositeSize(ScalarOrSameCompositeSizeConstraint {
+                port: PortId::Input(input_ports[*i].clone()),
+                reference: output_port.clone(),
+            })
+        }));
+        constraints
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BuiltIn {
     WorldPosition,
@@ -411,13 +890,297 @@ impl BuiltIn {
     }
 }
 
+/// Literal value held by a constant node, decoded from the node's `data` map
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+    Float(f32),
+    Vector2([f32; 2]),
+    Vector3([f32; 3]),
+    Vector4([f32; 4]),
+    Int(i32),
+    Bool(bool),
+    /// Linear RGBA with every channel in `[0, 1]`
+    Color([f32; 4]),
+}
+
+// Decoding rejects NaN and infinities, so equality on the stored floats is total
+impl Eq for ConstantValue {}
+
+impl ConstantValue {
+    pub fn output_port_type(&self) -> AbstractDataType {
+        match self {
+            ConstantValue::Float(_) => {
+                AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S1))
+            }
+            ConstantValue::Vector2(_) => {
+                AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S2))
+            }
+            ConstantValue::Vector3(_) => {
+                AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S3))
+            }
+            ConstantValue::Vector4(_) | ConstantValue::Color(_) => {
+                AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S4))
+            }
+            ConstantValue::Int(_) => AbstractDataType::ConcreteType(ConcreteDataType::Int),
+            ConstantValue::Bool(_) => AbstractDataType::ConcreteType(ConcreteDataType::Bool),
+        }
+    }
+
+    fn decode_float(data: &HashMap<String, Any>, key: &str) -> Result<f32, WbblWebappStoreError> {
+        match data.get(key) {
+            Some(Any::Number(n)) if n.is_finite() && n.abs() <= f32::MAX as f64 => Ok(*n as f32),
+            _ => Err(WbblWebappStoreError::MalformedNodeData),
+        }
+    }
+
+    fn decode_floats<const N: usize>(
+        data: &HashMap<String, Any>,
+        keys: [&str; N],
+    ) -> Result<[f32; N], WbblWebappStoreError> {
+        let mut values = [0.0; N];
+        for (value, key) in values.iter_mut().zip(keys) {
+            *value = Self::decode_float(data, key)?;
+        }
+        Ok(values)
+    }
+
+    fn decode_int(data: &HashMap<String, Any>, key: &str) -> Result<i32, WbblWebappStoreError> {
+        match data.get(key) {
+            Some(Any::BigInt(n)) => {
+                i32::try_from(*n).map_err(|_| WbblWebappStoreError::MalformedNodeData)
+            }
+            // Numbers coming from JS may not have been converted to a BigInt
+            Some(Any::Number(n))
+                if n.fract() == 0.0 && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 =>
+            {
+                Ok(*n as i32)
+            }
+            _ => Err(WbblWebappStoreError::MalformedNodeData),
+        }
+    }
+
+    fn decode_bool(data: &HashMap<String, Any>, key: &str) -> Result<bool, WbblWebappStoreError> {
+        match data.get(key) {
+            Some(Any::Bool(b)) => Ok(*b),
+            _ => Err(WbblWebappStoreError::MalformedNodeData),
+        }
+    }
+
+    /// Keys of the `data` map that a node of the given type holds its value in
+    pub fn data_keys(node_type: WbblWebappNodeType) -> &'static [&'static str] {
+        match node_type {
+            WbblWebappNodeType::Float | WbblWebappNodeType::Int | WbblWebappNodeType::Bool => {
+                &["value"]
+            }
+            WbblWebappNodeType::Vec2 => &["x", "y"],
+            WbblWebappNodeType::Vec3 => &["x", "y", "z"],
+            WbblWebappNodeType::Vec4 => &["x", "y", "z", "w"],
+            WbblWebappNodeType::Color => &["r", "g", "b", "a"],
+            _ => &[],
+        }
+    }
+
+    pub fn decode(
+        node_type: WbblWebappNodeType,
+        data: &HashMap<String, Any>,
+    ) -> Result<ConstantValue, WbblWebappStoreError> {
+        match node_type {
+            WbblWebappNodeType::Float => {
+                Ok(ConstantValue::Float(Self::decode_float(data, "value")?))
+            }
+            WbblWebappNodeType::Vec2 => Ok(ConstantValue::Vector2(Self::decode_floats(
+                data,
+                ["x", "y"],
+            )?)),
+            WbblWebappNodeType::Vec3 => Ok(ConstantValue::Vector3(Self::decode_floats(
+                data,
+                ["x", "y", "z"],
+            )?)),
+            WbblWebappNodeType::Vec4 => Ok(ConstantValue::Vector4(Self::decode_floats(
+                data,
+                ["x", "y", "z", "w"],
+            )?)),
+            WbblWebappNodeType::Int => Ok(ConstantValue::Int(Self::decode_int(data, "value")?)),
+            WbblWebappNodeType::Bool => Ok(ConstantValue::Bool(Self::decode_bool(data, "value")?)),
+            WbblWebappNodeType::Color => {
+                let rgba = Self::decode_floats(data, ["r", "g", "b", "a"])?;
+                if rgba.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                    return Err(WbblWebappStoreError::MalformedNodeData);
+                }
+                Ok(ConstantValue::Color(rgba))
+            }
+            _ => Err(WbblWebappStoreError::UnknownNodeType),
+        }
+    }
+}
+
+/// Checks a node's `data` map against the schema of its node type
+pub fn validate_node_data(
+    node_type: WbblWebappNodeType,
+    data: &HashMap<String, Any>,
+) -> Result<(), WbblWebappStoreError> {
+    let keys: &[&str] = match node_type {
+        WbblWebappNodeType::Swizzle => &[VectorComponent::DATA_KEY],
+        _ => ConstantValue::data_keys(node_type),
+    };
+    if data.keys().any(|k| !keys.contains(&k.as_str())) {
+        return Err(WbblWebappStoreError::UnknownNodeDataKey);
+    }
+    Node::node_type_from_webapp_node(node_type, data)?;
+    Ok(())
+}
+
+/// The type a port of a node with the given type and `data` takes before any narrowing
+pub fn get_declared_port_type(
+    node_type: WbblWebappNodeType,
+    data: &HashMap<String, Any>,
+    port: &PortId,
+) -> Result<Option<AbstractDataType>, WbblWebappStoreError> {
+    let node_type = Node::node_type_from_webapp_node(node_type, data)?;
+    let node_id = match port {
+        PortId::Input(id) => id.node_id,
+        PortId::Output(id) => id.node_id,
+    };
+    let node = Node {
+        id: node_id,
+        input_port_count: node_type.input_port_count(&[], &[]),
+        output_port_count: node_type.output_port_count(&[], &[]),
+        node_type,
+    };
+    let declared_type = match port {
+        PortId::Input(id) => node
+            .input_ports(&[])
+            .into_iter()
+            .find(|p| p.id == *id)
+            .map(|p| p.abstract_data_type),
+        PortId::Output(id) => node
+            .output_ports(&[])
+            .into_iter()
+            .find(|p| p.id == *id)
+            .map(|p| p.abstract_data_type),
+    };
+    Ok(declared_type.map(|t| narrow_declared_port_type(&node, port, t)))
+}
+
+// Constraints of the node that only involve this port (such as the width of a swizzle's output)
+// hold regardless of what it's connected to, so they can narrow the type it declares
+fn narrow_declared_port_type(
+    node: &Node,
+    port: &PortId,
+    declared_type: AbstractDataType,
+) -> AbstractDataType {
+    let mut assignments: im::HashMap<PortId, AbstractDataType> = im::HashMap::new();
+    let mut domains = im::HashMap::from(vec![(
+        port.clone(),
+        Rc::new(declared_type.get_abstract_domain()),
+    )]);
+    let only_this_port = HashSet::from([port.clone()]);
+    for constraint in node.constraints() {
+        if constraint.get_affected_ports() == only_this_port {
+            if let ConstraintApplicationResult::Contradiction =
+                constraint.apply(&mut assignments, &mut domains)
+            {
+                return declared_type;
+            }
+        }
+    }
+    assignments.get(port).copied().unwrap_or(declared_type)
+}
+
+/// How many (input, output) ports a node with the given type and `data` has
+pub fn get_declared_port_counts(
+    node_type: WbblWebappNodeType,
+    data: &HashMap<String, Any>,
+) -> Result<(u8, u8), WbblWebappStoreError> {
+    let node_type = Node::node_type_from_webapp_node(node_type, data)?;
+    Ok((
+        node_type.input_port_count(&[], &[]),
+        node_type.output_port_count(&[], &[]),
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorComponent {
+    X,
+    Y,
+    Z,
+    W,
+}
+
+impl VectorComponent {
+    /// Key of the swizzle pattern in a swizzle node's `data` map
+    pub const DATA_KEY: &'static str = "pattern";
+
+    pub fn index(&self) -> u32 {
+        match self {
+            VectorComponent::X => 0,
+            VectorComponent::Y => 1,
+            VectorComponent::Z => 2,
+            VectorComponent::W => 3,
+        }
+    }
+
+    /// Parses patterns such as `"xzy"` or `"bgra"`, which must have between one and four components
+    pub fn parse_pattern(pattern: &str) -> Option<Vec<VectorComponent>> {
+        let components: Option<Vec<VectorComponent>> = pattern
+            .chars()
+            .map(|c| match c {
+                'x' | 'r' => Some(VectorComponent::X),
+                'y' | 'g' => Some(VectorComponent::Y),
+                'z' | 'b' => Some(VectorComponent::Z),
+                'w' | 'a' => Some(VectorComponent::W),
+                _ => None,
+            })
+            .collect();
+        components.filter(|c| (1..=4).contains(&c.len()))
+    }
+
+    fn decode(data: &HashMap<String, Any>) -> Result<Vec<VectorComponent>, WbblWebappStoreError> {
+        match data.get(Self::DATA_KEY) {
+            Some(Any::String(pattern)) => {
+                Self::parse_pattern(pattern).ok_or(WbblWebappStoreError::MalformedNodeData)
+            }
+            _ => Err(WbblWebappStoreError::MalformedNodeData),
+        }
+    }
+
+    fn composite_size(component_count: usize) -> CompositeSize {
+        match component_count {
+            1 => CompositeSize::S1,
+            2 => CompositeSize::S2,
+            3 => CompositeSize::S3,
+            _ => CompositeSize::S4,
+        }
+    }
+
+    /// The smallest input that has every component in the pattern
+    pub fn min_composite_size(pattern: &[VectorComponent]) -> CompositeSize {
+        let max_index = pattern.iter().map(|c| c.index()).max().unwrap_or(0);
+        Self::composite_size(max_index as usize + 1)
+    }
+
+    pub fn output_composite_size(pattern: &[VectorComponent]) -> CompositeSize {
+        Self::composite_size(pattern.len())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NodeType {
     Output,
     Slab,
     Preview,
     BinaryOperation(BinaryOperation),
+    UnaryOperation(UnaryOperation),
+    NaryOperation(NaryOperation),
     BuiltIn(BuiltIn),
+    Constant(ConstantValue),
+    /// Outputs the seconds elapsed, the seconds since the previous frame and the sine of the
+    /// seconds elapsed
+    Time,
+    Split,
+    /// Concatenates the components of both inputs
+    Append,
+    Swizzle(Vec<VectorComponent>),
     Junction,
     Frame,
 }
@@ -426,10 +1189,17 @@ impl NodeType {
     pub fn input_port_count(&self, _incoming_edges: &[&Edge], _outgoing_edges: &[&Edge]) -> u8 {
         match self {
             NodeType::Output => 1,
-            NodeType::Slab => 0,
+            NodeType::Slab => slab_inputs::PORT_TYPES.len() as u8,
             NodeType::Preview => 1,
             NodeType::BinaryOperation(_) => 2,
+            NodeType::UnaryOperation(_) => 1,
+            NodeType::NaryOperation(op) => op.input_port_types().len() as u8,
             NodeType::BuiltIn(_) => 0,
+            NodeType::Constant(_) => 0,
+            NodeType::Time => 0,
+            NodeType::Split => 1,
+            NodeType::Append => 2,
+            NodeType::Swizzle(_) => 1,
             NodeType::Junction => 1,
             Self::Frame => 0,
         }
@@ -441,7 +1211,14 @@ impl NodeType {
             NodeType::Slab => 1,
             NodeType::Preview => 0,
             NodeType::BinaryOperation(_) => 1,
+            NodeType::UnaryOperation(_) => 1,
+            NodeType::NaryOperation(_) => 1,
             NodeType::BuiltIn(_) => 1,
+            NodeType::Constant(_) => 1,
+            NodeType::Time => 3,
+            NodeType::Split => 4,
+            NodeType::Append => 1,
+            NodeType::Swizzle(_) => 1,
             NodeType::Junction => 1,
             Self::Frame => 0,
         }
@@ -471,9 +1248,9 @@ impl Node {
 
     fn node_type_from_webapp_node(
         node: WbblWebappNodeType,
-        _data: &HashMap<String, Any>,
-    ) -> NodeType {
-        match node {
+        data: &HashMap<String, Any>,
+    ) -> Result<NodeType, WbblWebappStoreError> {
+        Ok(match node {
             WbblWebappNodeType::Output => NodeType::Output,
             WbblWebappNodeType::Slab => NodeType::Slab,
             WbblWebappNodeType::Preview => NodeType::Preview,
@@ -522,6 +1299,33 @@ impl Node {
             WbblWebappNodeType::ShiftRight => {
                 NodeType::BinaryOperation(crate::graph_types::BinaryOperation::ShiftRight)
             }
+            WbblWebappNodeType::Negate => NodeType::UnaryOperation(UnaryOperation::Negate),
+            WbblWebappNodeType::Not => NodeType::UnaryOperation(UnaryOperation::Not),
+            WbblWebappNodeType::Abs => NodeType::UnaryOperation(UnaryOperation::Abs),
+            WbblWebappNodeType::Sign => NodeType::UnaryOperation(UnaryOperation::Sign),
+            WbblWebappNodeType::Floor => NodeType::UnaryOperation(UnaryOperation::Floor),
+            WbblWebappNodeType::Ceil => NodeType::UnaryOperation(UnaryOperation::Ceil),
+            WbblWebappNodeType::Round => NodeType::UnaryOperation(UnaryOperation::Round),
+            WbblWebappNodeType::Fract => NodeType::UnaryOperation(UnaryOperation::Fract),
+            WbblWebappNodeType::Trunc => NodeType::UnaryOperation(UnaryOperation::Trunc),
+            WbblWebappNodeType::Sqrt => NodeType::UnaryOperation(UnaryOperation::Sqrt),
+            WbblWebappNodeType::Exp => NodeType::UnaryOperation(UnaryOperation::Exp),
+            WbblWebappNodeType::Log => NodeType::UnaryOperation(UnaryOperation::Log),
+            WbblWebappNodeType::Sin => NodeType::UnaryOperation(UnaryOperation::Sin),
+            WbblWebappNodeType::Cos => NodeType::UnaryOperation(UnaryOperation::Cos),
+            WbblWebappNodeType::Tan => NodeType::UnaryOperation(UnaryOperation::Tan),
+            WbblWebappNodeType::Asin => NodeType::UnaryOperation(UnaryOperation::Asin),
+            WbblWebappNodeType::Acos => NodeType::UnaryOperation(UnaryOperation::Acos),
+            WbblWebappNodeType::Atan => NodeType::UnaryOperation(UnaryOperation::Atan),
+            WbblWebappNodeType::Saturate => NodeType::UnaryOperation(UnaryOperation::Saturate),
+            WbblWebappNodeType::Length => NodeType::UnaryOperation(UnaryOperation::Length),
+            WbblWebappNodeType::Normalize => NodeType::UnaryOperation(UnaryOperation::Normalize),
+            WbblWebappNodeType::Mix => NodeType::NaryOperation(NaryOperation::Mix),
+            WbblWebappNodeType::Clamp => NodeType::NaryOperation(NaryOperation::Clamp),
+            WbblWebappNodeType::Smoothstep => NodeType::NaryOperation(NaryOperation::Smoothstep),
+            WbblWebappNodeType::Step => NodeType::NaryOperation(NaryOperation::Step),
+            WbblWebappNodeType::Fma => NodeType::NaryOperation(NaryOperation::Fma),
+            WbblWebappNodeType::Select => NodeType::NaryOperation(NaryOperation::Select),
             WbblWebappNodeType::WorldPosition => {
                 NodeType::BuiltIn(crate::graph_types::BuiltIn::WorldPosition)
             }
@@ -543,8 +1347,19 @@ impl Node {
             WbblWebappNodeType::TexCoord2 => {
                 NodeType::BuiltIn(crate::graph_types::BuiltIn::TextureCoordinate2)
             }
+            WbblWebappNodeType::Float
+            | WbblWebappNodeType::Vec2
+            | WbblWebappNodeType::Vec3
+            | WbblWebappNodeType::Vec4
+            | WbblWebappNodeType::Int
+            | WbblWebappNodeType::Bool
+            | WbblWebappNodeType::Color => NodeType::Constant(ConstantValue::decode(node, data)?),
+            WbblWebappNodeType::Time => NodeType::Time,
+            WbblWebappNodeType::Split => NodeType::Split,
+            WbblWebappNodeType::Append => NodeType::Append,
+            WbblWebappNodeType::Swizzle => NodeType::Swizzle(VectorComponent::decode(data)?),
             WbblWebappNodeType::Junction => NodeType::Junction,
-        }
+        })
     }
 
     pub fn insert_new<Txn: ReadTxn>(
@@ -561,7 +1376,7 @@ impl Node {
             return Err(WbblWebappStoreError::UnknownNodeType);
         }
         let node_transfer_type = node_transfer_type.unwrap();
-        let node_type = Self::node_type_from_webapp_node(node_transfer_type, &data);
+        let node_type = Self::node_type_from_webapp_node(node_transfer_type, &data)?;
         let input_port_count = node_type.input_port_count(&[], &[]);
         let output_port_count = node_type.output_port_count(&[], &[]);
         let node = Node {
@@ -598,105 +1413,8 @@ impl Node {
             return Err(WbblWebappStoreError::UnknownNodeType);
         }
         let node_transfer_type = node_transfer_type.unwrap();
-        if let Some(prev_node) = graph.nodes.get(&id) {
-            let prev_input_port_count = prev_node.input_port_count;
-            let prev_output_port_count = prev_node.input_port_count;
-            let mut incoming_edges: Vec<&Edge> = Vec::new();
-            let mut outgoing_edges: Vec<&Edge> = Vec::new();
-
-            for i in 0..prev_input_port_count {
-                if let Some(input_port) = graph.input_ports.get(&InputPortId {
-                    node_id: prev_node.id,
-                    port_index: i,
-                }) {
-                    if let Some(Some(incoming_edge)) =
-                        input_port.incoming_edge.map(|x| graph.edges.get(&x))
-                    {
-                        incoming_edges.push(incoming_edge);
-                    }
-                }
-            }
-            for i in 0..prev_output_port_count {
-                if let Some(output_port) = graph.output_ports.get(&OutputPortId {
-                    node_id: prev_node.id,
-                    port_index: i,
-                }) {
-                    for edge_id in output_port.outgoing_edges.iter() {
-                        if let Some(outgoing_edge) = graph.edges.get(edge_id) {
-                            outgoing_edges.push(outgoing_edge);
-                        }
-                    }
-                }
-            }
-
-            let node_type = Self::node_type_from_webapp_node(node_transfer_type, &data);
-
-            let new_input_port_count = node_type.input_port_count(&incoming_edges, &outgoing_edges);
-            let new_output_port_count =
-                node_type.output_port_count(&incoming_edges, &outgoing_edges);
-
-            if new_input_port_count < prev_input_port_count {
-                for i in new_input_port_count..prev_input_port_count {
-                    let port_id = InputPortId {
-                        node_id: id,
-                        port_index: i,
-                    };
-                    if let Some(port) = graph.input_ports.remove(&port_id) {
-                        if let Some(edge_id) = port.incoming_edge {
-                            graph.edges.remove(&edge_id);
-                        }
-                    }
-                }
-            }
-
-            if new_output_port_count < prev_output_port_count {
-                for i in new_output_port_count..prev_output_port_count {
-                    let port_id = OutputPortId {
-                        node_id: id,
-                        port_index: i,
-                    };
-                    if let Some(port) = graph.output_ports.remove(&port_id) {
-                        for edge_id in port.outgoing_edges.iter() {
-                            graph.edges.remove(edge_id);
-                        }
-                    }
-                }
-            }
-
-            let node = Node {
-                id,
-                input_port_count: new_input_port_count,
-                output_port_count: new_output_port_count,
-                node_type,
-            };
-            for mut port in node.input_ports(&[]) {
-                if let Some(prev_port) = graph.input_ports.remove(&port.id) {
-                    if prev_port.abstract_data_type == port.abstract_data_type {
-                        // If abstract data type is same, keep edge
-                        port.incoming_edge = prev_port.incoming_edge;
-                    }
-                    graph.input_ports.insert(port.id.clone(), port);
-                } else {
-                    graph.input_ports.insert(port.id.clone(), port);
-                }
-            }
-            for mut port in node.output_ports(&[]) {
-                if let Some(prev_port) = graph.output_ports.remove(&port.id) {
-                    if prev_port.abstract_data_type == port.abstract_data_type {
-                        // If abstract data type is same, keep edge
-                        port.outgoing_edges = prev_port.outgoing_edges;
-                    }
-                    graph.output_ports.insert(port.id.clone(), port);
-                } else {
-                    graph.output_ports.insert(port.id.clone(), port);
-                }
-            }
-            graph.nodes.insert(node.id, node);
-            graph.dirty = true;
-            Ok(())
-        } else {
-            Err(WbblWebappStoreError::NotFound)
-        }
+        let node_type = Self::node_type_from_webapp_node(node_transfer_type, &data)?;
+        graph.update_node_type(id, node_type)
     }
 }
 
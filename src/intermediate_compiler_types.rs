@@ -1,11 +1,17 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
+use serde::{Deserialize, Serialize};
 use wgpu::naga::Module;
+use wgpu::naga::Span;
 use wgpu::naga::StorageFormat;
 
+use crate::data_types::CompositeSize;
 use crate::data_types::ComputationDomain;
 use crate::data_types::ComputeOutputType;
+use crate::data_types::ConcreteDataType;
 use crate::data_types::Dimensionality;
+use crate::graph_transfer_types::{string_to_uuid, uuid_to_string};
+use crate::graph_types::PortId;
 
 pub struct ComputeRasterizerShader {
     pub primary_shader: Module,
@@ -26,6 +32,48 @@ pub struct ComputeShader {
 pub struct VertexFragmentShader {
     pub vertex: Module,
     pub fragment: Module,
+    pub fragment_span_origins: SpanOrigins,
+}
+
+/// The ports that naga objects generated from the graph originate from. Node ids don't fit in
+/// a span, so generated spans index into this table instead. Spans made by `make_span` are
+/// empty, so they never collide with these
+#[derive(Debug, Default, Clone)]
+pub struct SpanOrigins {
+    origins: Vec<PortId>,
+    indices: HashMap<PortId, u32>,
+}
+
+impl SpanOrigins {
+    pub fn span_for(&mut self, port: &PortId) -> Span {
+        let index = match self.indices.get(port) {
+            Some(index) => *index,
+            None => {
+                let index = self.origins.len() as u32;
+                self.origins.push(port.clone());
+                self.indices.insert(port.clone(), index);
+                index
+            }
+        };
+        Span::new(index, index + 1)
+    }
+
+    pub fn get(&self, span: Span) -> Option<&PortId> {
+        let range = span.to_range()?;
+        if range.len() != 1 {
+            return None;
+        }
+        self.origins.get(range.start)
+    }
+}
+
+/// A problem with the code generated for a node, shown on the node in the graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeDiagnostic {
+    #[serde(serialize_with = "uuid_to_string", deserialize_with = "string_to_uuid")]
+    pub node_id: u128,
+    pub port: Option<PortId>,
+    pub message: String,
 }
 
 pub enum Shader {
@@ -42,6 +90,81 @@ pub struct Stage {
     pub dependants: HashSet<u128>,
 }
 
+impl Stage {
+    /// Time varying stages have to be re-evaluated every frame, rather than only when the graph changes
+    pub fn is_time_varying(&self) -> bool {
+        self.domain.contains(&ComputationDomain::TimeVarying)
+    }
+}
+
 pub struct BaseSizeMultiplier(pub f32);
 
+/// How a preview node shows the value flowing into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreviewVisualisation {
+    /// Scalars are shown in greyscale
+    Greyscale,
+    /// Vectors are remapped from [-1, 1] to colours, so that directions are visible
+    RemappedColour,
+    /// True is shown flat, and false as a checkerboard
+    Checker,
+    /// Materials are shaded as they would be at the output
+    Material,
+    /// Textures and procedural fields are sampled at the mesh's texture coordinates, so they're
+    /// laid out flat over its UVs
+    Flat2D,
+}
+
+impl PreviewVisualisation {
+    /// Picks how a value of type `t` is shown in a preview
+    pub fn for_type(t: ConcreteDataType) -> PreviewVisualisation {
+        match t {
+            ConcreteDataType::Float(CompositeSize::S1) | ConcreteDataType::Int => {
+                PreviewVisualisation::Greyscale
+            }
+            ConcreteDataType::Float(_) => PreviewVisualisation::RemappedColour,
+            ConcreteDataType::Bool => PreviewVisualisation::Checker,
+            ConcreteDataType::Texture(_, _) | ConcreteDataType::ProceduralField(_, _) => {
+                PreviewVisualisation::Flat2D
+            }
+            ConcreteDataType::SlabMaterial => PreviewVisualisation::Material,
+        }
+    }
+}
+
 pub struct IntermediateOutput(pub Vec<Stage>);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum CompilationError {
+    MissingNode(u128),
+    MissingConcreteType(PortId),
+    MissingExpression(PortId),
+    UnsupportedType(ConcreteDataType),
+}
+
+impl CompilationError {
+    /// Errors that can't be traced to a port are attributed to `root`, the node being compiled
+    pub fn to_node_diagnostic(&self, root: u128) -> NodeDiagnostic {
+        let (node_id, port, message) = match self {
+            CompilationError::MissingNode(node_id) => (*node_id, None, "Missing node".to_owned()),
+            CompilationError::MissingConcreteType(port) => (
+                port.node_id(),
+                Some(port.clone()),
+                "Type could not be determined".to_owned(),
+            ),
+            CompilationError::MissingExpression(port) => (
+                port.node_id(),
+                Some(port.clone()),
+                "Value could not be generated".to_owned(),
+            ),
+            CompilationError::UnsupportedType(t) => {
+                (root, None, format!("{:?} values are not supported yet", t))
+            }
+        };
+        NodeDiagnostic {
+            node_id,
+            port,
+            message,
+        }
+    }
+}
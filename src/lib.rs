@@ -10,8 +10,11 @@ pub mod constraint_solver_constraints;
 pub mod convex_hull;
 pub mod data_types;
 pub(crate) mod dot_converter;
+pub mod fragment_shader;
 pub mod gltf_encoder;
 pub mod graph_functions;
+pub mod graph_layout;
+pub mod graph_presence;
 pub mod graph_transfer_types;
 pub mod graph_types;
 pub mod intermediate_compiler_types;
@@ -19,7 +22,10 @@ pub mod model_scene_file_abstractions;
 pub(crate) mod node_display_data;
 pub mod preview_renderer;
 pub mod shader_layouts;
+pub mod spatial_index;
 pub mod store_errors;
+pub mod sync_room;
+pub mod sync_transport;
 pub mod test_fragment_shader;
 pub(crate) mod utils;
 pub mod vertex_shader;
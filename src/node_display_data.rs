@@ -2,6 +2,13 @@ use crate::graph_transfer_types::WbblWebappNodeType;
 
 const BINARY_NODE_WIDTH: f64 = 150.0;
 const BINARY_NODE_HEIGHT: f64 = 100.0;
+const NARY_NODE_WIDTH: f64 = 150.0;
+const NARY_NODE_HEIGHT: f64 = 120.0;
+const UNARY_NODE_WIDTH: f64 = 150.0;
+const UNARY_NODE_HEIGHT: f64 = 80.0;
+const CONSTANT_NODE_WIDTH: f64 = 200.0;
+const CONSTANT_NODE_BASE_HEIGHT: f64 = 70.0;
+const CONSTANT_NODE_FIELD_HEIGHT: f64 = 30.0;
 const BUILTIN_NODE_WIDTH: f64 = 150.0;
 const BUILTIN_NODE_HEIGHT: f64 = 100.0;
 pub const PORT_SIZE: f64 = 14.0;
@@ -15,7 +22,7 @@ pub fn get_node_dimensions(
 ) -> (f64, f64) {
     match node_type {
         WbblWebappNodeType::Output => (315.0, 315.0),
-        WbblWebappNodeType::Slab => (200.0, 200.0),
+        WbblWebappNodeType::Slab => (200.0, 220.0),
         WbblWebappNodeType::Preview => (150.0, 170.0),
         WbblWebappNodeType::Add => (BINARY_NODE_WIDTH, BINARY_NODE_HEIGHT),
         WbblWebappNodeType::Subtract => (BINARY_NODE_WIDTH, BINARY_NODE_HEIGHT),
@@ -32,6 +39,65 @@ pub fn get_node_dimensions(
         WbblWebappNodeType::Or => (BINARY_NODE_WIDTH, BINARY_NODE_HEIGHT),
         WbblWebappNodeType::ShiftLeft => (BINARY_NODE_WIDTH, BINARY_NODE_HEIGHT),
         WbblWebappNodeType::ShiftRight => (BINARY_NODE_WIDTH, BINARY_NODE_HEIGHT),
+        WbblWebappNodeType::Negate => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Not => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Abs => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Sign => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Floor => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Ceil => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Round => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Fract => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Trunc => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Sqrt => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Exp => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Log => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Sin => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Cos => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Tan => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Asin => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Acos => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Atan => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Saturate => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Length => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Normalize => (UNARY_NODE_WIDTH, UNARY_NODE_HEIGHT),
+        WbblWebappNodeType::Mix => (NARY_NODE_WIDTH, NARY_NODE_HEIGHT),
+        WbblWebappNodeType::Clamp => (NARY_NODE_WIDTH, NARY_NODE_HEIGHT),
+        WbblWebappNodeType::Smoothstep => (NARY_NODE_WIDTH, NARY_NODE_HEIGHT),
+        WbblWebappNodeType::Step => (BINARY_NODE_WIDTH, BINARY_NODE_HEIGHT),
+        WbblWebappNodeType::Fma => (NARY_NODE_WIDTH, NARY_NODE_HEIGHT),
+        WbblWebappNodeType::Select => (NARY_NODE_WIDTH, NARY_NODE_HEIGHT),
+        WbblWebappNodeType::Float => (
+            CONSTANT_NODE_WIDTH,
+            CONSTANT_NODE_BASE_HEIGHT + CONSTANT_NODE_FIELD_HEIGHT,
+        ),
+        WbblWebappNodeType::Vec2 => (
+            CONSTANT_NODE_WIDTH,
+            CONSTANT_NODE_BASE_HEIGHT + CONSTANT_NODE_FIELD_HEIGHT * 2.0,
+        ),
+        WbblWebappNodeType::Vec3 => (
+            CONSTANT_NODE_WIDTH,
+            CONSTANT_NODE_BASE_HEIGHT + CONSTANT_NODE_FIELD_HEIGHT * 3.0,
+        ),
+        WbblWebappNodeType::Vec4 => (
+            CONSTANT_NODE_WIDTH,
+            CONSTANT_NODE_BASE_HEIGHT + CONSTANT_NODE_FIELD_HEIGHT * 4.0,
+        ),
+        WbblWebappNodeType::Int => (
+            CONSTANT_NODE_WIDTH,
+            CONSTANT_NODE_BASE_HEIGHT + CONSTANT_NODE_FIELD_HEIGHT,
+        ),
+        WbblWebappNodeType::Bool => (
+            CONSTANT_NODE_WIDTH,
+            CONSTANT_NODE_BASE_HEIGHT + CONSTANT_NODE_FIELD_HEIGHT,
+        ),
+        WbblWebappNodeType::Color => (
+            CONSTANT_NODE_WIDTH,
+            CONSTANT_NODE_BASE_HEIGHT + CONSTANT_NODE_FIELD_HEIGHT * 4.0,
+        ),
+        WbblWebappNodeType::Split => (150.0, 120.0),
+        WbblWebappNodeType::Append => (BINARY_NODE_WIDTH, BINARY_NODE_HEIGHT),
+        WbblWebappNodeType::Swizzle => (150.0, 110.0),
+        WbblWebappNodeType::Time => (150.0, 100.0),
         WbblWebappNodeType::WorldPosition => (BUILTIN_NODE_WIDTH, BUILTIN_NODE_HEIGHT),
         WbblWebappNodeType::ClipPosition => (BUILTIN_NODE_WIDTH, BUILTIN_NODE_HEIGHT),
         WbblWebappNodeType::WorldNormal => (BUILTIN_NODE_WIDTH, BUILTIN_NODE_HEIGHT),
@@ -182,14 +182,83 @@ impl SharedPreviewRendererResources {
     }
 }
 
+fn create_render_pipeline(
+    shared_resources: &SharedPreviewRendererResources,
+    swapchain_format: wgpu::TextureFormat,
+    vertex_shader: wgpu::naga::Module,
+    fragment_shader: wgpu::naga::Module,
+) -> wgpu::RenderPipeline {
+    let vertex_shader =
+        shared_resources
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Naga(Cow::Owned(vertex_shader)),
+            });
+
+    let fragment_shader =
+        shared_resources
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Naga(Cow::Owned(fragment_shader)),
+            });
+
+    let pipeline_layout =
+        shared_resources
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[
+                    &shared_resources.vertices_layout,
+                    &shared_resources.frame_data_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+    shared_resources
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vertex_shader,
+                entry_point: "vertexMain",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &fragment_shader,
+                entry_point: "fragmentMain",
+                targets: &[Some(swapchain_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Cw,
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+}
+
 pub struct PreviewRendererResources {
+    pub canvas: OffscreenCanvas,
     pub surface: Rc<wgpu::Surface<'static>>,
+    pub swapchain_format: wgpu::TextureFormat,
     pub render_pipeline: Rc<wgpu::RenderPipeline>,
     pub geometry_buffer: Rc<wgpu::Buffer>,
     pub geometry: Rc<EncodedSceneFile>,
     pub frame: Rc<Frame>,
     pub width: u32,
     pub height: u32,
+    // Whether the preview's shader reads the time, so has to be redrawn every frame
+    pub time_varying: bool,
+    pub rendered: bool,
 }
 
 impl PreviewRendererResources {
@@ -200,13 +269,14 @@ impl PreviewRendererResources {
         canvas: OffscreenCanvas,
         vertex_shader: wgpu::naga::Module,
         fragment_shader: wgpu::naga::Module,
+        time_varying: bool,
     ) -> Result<PreviewRendererResources, Box<dyn Error>> {
         let width = canvas.width();
         let height = canvas.height();
 
         let surface: Rc<wgpu::Surface> = shared_resources
             .instance
-            .create_surface(SurfaceTarget::OffscreenCanvas(canvas))?
+            .create_surface(SurfaceTarget::OffscreenCanvas(canvas.clone()))?
             .into();
         let (geometry, geometry_buffer) = match (
             shared_resources.geometry.get(&geometry),
@@ -216,79 +286,76 @@ impl PreviewRendererResources {
             _ => Err(PreviewRendererError::GeometryTypeNotFound),
         }?;
 
-        let vertex_shader =
-            shared_resources
-                .device
-                .create_shader_module(wgpu::ShaderModuleDescriptor {
-                    label: None,
-                    source: wgpu::ShaderSource::Naga(Cow::Owned(vertex_shader)),
-                });
-
-        let fragment_shader =
-            shared_resources
-                .device
-                .create_shader_module(wgpu::ShaderModuleDescriptor {
-                    label: None,
-                    source: wgpu::ShaderSource::Naga(Cow::Owned(fragment_shader)),
-                });
-
-        let pipeline_layout =
-            shared_resources
-                .device
-                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: None,
-                    bind_group_layouts: &[
-                        &shared_resources.vertices_layout,
-                        &shared_resources.frame_data_layout,
-                    ],
-                    push_constant_ranges: &[],
-                });
-
         let swapchain_capabilities = surface.get_capabilities(&shared_resources.adapter);
         let swapchain_format = swapchain_capabilities.formats[0];
 
-        let render_pipeline = shared_resources
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &vertex_shader,
-                    entry_point: "vertexMain",
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &fragment_shader,
-                    entry_point: "fragmentMain",
-                    targets: &[Some(swapchain_format.into())],
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Cw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    unclipped_depth: false,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    conservative: false,
-                },
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            })
-            .into();
+        let render_pipeline = create_render_pipeline(
+            &shared_resources,
+            swapchain_format,
+            vertex_shader,
+            fragment_shader,
+        )
+        .into();
 
         Ok(PreviewRendererResources {
+            canvas,
             surface,
+            swapchain_format,
             render_pipeline,
             geometry_buffer,
             geometry,
             frame: Frame::default(width, height).into(),
             width,
             height,
+            time_varying,
+            rendered: false,
         })
     }
 
-    pub fn render(&mut self, shared_resources: Rc<SharedPreviewRendererResources>) {
+    /// Swaps in newly compiled shaders. The old pipeline is only replaced once the new one has
+    /// been created, so the preview keeps its last frame until it is redrawn with the new shaders
+    pub fn replace_shaders(
+        &mut self,
+        shared_resources: &SharedPreviewRendererResources,
+        vertex_shader: wgpu::naga::Module,
+        fragment_shader: wgpu::naga::Module,
+        time_varying: bool,
+    ) {
+        self.render_pipeline = create_render_pipeline(
+            shared_resources,
+            self.swapchain_format,
+            vertex_shader,
+            fragment_shader,
+        )
+        .into();
+        if time_varying && !self.time_varying {
+            Rc::make_mut(&mut self.frame).reset_time();
+        }
+        self.time_varying = time_varying;
+        self.rendered = false;
+    }
+
+    pub fn needs_render(&self) -> bool {
+        self.time_varying || !self.rendered
+    }
+
+    /// Resizes the canvas the preview is drawn to. Resizing clears the canvas, so the preview is
+    /// redrawn whether or not it's time varying
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) || width == 0 || height == 0 {
+            return;
+        }
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        self.width = width;
+        self.height = height;
+        let mut frame = Frame::default(width, height);
+        frame.time = self.frame.time;
+        self.frame = frame.into();
+        self.rendered = false;
+    }
+
+    pub fn render(&mut self, shared_resources: Rc<SharedPreviewRendererResources>, seconds: f32) {
         // Create the logical device and command queue
 
         let model_matrix = Mat4::default();
@@ -308,6 +375,8 @@ impl PreviewRendererResources {
                     usage: BufferUsages::STORAGE,
                 });
 
+        Rc::make_mut(&mut self.frame).set_time(seconds);
+
         let frame_data_buffer = shared_resources
             .device
             .create_buffer_init(&BufferInitDescriptor {
@@ -377,10 +446,15 @@ impl PreviewRendererResources {
 
         self.surface.configure(&shared_resources.device, &config);
 
-        let frame = self
-            .surface
-            .get_current_texture()
-            .expect("Failed to acquire next swap chain texture");
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            // The surface is configured again before the next attempt, which is usually enough
+            // for it to recover
+            Err(_) => {
+                self.rendered = false;
+                return;
+            }
+        };
 
         let view = frame
             .texture
@@ -427,5 +501,6 @@ impl PreviewRendererResources {
 
         shared_resources.queue.submit(Some(encoder.finish()));
         frame.present();
+        self.rendered = true;
     }
 }
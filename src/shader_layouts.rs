@@ -85,7 +85,12 @@ pub mod frame {
     pub const VIEW_MATRIX_INV_INDEX: u32 = 3;
     pub const DEPTH_UNPROJECT_INDEX: u32 = 4;
     pub const SCREEN_TO_VIEW_SPACE_INDEX: u32 = 5;
-    pub const FRAME_STRIDE: u32 = 288;
+    pub const TIME_INDEX: u32 = 6;
+    pub const FRAME_STRIDE: u32 = 304;
+
+    pub const TIME_SECONDS_INDEX: u32 = 0;
+    pub const TIME_DELTA_INDEX: u32 = 1;
+    pub const TIME_SINE_INDEX: u32 = 2;
 
     #[repr(C)]
     #[repr(align(16))]
@@ -98,6 +103,8 @@ pub mod frame {
         pub view_matrix_inv: Mat4,
         pub depth_unproject: Vec2,
         pub screen_to_view_space: Vec3A,
+        // Seconds elapsed, seconds since the previous frame and the sine of the seconds elapsed
+        pub time: Vec3A,
     }
 
     impl Frame {
@@ -144,8 +151,24 @@ pub mod frame {
                     y: (-near * far) / (near - far),
                 },
                 screen_to_view_space: screen_to_view_space.into(),
+                time: Vec3A::ZERO,
             }
         }
+
+        /// Forgets the time of the previous frame, so that the next delta is zero rather than the
+        /// time since the preview was last drawn
+        pub fn reset_time(&mut self) {
+            self.time = Vec3A::ZERO;
+        }
+
+        pub fn set_time(&mut self, seconds: f32) {
+            let delta = if self.time.x > 0.0 {
+                (seconds - self.time.x).max(0.0)
+            } else {
+                0.0
+            };
+            self.time = Vec3A::new(seconds, delta, seconds.sin());
+        }
     }
 
     unsafe impl Pod for Frame {}
@@ -196,6 +219,12 @@ pub mod frame {
                         binding: None,
                         offset: 272,
                     },
+                    StructMember {
+                        name: Some("time".to_owned()),
+                        ty: type_float32_3,
+                        binding: None,
+                        offset: 288,
+                    },
                 ],
                 span: FRAME_STRIDE,
             },
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+use mint::Point2;
+use rstar::{RTree, RTreeObject, AABB};
+
+use crate::graph_transfer_types::{WbblWebappGraphEntity, WbblWebappGraphEntityId};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialIndexEntry {
+    pub id: WbblWebappGraphEntityId,
+    pub envelope: AABB<Point2<f32>>,
+}
+
+impl RTreeObject for SpatialIndexEntry {
+    type Envelope = AABB<Point2<f32>>;
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+/// Bounding boxes of the nodes, edges and groups of a graph, keyed by entity id.
+/// Entries are replaced wholesale on update, so the tree never holds a stale copy of an entity.
+#[derive(Default)]
+pub struct SpatialIndex {
+    tree: RTree<SpatialIndexEntry>,
+    envelopes: HashMap<WbblWebappGraphEntityId, AABB<Point2<f32>>>,
+}
+
+fn rect(min: Vec2, max: Vec2) -> AABB<Point2<f32>> {
+    AABB::from_corners(min.into(), max.into())
+}
+
+fn area(envelope: &AABB<Point2<f32>>) -> f32 {
+    let lower = envelope.lower();
+    let upper = envelope.upper();
+    (upper.x - lower.x) * (upper.y - lower.y)
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.envelopes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envelopes.is_empty()
+    }
+
+    pub fn contains(&self, id: &WbblWebappGraphEntityId) -> bool {
+        self.envelopes.contains_key(id)
+    }
+
+    pub fn get_envelope(&self, id: &WbblWebappGraphEntityId) -> Option<AABB<Point2<f32>>> {
+        self.envelopes.get(id).copied()
+    }
+
+    pub fn insert_or_update(&mut self, entity: &WbblWebappGraphEntity) {
+        self.insert_or_update_envelope(entity.id(), entity.envelope());
+    }
+
+    pub fn insert_or_update_envelope(
+        &mut self,
+        id: WbblWebappGraphEntityId,
+        envelope: AABB<Point2<f32>>,
+    ) {
+        if let Some(prev_envelope) = self.envelopes.insert(id, envelope) {
+            if prev_envelope == envelope {
+                return;
+            }
+            self.tree.remove(&SpatialIndexEntry {
+                id,
+                envelope: prev_envelope,
+            });
+        }
+        self.tree.insert(SpatialIndexEntry { id, envelope });
+    }
+
+    pub fn remove(&mut self, id: &WbblWebappGraphEntityId) -> bool {
+        match self.envelopes.remove(id) {
+            Some(envelope) => self
+                .tree
+                .remove(&SpatialIndexEntry { id: *id, envelope })
+                .is_some(),
+            None => false,
+        }
+    }
+
+    /// Entities whose bounds intersect the rectangle spanned by `min` and `max`
+    pub fn query_rect(&self, min: Vec2, max: Vec2) -> Vec<WbblWebappGraphEntityId> {
+        let mut results: Vec<WbblWebappGraphEntityId> = self
+            .tree
+            .locate_in_envelope_intersecting(&rect(min, max))
+            .map(|entry| entry.id)
+            .collect();
+        results.sort();
+        results
+    }
+
+    /// Entities within the part of the graph visible through a viewport of the given `size`,
+    /// where `screen = graph * zoom + translation`
+    pub fn entities_in_viewport(
+        &self,
+        translation: Vec2,
+        zoom: f32,
+        size: Vec2,
+        margin: f32,
+    ) -> Vec<WbblWebappGraphEntityId> {
+        if zoom <= 0.0 {
+            return vec![];
+        }
+        let min = (-translation) / zoom - Vec2::splat(margin);
+        let max = (size - translation) / zoom + Vec2::splat(margin);
+        self.query_rect(min, max)
+    }
+
+    /// Nodes whose bounds intersect the square of half-width `radius` centred on `point`
+    pub fn nodes_near(&self, point: Vec2, radius: f32) -> Vec<u128> {
+        let radius = Vec2::splat(radius.max(0.0));
+        self.query_rect(point - radius, point + radius)
+            .into_iter()
+            .filter_map(|id| match id {
+                WbblWebappGraphEntityId::NodeId(node_id) => Some(node_id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The node under `point`. Where nodes overlap the smallest one wins, so that junctions
+    /// and other small nodes stay reachable on top of larger ones.
+    pub fn node_at(&self, point: Vec2) -> Option<u128> {
+        self.tree
+            .locate_in_envelope_intersecting(&AABB::from_point(point.into()))
+            .filter_map(|entry| match entry.id {
+                WbblWebappGraphEntityId::NodeId(node_id) => Some((node_id, area(&entry.envelope))),
+                _ => None,
+            })
+            .min_by(|(a_id, a_area), (b_id, b_area)| {
+                a_area.total_cmp(b_area).then_with(|| a_id.cmp(b_id))
+            })
+            .map(|(node_id, _)| node_id)
+    }
+}
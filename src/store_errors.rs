@@ -16,4 +16,11 @@ pub enum WbblWebappStoreError {
     SerializationFailure,
     CannotDeleteOutputNode,
     SubscriptionFailure,
+    MalformedNodeData,
+    UnknownNodeDataKey,
+    WouldCreateCycle,
+    IncompatiblePortTypes,
+    InputAlreadyConnected,
+    MalformedVector,
+    WorkerFailure,
 }
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use yrs::{
+    encoding::read::Cursor,
+    sync::{Awareness, Message, SyncMessage},
+    updates::{
+        decoder::{Decode, DecoderV1},
+        encoder::Encode,
+    },
+    Doc, ReadTxn, StateVector, Transact, Update,
+};
+
+use crate::awareness_sync::SYNC_DONE_MESSAGE_TAG;
+
+/// Identifies a connection to a room. A client may connect more than once over its lifetime
+pub type PeerId = u64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recipient {
+    Peer(PeerId),
+    AllExcept(PeerId),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomMessage {
+    pub recipient: Recipient,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct RoomOutput {
+    pub messages: Vec<RoomMessage>,
+    // Document updates applied while handling the message, to be persisted
+    pub updates: Vec<Vec<u8>>,
+}
+
+impl RoomOutput {
+    fn send(&mut self, recipient: Recipient, message: Message) {
+        self.messages.push(RoomMessage {
+            recipient,
+            data: message.encode_v1(),
+        });
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SyncRoomError {
+    MalformedPayload,
+    FailedToApplyUpdate,
+    UnexpectedMessage,
+}
+
+impl SyncRoomError {
+    /// The reason given when closing the connection, matching those used by the client
+    pub fn close_reason(&self) -> &'static str {
+        match self {
+            SyncRoomError::MalformedPayload => "MALFORMED PAYLOAD",
+            SyncRoomError::FailedToApplyUpdate => "FAILED TO APPLY UPDATE",
+            SyncRoomError::UnexpectedMessage => "UNEXPECTED MESSAGE",
+        }
+    }
+}
+
+/// The server side of the y-sync protocol for a single document
+pub struct SyncRoom {
+    awareness: Awareness,
+    // The awareness client id of each connected peer
+    peers: HashMap<PeerId, u64>,
+}
+
+impl SyncRoom {
+    pub fn new(doc: Doc) -> SyncRoom {
+        SyncRoom {
+            awareness: Awareness::new(doc),
+            peers: HashMap::new(),
+        }
+    }
+
+    pub fn from_updates(updates: &[Vec<u8>]) -> Result<SyncRoom, SyncRoomError> {
+        let doc = Doc::new();
+        {
+            let mut txn = doc.transact_mut();
+            for update in updates {
+                let update =
+                    Update::decode_v1(update).map_err(|_| SyncRoomError::FailedToApplyUpdate)?;
+                txn.apply_update(update);
+            }
+        }
+        Ok(SyncRoom::new(doc))
+    }
+
+    pub fn doc(&self) -> &Doc {
+        self.awareness.doc()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// The whole document as a single update, used to compact the persisted log
+    pub fn encode_state(&self) -> Vec<u8> {
+        self.doc()
+            .transact()
+            .encode_state_as_update_v1(&StateVector::default())
+    }
+
+    pub fn join(&mut self, peer: PeerId, client_id: u64) -> RoomOutput {
+        self.peers.insert(peer, client_id);
+        let mut output = RoomOutput::default();
+        // Ask for anything the client has that the room doesn't, such as offline edits
+        let state_vector = self.doc().transact().state_vector();
+        output.send(
+            Recipient::Peer(peer),
+            Message::Sync(SyncMessage::SyncStep1(state_vector)),
+        );
+        output
+    }
+
+    pub fn leave(&mut self, peer: PeerId) -> RoomOutput {
+        let mut output = RoomOutput::default();
+        if let Some(client_id) = self.peers.remove(&peer) {
+            self.awareness.remove_state(client_id);
+            if let Ok(update) = self.awareness.update_with_clients([client_id]) {
+                output.send(Recipient::AllExcept(peer), Message::Awareness(update));
+            }
+        }
+        output
+    }
+
+    pub fn handle_message(
+        &mut self,
+        peer: PeerId,
+        message: &[u8],
+    ) -> Result<RoomOutput, SyncRoomError> {
+        let mut output = RoomOutput::default();
+        let cursor: Cursor = Cursor::new(message);
+        let mut decoder = DecoderV1::new(cursor);
+        let reader = yrs::sync::protocol::MessageReader::new(&mut decoder);
+
+        for message in reader {
+            match message.map_err(|_| SyncRoomError::MalformedPayload)? {
+                Message::Sync(SyncMessage::SyncStep1(sv)) => {
+                    let update = self.doc().transact().encode_state_as_update_v1(&sv);
+                    output.send(
+                        Recipient::Peer(peer),
+                        Message::Sync(SyncMessage::SyncStep2(update)),
+                    );
+                    // The client holds back its own edits until the initial sync is done
+                    output.send(
+                        Recipient::Peer(peer),
+                        Message::Custom(SYNC_DONE_MESSAGE_TAG, vec![]),
+                    );
+                }
+                Message::Sync(SyncMessage::SyncStep2(update))
+                | Message::Sync(SyncMessage::Update(update)) => {
+                    let decoded = Update::decode_v1(&update)
+                        .map_err(|_| SyncRoomError::FailedToApplyUpdate)?;
+                    // A client answers the room's sync step 1 and resends its queue on reconnect
+                    // whether or not it has anything the room is missing, so only updates that
+                    // change the document are passed on and persisted
+                    let before = self.doc().transact().snapshot();
+                    self.doc().transact_mut().apply_update(decoded);
+                    if self.doc().transact().snapshot() == before {
+                        continue;
+                    }
+                    output.send(
+                        Recipient::AllExcept(peer),
+                        Message::Sync(SyncMessage::Update(update.clone())),
+                    );
+                    output.updates.push(update);
+                }
+                Message::Auth(_) => return Err(SyncRoomError::UnexpectedMessage),
+                Message::AwarenessQuery => {
+                    let update = self
+                        .awareness
+                        .update()
+                        .map_err(|_| SyncRoomError::MalformedPayload)?;
+                    output.send(Recipient::Peer(peer), Message::Awareness(update));
+                }
+                Message::Awareness(update) => {
+                    self.awareness
+                        .apply_update(update.clone())
+                        .map_err(|_| SyncRoomError::MalformedPayload)?;
+                    output.send(Recipient::AllExcept(peer), Message::Awareness(update));
+                }
+                Message::Custom(_, _) => {}
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Updates are persisted as a log of length prefixed entries, so that they can be appended
+/// without rewriting the file
+pub fn encode_update_log_entry(update: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(update.len() + 4);
+    entry.extend_from_slice(&(update.len() as u32).to_le_bytes());
+    entry.extend_from_slice(update);
+    entry
+}
+
+/// Reads the updates in a log. A truncated final entry, as left by an interrupted write,
+/// is ignored
+pub fn decode_update_log(log: &[u8]) -> Vec<Vec<u8>> {
+    let mut updates = Vec::new();
+    let mut remaining = log;
+    while remaining.len() >= 4 {
+        let len =
+            u32::from_le_bytes([remaining[0], remaining[1], remaining[2], remaining[3]]) as usize;
+        if remaining.len() - 4 < len {
+            break;
+        }
+        updates.push(remaining[4..4 + len].to_vec());
+        remaining = &remaining[4 + len..];
+    }
+    updates
+}
@@ -0,0 +1,125 @@
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+};
+
+use web_sys::WebSocket;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SyncTransportError {
+    NotOpen,
+    SendFailed,
+}
+
+/// A message oriented connection to a sync peer, carrying encoded y-sync messages
+pub trait SyncTransport {
+    fn is_open(&self) -> bool;
+    fn send(&self, message: &[u8]) -> Result<(), SyncTransportError>;
+    fn close(&self, code: u16, reason: &str);
+}
+
+pub struct WebsocketTransport {
+    websocket: RefCell<WebSocket>,
+}
+
+impl WebsocketTransport {
+    pub fn new(websocket: WebSocket) -> WebsocketTransport {
+        WebsocketTransport {
+            websocket: RefCell::new(websocket),
+        }
+    }
+
+    pub fn websocket(&self) -> Ref<WebSocket> {
+        self.websocket.borrow()
+    }
+
+    /// Swaps in a new socket after a reconnect, returning the previous one
+    pub fn replace(&self, websocket: WebSocket) -> WebSocket {
+        self.websocket.replace(websocket)
+    }
+}
+
+impl SyncTransport for WebsocketTransport {
+    fn is_open(&self) -> bool {
+        self.websocket.borrow().ready_state() == WebSocket::OPEN
+    }
+
+    fn send(&self, message: &[u8]) -> Result<(), SyncTransportError> {
+        let websocket = self.websocket.borrow();
+        if websocket.ready_state() != WebSocket::OPEN {
+            return Err(SyncTransportError::NotOpen);
+        }
+        websocket
+            .send_with_u8_array(message)
+            .map_err(|_| SyncTransportError::SendFailed)
+    }
+
+    fn close(&self, code: u16, reason: &str) {
+        let _ = self
+            .websocket
+            .borrow()
+            .close_with_code_and_reason(code, reason);
+    }
+}
+
+/// One end of an in-process connection. Sent messages are queued on the other end
+/// until it calls `receive`, so that tests control the order in which peers make progress
+pub struct InMemoryTransport {
+    inbox: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    outbox: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    open: Rc<Cell<bool>>,
+    close_reason: Rc<RefCell<Option<(u16, String)>>>,
+}
+
+impl InMemoryTransport {
+    pub fn pair() -> (InMemoryTransport, InMemoryTransport) {
+        let a_to_b = Rc::new(RefCell::new(VecDeque::new()));
+        let b_to_a = Rc::new(RefCell::new(VecDeque::new()));
+        let open = Rc::new(Cell::new(true));
+        let close_reason = Rc::new(RefCell::new(None));
+        (
+            InMemoryTransport {
+                inbox: b_to_a.clone(),
+                outbox: a_to_b.clone(),
+                open: open.clone(),
+                close_reason: close_reason.clone(),
+            },
+            InMemoryTransport {
+                inbox: a_to_b,
+                outbox: b_to_a,
+                open,
+                close_reason,
+            },
+        )
+    }
+
+    pub fn receive(&self) -> Option<Vec<u8>> {
+        self.inbox.borrow_mut().pop_front()
+    }
+
+    /// The code and reason given by whichever end closed the connection
+    pub fn close_reason(&self) -> Option<(u16, String)> {
+        self.close_reason.borrow().clone()
+    }
+}
+
+impl SyncTransport for InMemoryTransport {
+    fn is_open(&self) -> bool {
+        self.open.get()
+    }
+
+    fn send(&self, message: &[u8]) -> Result<(), SyncTransportError> {
+        if !self.open.get() {
+            return Err(SyncTransportError::NotOpen);
+        }
+        self.outbox.borrow_mut().push_back(message.to_vec());
+        Ok(())
+    }
+
+    fn close(&self, code: u16, reason: &str) {
+        if self.open.replace(false) {
+            self.close_reason.replace(Some((code, reason.to_owned())));
+        }
+    }
+}
@@ -1,10 +1,19 @@
 use crate::{
     animation_frame::{AnimationFrameHandler, AnimationFrameProcessor},
     builtin_geometry::BuiltInGeometry,
+    compiler::{compile_to_naga_ir, validate_stage},
+    constraint_solver::{ConstraintSolverError, TypeConflict},
     data_types::AbstractDataType,
-    graph_functions,
+    graph_functions::{
+        concretise_types_in_graph, decompose_branches, decompose_subgraphs, get_upstream_subgraph,
+        label_branches, label_computation_domains, label_subgraphs, topologically_order_nodes,
+        IncrementalTypeNarrower,
+    },
     graph_transfer_types::{GRAPH_YRS_EDGES_MAP_KEY, GRAPH_YRS_NODES_MAP_KEY},
-    graph_types::{Edge, Graph, Node, PortId},
+    graph_types::{Edge, Graph, Node, NodeType, PortId},
+    intermediate_compiler_types::{
+        IntermediateOutput, NodeDiagnostic, Shader, VertexFragmentShader,
+    },
     log,
     preview_renderer::{PreviewRendererResources, SharedPreviewRendererResources},
     test_fragment_shader::make_fragment_shader_module,
@@ -13,16 +22,32 @@ use crate::{
     yrs_utils::get_map,
 };
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, collections::HashMap, panic, rc::Rc, str::FromStr};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    panic,
+    rc::Rc,
+    str::FromStr,
+};
 use wasm_bindgen::prelude::*;
-use web_sys::{DedicatedWorkerGlobalScope, OffscreenCanvas, Window};
+use web_sys::{js_sys, DedicatedWorkerGlobalScope, OffscreenCanvas, Window};
 use yrs::{updates::decoder::Decode, DeepObservable, Subscription, Transact, Update};
 
+// How long the graph has to be left unchanged before previews are recompiled
+const COMPILE_DEBOUNCE_MILLIS: f64 = 150.0;
+
 #[allow(unused)]
 pub struct WbblGraphWebWorkerMain {
     doc: Rc<yrs::Doc>,
     graph: Rc<RefCell<Graph>>,
+    type_narrower: Rc<RefCell<IncrementalTypeNarrower>>,
+    // When the graph last changed, if previews haven't been recompiled since
+    compile_requested_at: Rc<Cell<Option<f64>>>,
     preview_resources: HashMap<u128, Rc<RefCell<PreviewRendererResources>>>,
+    // The subgraph each preview was last compiled from, so unaffected previews can be skipped
+    compiled_previews: HashMap<u128, Graph>,
+    // Problems found when last compiling each output or preview node, by that node's id
+    diagnostics: HashMap<u128, Vec<NodeDiagnostic>>,
     shared_preview_resources: Rc<SharedPreviewRendererResources>,
     animation_frame_handler: Rc<RefCell<AnimationFrameHandler>>,
     worker_scope: Rc<DedicatedWorkerGlobalScope>,
@@ -79,28 +104,181 @@ impl WbblGraphWebWorkerJsWrapper {
     pub fn deregister_canvas(&mut self, node_id: &str) -> Result<(), WbblGraphWebWorkerError> {
         self.main.as_ref().borrow_mut().deregister_canvas(node_id)
     }
+
+    pub fn resize_canvas(
+        &mut self,
+        node_id: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<(), WbblGraphWebWorkerError> {
+        self.main
+            .as_ref()
+            .borrow_mut()
+            .resize_canvas(node_id, width, height)
+    }
+}
+
+/// A request to the worker. Requests with an id are always answered with a response carrying
+/// the same id, while those without one only report failures
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WbblGraphWebWorkerRequest {
+    pub request_id: Option<u32>,
+    pub message: WbblGraphWebWorkerRequestMessage,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum WbblGraphWebWorkerRequestMessage {
     Poll,
     ReceiveUpdate(Vec<u8>),
+    Compile,
+    GetTypes,
+    // Discards the worker's copy of the graph, so that a different graph can be sent
+    Reset,
+    Shutdown,
 }
 
+/// A message from the worker. Responses without a request id are broadcast as the graph changes
 #[derive(Debug, Serialize, Deserialize)]
+pub struct WbblGraphWebWorkerResponse {
+    pub request_id: Option<u32>,
+    pub message: WbblGraphWebWorkerResponseMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum WbblGraphWebWorkerResponseMessage {
     Ready,
+    Acknowledged,
     TypesUpdated(HashMap<PortId, AbstractDataType>),
-    TypeUnificationFailure,
+    TypeUnificationFailure(Option<TypeConflict>),
+    Compiled(WbblGraphCompilationSummary),
+    // Every problem found by the latest compiles, sent whenever they change
+    DiagnosticsUpdated(Vec<NodeDiagnostic>),
+    Failed(WbblGraphWebWorkerError),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WbblGraphCompilationSummary {
+    pub stages: usize,
+    pub time_varying: bool,
 }
 
 #[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WbblGraphWebWorkerError {
     MalformedMessage,
     MalformedId,
     WebGpuError,
     CouldNotPostMessage,
     CouldNotUnifyTypes,
+    MalformedUpdate,
+    MissingOutputNode,
+    CompilationFailed,
+    SerializationFailure,
+}
+
+fn empty_graph() -> Graph {
+    Graph {
+        id: uuid::Uuid::new_v4().as_u128(),
+        nodes: HashMap::new(),
+        edges: HashMap::new(),
+        dirty: false,
+        input_ports: HashMap::new(),
+        output_ports: HashMap::new(),
+    }
+}
+
+// Compiles a graph extracted with `get_upstream_subgraph`, with its root as the output. Any
+// problems that can be traced to nodes are added to `diagnostics`
+fn compile_subgraph(
+    graph: Graph,
+    diagnostics: &mut Vec<NodeDiagnostic>,
+) -> Result<IntermediateOutput, WbblGraphWebWorkerError> {
+    let root = graph.id;
+    let node_ordering = topologically_order_nodes(&graph);
+    let concrete_types = concretise_types_in_graph(&graph, &node_ordering)
+        .map_err(|_| WbblGraphWebWorkerError::CouldNotUnifyTypes)?;
+    let computation_domains = label_computation_domains(&graph, &node_ordering);
+    let subgraphs = label_subgraphs(&graph);
+    let branches = label_branches(&graph);
+    let multi_graph = decompose_subgraphs(graph, &subgraphs, &node_ordering);
+    let branched_multi_graph = decompose_branches(multi_graph, &branches);
+    let output =
+        match compile_to_naga_ir(&branched_multi_graph, &computation_domains, &concrete_types) {
+            Ok(output) => output,
+            Err(err) => {
+                log!("Compilation failed {:?}", err);
+                diagnostics.push(err.to_node_diagnostic(root));
+                return Err(WbblGraphWebWorkerError::CompilationFailed);
+            }
+        };
+    for stage in output.0.iter() {
+        if let Err(stage_diagnostics) = validate_stage(stage) {
+            log!("Validation failed {:?}", stage_diagnostics);
+            diagnostics.extend(stage_diagnostics);
+        }
+    }
+    if diagnostics.is_empty() {
+        Ok(output)
+    } else {
+        Err(WbblGraphWebWorkerError::CompilationFailed)
+    }
+}
+
+fn find_output_node(graph: &Graph) -> Option<u128> {
+    graph
+        .nodes
+        .values()
+        .find(|node| node.node_type == NodeType::Output)
+        .map(|node| node.id)
+}
+
+// Previews only draw the vertex and fragment stage, along with whether it has to be redrawn
+// every frame
+fn compile_preview(
+    graph: Graph,
+    diagnostics: &mut Vec<NodeDiagnostic>,
+) -> Result<(VertexFragmentShader, bool), WbblGraphWebWorkerError> {
+    compile_subgraph(graph, diagnostics)?
+        .0
+        .into_iter()
+        .find_map(|stage| {
+            let time_varying = stage.is_time_varying();
+            match stage.shader {
+                Shader::VertexFragment(shader) => Some((shader, time_varying)),
+                _ => None,
+            }
+        })
+        .ok_or(WbblGraphWebWorkerError::CompilationFailed)
+}
+
+fn types_message(
+    result: Result<HashMap<PortId, AbstractDataType>, ConstraintSolverError>,
+) -> WbblGraphWebWorkerResponseMessage {
+    match result {
+        Ok(types) => WbblGraphWebWorkerResponseMessage::TypesUpdated(types),
+        Err(ConstraintSolverError::ConflictFound(conflict)) => {
+            WbblGraphWebWorkerResponseMessage::TypeUnificationFailure(Some(conflict))
+        }
+        Err(ConstraintSolverError::ContradictionFound) => {
+            WbblGraphWebWorkerResponseMessage::TypeUnificationFailure(None)
+        }
+    }
+}
+
+fn post_response(
+    worker_scope: &DedicatedWorkerGlobalScope,
+    request_id: Option<u32>,
+    message: WbblGraphWebWorkerResponseMessage,
+) -> Result<(), WbblGraphWebWorkerError> {
+    let response = WbblGraphWebWorkerResponse {
+        request_id,
+        message,
+    };
+    let response = serde_wasm_bindgen::to_value(&response)
+        .map_err(|_| WbblGraphWebWorkerError::SerializationFailure)?;
+    worker_scope
+        .post_message(&response)
+        .map_err(|_| WbblGraphWebWorkerError::CouldNotPostMessage)
 }
 
 impl WbblGraphWebWorkerMain {
@@ -112,14 +290,42 @@ impl WbblGraphWebWorkerMain {
         let shared_preview_resources = SharedPreviewRendererResources::new()
             .await
             .expect("Expected success");
-        let graph = Rc::new(RefCell::new(Graph {
-            id: uuid::Uuid::new_v4().as_u128(),
-            nodes: HashMap::new(),
-            edges: HashMap::new(),
-            dirty: false,
-            input_ports: HashMap::new(),
-            output_ports: HashMap::new(),
-        }));
+        let graph = Rc::new(RefCell::new(empty_graph()));
+        let type_narrower = Rc::new(RefCell::new(IncrementalTypeNarrower::new()));
+        let compile_requested_at = Rc::new(Cell::new(None));
+        let (doc, nodes, edges, subscriptions) =
+            Self::create_document(&graph, &worker_scope, &type_narrower, &compile_requested_at);
+
+        WbblGraphWebWorkerMain {
+            doc,
+            graph,
+            type_narrower,
+            compile_requested_at,
+            shared_preview_resources: shared_preview_resources.into(),
+            preview_resources: HashMap::new(),
+            compiled_previews: HashMap::new(),
+            diagnostics: HashMap::new(),
+            animation_frame_handler,
+            worker_scope: worker_scope.clone(),
+            subscriptions,
+            nodes,
+            edges,
+        }
+    }
+
+    // Creates the worker's copy of the graph document, with observers that keep the graph
+    // and its types up to date, and request a compile when it changes
+    fn create_document(
+        graph: &Rc<RefCell<Graph>>,
+        worker_scope: &Rc<DedicatedWorkerGlobalScope>,
+        type_narrower: &Rc<RefCell<IncrementalTypeNarrower>>,
+        compile_requested_at: &Rc<Cell<Option<f64>>>,
+    ) -> (
+        Rc<yrs::Doc>,
+        Rc<yrs::MapRef>,
+        Rc<yrs::MapRef>,
+        Vec<Subscription>,
+    ) {
         let doc = Rc::new(yrs::Doc::new());
         let nodes = Rc::new(doc.get_or_insert_map(GRAPH_YRS_NODES_MAP_KEY.to_owned()));
         let edges = Rc::new(doc.get_or_insert_map(GRAPH_YRS_EDGES_MAP_KEY.to_owned()));
@@ -127,6 +333,7 @@ impl WbblGraphWebWorkerMain {
         let nodes_subscription = nodes.observe_deep({
             let graph = graph.clone();
             let nodes = nodes.clone();
+            let type_narrower = type_narrower.clone();
             move |txn, evts| {
                 for evt in evts.iter() {
                     if let yrs::types::Event::Map(map_evt) = evt {
@@ -145,8 +352,14 @@ impl WbblGraphWebWorkerMain {
                                                             log!("Nodes Err 3 {:?}", err)
                                                         });
                                             };
+                                            type_narrower
+                                                .borrow_mut()
+                                                .invalidate_node(&graph, key);
                                         }
                                         yrs::types::EntryChange::Removed(_) => {
+                                            type_narrower
+                                                .borrow_mut()
+                                                .invalidate_node(&graph, key);
                                             if let Some(prev_node) = graph.nodes.remove(&key) {
                                                 for port_id in prev_node.port_ids() {
                                                     match port_id {
@@ -228,9 +441,12 @@ impl WbblGraphWebWorkerMain {
                                 ) if field.to_string() == "data" => {
                                     // Only update node if data changed.
                                     // We don't care about the other node properties
-                                    if try_into_u128(key).is_ok() {
+                                    if let Ok(node_id) = try_into_u128(key) {
                                         let mut graph = graph.borrow_mut();
                                         if let Ok(new_node) = get_map(key, txn, &nodes) {
+                                            type_narrower
+                                                .borrow_mut()
+                                                .invalidate_node(&graph, node_id);
                                             let _ =
                                                 Node::update_existing(txn, &new_node, &mut graph)
                                                     .inspect_err(|err| {
@@ -241,7 +457,7 @@ impl WbblGraphWebWorkerMain {
                                 }
                                 // Here we need to test if the data was replaced as a whole
                                 (Some(yrs::types::PathSegment::Key(key)), None) => {
-                                    if try_into_u128(key).is_ok() {
+                                    if let Ok(node_id) = try_into_u128(key) {
                                         let mut graph = graph.borrow_mut();
                                         // Node updated
                                         let keys = map_evt.keys(txn);
@@ -252,6 +468,9 @@ impl WbblGraphWebWorkerMain {
                                                 }
                                                 yrs::types::EntryChange::Updated(_, _) => {
                                                     if let Ok(node) = get_map(key, txn, &nodes) {
+                                                        type_narrower
+                                                            .borrow_mut()
+                                                            .invalidate_node(&graph, node_id);
                                                         let _ = Node::update_existing(
                                                             txn, &node, &mut graph,
                                                         )
@@ -277,6 +496,7 @@ impl WbblGraphWebWorkerMain {
 
         let edges_subscription = edges.observe_deep({
             let graph = graph.clone();
+            let type_narrower = type_narrower.clone();
             move |txn, evts| {
                 for evt in evts.iter() {
                     if let yrs::types::Event::Map(map_evt) = evt {
@@ -291,11 +511,17 @@ impl WbblGraphWebWorkerMain {
                                             let mut graph = graph.borrow_mut();
                                             let _ = Edge::insert_new(txn, new_edge, &mut graph)
                                                 .inspect_err(|err| log!("Edges Err {:?}", err));
+                                            if let Some(edge) = graph.edges.get(&edge_uuid) {
+                                                type_narrower.borrow_mut().invalidate_edge(edge);
+                                            }
                                         }
                                         yrs::types::EntryChange::Removed(_) => {
                                             let mut graph = graph.borrow_mut();
                                             if let Some(prev_edge) = graph.edges.remove(&edge_uuid)
                                             {
+                                                type_narrower
+                                                    .borrow_mut()
+                                                    .invalidate_removed_edge(&prev_edge);
                                                 let input_port_id = prev_edge.input_port;
                                                 if let Some(input_port) =
                                                     graph.input_ports.get_mut(&input_port_id)
@@ -333,73 +559,214 @@ impl WbblGraphWebWorkerMain {
             .observe_after_transaction({
                 let graph = graph.clone();
                 let worker_scope = worker_scope.clone();
+                let type_narrower = type_narrower.clone();
+                let compile_requested_at = compile_requested_at.clone();
                 move |_| {
                     let mut graph = graph.borrow_mut();
                     if graph.dirty {
                         graph.dirty = false;
-                        match graph_functions::narrow_abstract_types(&graph) {
-                            Ok(types) => {
-                                worker_scope
-                                    .post_message(
-                                        &serde_wasm_bindgen::to_value(
-                                            &WbblGraphWebWorkerResponseMessage::TypesUpdated(types),
-                                        )
-                                        .unwrap(),
-                                    )
-                                    .unwrap();
-                            }
-                            Err(_) => {
-                                worker_scope
-                                .post_message(
-                                    &serde_wasm_bindgen::to_value(
-                                        &WbblGraphWebWorkerResponseMessage::TypeUnificationFailure,
-                                    )
-                                    .unwrap(),
-                                )
-                                .unwrap();
-                            }
-                        };
+                        compile_requested_at.set(Some(js_sys::Date::now()));
+                        let message = types_message(type_narrower.borrow_mut().narrow(&graph));
+                        let _ = post_response(&worker_scope, None, message)
+                            .inspect_err(|err| log!("Failed to publish types {:?}", err));
                     }
                 }
             })
             .unwrap();
-        // doc.integrate(&mut txn, update);
 
-        WbblGraphWebWorkerMain {
+        (
             doc,
-            graph,
-            shared_preview_resources: shared_preview_resources.into(),
-            preview_resources: HashMap::new(),
-            animation_frame_handler,
-            worker_scope: worker_scope.clone(),
-            subscriptions: vec![nodes_subscription, edges_subscription, doc_subscription],
             nodes,
             edges,
-        }
+            vec![nodes_subscription, edges_subscription, doc_subscription],
+        )
     }
 
-    fn post_message(
+    fn post_response(
         &self,
+        request_id: Option<u32>,
         message: WbblGraphWebWorkerResponseMessage,
     ) -> Result<(), WbblGraphWebWorkerError> {
-        self.worker_scope
-            .post_message(&serde_wasm_bindgen::to_value(&message).unwrap())
-            .map_err(|_| WbblGraphWebWorkerError::CouldNotPostMessage)
+        post_response(&self.worker_scope, request_id, message)
     }
 
     pub fn handle_message(&mut self, value: JsValue) -> Result<(), WbblGraphWebWorkerError> {
-        let message = serde_wasm_bindgen::from_value::<WbblGraphWebWorkerRequestMessage>(value)
-            .map_err(|_| WbblGraphWebWorkerError::MalformedMessage)?;
+        let request =
+            match serde_wasm_bindgen::from_value::<WbblGraphWebWorkerRequest>(value.clone()) {
+                Ok(request) => request,
+                Err(_) => {
+                    // The request id may still be readable, so that the sender isn't left waiting
+                    let request_id = js_sys::Reflect::get(&value, &JsValue::from_str("request_id"))
+                        .ok()
+                        .and_then(|id| id.as_f64())
+                        .map(|id| id as u32);
+                    self.post_response(
+                        request_id,
+                        WbblGraphWebWorkerResponseMessage::Failed(
+                            WbblGraphWebWorkerError::MalformedMessage,
+                        ),
+                    )?;
+                    return Err(WbblGraphWebWorkerError::MalformedMessage);
+                }
+            };
+        let request_id = request.request_id;
+        let message = match self.handle_request(request.message) {
+            Ok(Some(message)) => message,
+            Ok(None) if request_id.is_none() => return Ok(()),
+            Ok(None) => WbblGraphWebWorkerResponseMessage::Acknowledged,
+            Err(err) => WbblGraphWebWorkerResponseMessage::Failed(err),
+        };
+        self.post_response(request_id, message)
+    }
+
+    fn handle_request(
+        &mut self,
+        message: WbblGraphWebWorkerRequestMessage,
+    ) -> Result<Option<WbblGraphWebWorkerResponseMessage>, WbblGraphWebWorkerError> {
         match message {
             WbblGraphWebWorkerRequestMessage::Poll => {
-                self.post_message(WbblGraphWebWorkerResponseMessage::Ready)
+                Ok(Some(WbblGraphWebWorkerResponseMessage::Ready))
             }
             WbblGraphWebWorkerRequestMessage::ReceiveUpdate(update) => {
-                self.doc
-                    .transact_mut()
-                    .apply_update(Update::decode_v2(&update).unwrap());
-                Ok(())
+                let update = Update::decode_v1(&update)
+                    .map_err(|_| WbblGraphWebWorkerError::MalformedUpdate)?;
+                self.doc.transact_mut().apply_update(update);
+                // The output node's diagnostics are recompiled even when nothing is previewed
+                let mut animation_frame_handler =
+                    self.animation_frame_handler.as_ref().borrow_mut();
+                if self.compile_requested_at.get().is_some()
+                    && !animation_frame_handler.is_scheduled()
+                {
+                    animation_frame_handler.start();
+                }
+                Ok(None)
+            }
+            WbblGraphWebWorkerRequestMessage::Compile => {
+                let output = self.compile()?;
+                Ok(Some(WbblGraphWebWorkerResponseMessage::Compiled(
+                    WbblGraphCompilationSummary {
+                        stages: output.0.len(),
+                        time_varying: output.0.iter().any(|stage| stage.is_time_varying()),
+                    },
+                )))
+            }
+            WbblGraphWebWorkerRequestMessage::GetTypes => {
+                let graph = self.graph.borrow();
+                Ok(Some(types_message(
+                    self.type_narrower.borrow_mut().narrow(&graph),
+                )))
+            }
+            WbblGraphWebWorkerRequestMessage::Reset => {
+                // The old observers are dropped first, so they don't see the graph being replaced
+                self.subscriptions.clear();
+                self.graph.replace(empty_graph());
+                self.type_narrower.replace(IncrementalTypeNarrower::new());
+                self.compiled_previews.clear();
+                for root in self.diagnostics.keys().copied().collect::<Vec<u128>>() {
+                    self.set_diagnostics(root, vec![]);
+                }
+                let (doc, nodes, edges, subscriptions) = Self::create_document(
+                    &self.graph,
+                    &self.worker_scope,
+                    &self.type_narrower,
+                    &self.compile_requested_at,
+                );
+                self.doc = doc;
+                self.nodes = nodes;
+                self.edges = edges;
+                self.subscriptions = subscriptions;
+                Ok(None)
+            }
+            WbblGraphWebWorkerRequestMessage::Shutdown => {
+                self.subscriptions.clear();
+                self.preview_resources.clear();
+                self.compiled_previews.clear();
+                self.diagnostics.clear();
+                self.animation_frame_handler.as_ref().borrow_mut().cancel();
+                // Closing is deferred, so the acknowledgement is still delivered
+                let worker_scope = self.worker_scope.clone();
+                wasm_bindgen_futures::spawn_local(async move { worker_scope.close() });
+                Ok(None)
+            }
+        }
+    }
+
+    fn compile(&mut self) -> Result<IntermediateOutput, WbblGraphWebWorkerError> {
+        let (output_node_id, subgraph) = {
+            let graph = self.graph.borrow();
+            let output_node_id =
+                find_output_node(&graph).ok_or(WbblGraphWebWorkerError::MissingOutputNode)?;
+            let subgraph = get_upstream_subgraph(&graph, output_node_id)
+                .ok_or(WbblGraphWebWorkerError::MissingOutputNode)?;
+            (output_node_id, subgraph)
+        };
+        let mut diagnostics = vec![];
+        let output = compile_subgraph(subgraph, &mut diagnostics);
+        self.set_diagnostics(output_node_id, diagnostics);
+        output
+    }
+
+    // Records the diagnostics from compiling `root`, and sends every node's diagnostics to the
+    // store when they changed
+    fn set_diagnostics(&mut self, root: u128, diagnostics: Vec<NodeDiagnostic>) {
+        let previous = if diagnostics.is_empty() {
+            self.diagnostics.remove(&root)
+        } else {
+            self.diagnostics.insert(root, diagnostics.clone())
+        };
+        if previous.unwrap_or_default() == diagnostics {
+            return;
+        }
+        let all_diagnostics = self.diagnostics.values().flatten().cloned().collect();
+        let _ = self
+            .post_response(
+                None,
+                WbblGraphWebWorkerResponseMessage::DiagnosticsUpdated(all_diagnostics),
+            )
+            .inspect_err(|err| log!("Failed to publish diagnostics {:?}", err));
+    }
+
+    // Recompiles the previews whose upstream subgraph changed since they were last compiled,
+    // along with the output node so that its diagnostics stay current. Previews that fail to
+    // compile keep showing their previous shaders
+    fn recompile_previews(&mut self) {
+        let mut preview_diagnostics = vec![];
+        let graph = self.graph.borrow();
+        for (node_id, resources) in self.preview_resources.iter() {
+            let Some(subgraph) = get_upstream_subgraph(&graph, *node_id) else {
+                continue;
+            };
+            if self.compiled_previews.get(node_id) == Some(&subgraph) {
+                continue;
+            }
+            let mut diagnostics = vec![];
+            match compile_preview(subgraph.clone(), &mut diagnostics) {
+                Ok((shader, time_varying)) => resources.borrow_mut().replace_shaders(
+                    &self.shared_preview_resources,
+                    shader.vertex,
+                    shader.fragment,
+                    time_varying,
+                ),
+                Err(err) => log!("Failed to compile preview {:?}", err),
             }
+            self.compiled_previews.insert(*node_id, subgraph);
+            preview_diagnostics.push((*node_id, diagnostics));
+        }
+        if let Some(output_node_id) = find_output_node(&graph)
+            .filter(|output_node_id| !self.preview_resources.contains_key(output_node_id))
+        {
+            if let Some(subgraph) = get_upstream_subgraph(&graph, output_node_id)
+                .filter(|subgraph| self.compiled_previews.get(&output_node_id) != Some(subgraph))
+            {
+                let mut diagnostics = vec![];
+                let _ = compile_subgraph(subgraph.clone(), &mut diagnostics);
+                self.compiled_previews.insert(output_node_id, subgraph);
+                preview_diagnostics.push((output_node_id, diagnostics));
+            }
+        }
+        drop(graph);
+        for (node_id, diagnostics) in preview_diagnostics {
+            self.set_diagnostics(node_id, diagnostics);
         }
     }
 
@@ -410,16 +777,35 @@ impl WbblGraphWebWorkerMain {
         offscreen_canvas: OffscreenCanvas,
     ) -> Result<(), WbblGraphWebWorkerError> {
         let id = uuid::Uuid::from_str(node_id).map_err(|_| WbblGraphWebWorkerError::MalformedId)?;
+        let subgraph = get_upstream_subgraph(&self.graph.borrow(), id.as_u128());
+        let mut diagnostics = vec![];
+        // Until the preview's subgraph compiles, it shows the test shader
+        let (vertex_shader, fragment_shader, time_varying) = match subgraph
+            .clone()
+            .map(|subgraph| compile_preview(subgraph, &mut diagnostics))
+        {
+            Some(Ok((shader, time_varying))) => (shader.vertex, shader.fragment, time_varying),
+            _ => (
+                make_vertex_shader_module(),
+                make_fragment_shader_module(),
+                false,
+            ),
+        };
         let resources = PreviewRendererResources::new_from_offscreen_canvas(
             self.shared_preview_resources.clone(),
             BuiltInGeometry::UVSphere,
             offscreen_canvas,
-            make_vertex_shader_module(),
-            make_fragment_shader_module(),
+            vertex_shader,
+            fragment_shader,
+            time_varying,
         )
         .map_err(|_| WbblGraphWebWorkerError::WebGpuError)?;
         self.preview_resources
             .insert(id.as_u128(), RefCell::new(resources).into());
+        if let Some(subgraph) = subgraph {
+            self.compiled_previews.insert(id.as_u128(), subgraph);
+        }
+        self.set_diagnostics(id.as_u128(), diagnostics);
         if self.preview_resources.len() == 1 {
             self.animation_frame_handler.as_ref().borrow_mut().start();
         }
@@ -429,21 +815,43 @@ impl WbblGraphWebWorkerMain {
     pub fn deregister_canvas(&mut self, node_id: &str) -> Result<(), WbblGraphWebWorkerError> {
         let id = uuid::Uuid::from_str(node_id).map_err(|_| WbblGraphWebWorkerError::MalformedId)?;
         self.preview_resources.remove(&id.as_u128());
-        if self.preview_resources.is_empty() {
+        self.compiled_previews.remove(&id.as_u128());
+        self.set_diagnostics(id.as_u128(), vec![]);
+        if self.preview_resources.is_empty() && self.compile_requested_at.get().is_none() {
             self.animation_frame_handler.as_ref().borrow_mut().cancel();
         }
         Ok(())
     }
+
+    pub fn resize_canvas(
+        &mut self,
+        node_id: &str,
+        width: u32,
+        height: u32,
+    ) -> Result<(), WbblGraphWebWorkerError> {
+        let id = uuid::Uuid::from_str(node_id).map_err(|_| WbblGraphWebWorkerError::MalformedId)?;
+        if let Some(resources) = self.preview_resources.get(&id.as_u128()) {
+            resources.as_ref().borrow_mut().resize(width, height);
+        }
+        Ok(())
+    }
 }
 
 impl AnimationFrameProcessor for WbblGraphWebWorkerMain {
-    fn process_frame(&mut self) -> bool {
+    fn process_frame(&mut self, timestamp: f64) -> bool {
+        if let Some(requested_at) = self.compile_requested_at.get() {
+            if js_sys::Date::now() - requested_at >= COMPILE_DEBOUNCE_MILLIS {
+                self.compile_requested_at.set(None);
+                self.recompile_previews();
+            }
+        }
+        let seconds = (timestamp / 1000.0) as f32;
         for resource in self.preview_resources.values_mut() {
-            resource
-                .as_ref()
-                .borrow_mut()
-                .render(self.shared_preview_resources.clone());
+            let mut resource = resource.as_ref().borrow_mut();
+            if resource.needs_render() {
+                resource.render(self.shared_preview_resources.clone(), seconds);
+            }
         }
-        !self.preview_resources.is_empty()
+        !self.preview_resources.is_empty() || self.compile_requested_at.get().is_some()
     }
 }
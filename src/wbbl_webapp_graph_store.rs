@@ -1,34 +1,48 @@
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{HashMap, HashSet},
     rc::Rc,
     str::FromStr,
+    sync::atomic::{AtomicU32, Ordering},
     vec,
 };
 
 use glam::Vec2;
-use rstar::{RTree, RTreeObject};
 use wasm_bindgen::prelude::*;
-use web_sys::{js_sys, MessageEvent, Worker};
+use web_sys::{js_sys, window, MessageEvent, Worker};
 use yrs::{
     types::{PathSegment, ToJson},
     DeepObservable, Map, MapPrelim, MapRef, Transact, TransactionMut,
 };
 
 use crate::{
+    animation_frame::{AnimationFrameHandler, AnimationFrameProcessor},
+    awareness_sync::{AwarenessWebsocketSync, ConnectionState, WasmPackClock},
+    callback_future::CallbackFuture,
     convex_hull::{get_convex_hull, get_ray_ray_intersection},
     data_types::AbstractDataType,
+    graph_functions::{edges_contain_cycle, is_reachable},
+    graph_layout::{layout_nodes, LayoutDirection, LayoutOptions},
+    graph_presence::{WbblWebappCollaborator, WbblWebappPresence, WbblWebappViewport},
     graph_transfer_types::{
         from_type_name, get_type_name, Any, WbblWebappEdge, WbblWebappGraphEntity,
-        WbblWebappGraphEntityId, WbblWebappGraphSnapshot, WbblWebappNode, WbblWebappNodeGroup,
-        WbblWebappNodeType, WbblePosition, GRAPH_YRS_EDGES_MAP_KEY, GRAPH_YRS_NODES_MAP_KEY,
-        GRAPH_YRS_NODE_GROUP_SELECTIONS_MAP_KEY,
+        WbblWebappGraphEntityId, WbblWebappGraphEntityIds, WbblWebappGraphSnapshot, WbblWebappNode,
+        WbblWebappNodeGroup, WbblWebappNodeType, WbblePosition, GRAPH_YRS_EDGES_MAP_KEY,
+        GRAPH_YRS_NODES_MAP_KEY, GRAPH_YRS_NODE_GROUP_SELECTIONS_MAP_KEY,
+    },
+    graph_types::{
+        get_declared_port_counts, get_declared_port_type, validate_node_data, InputPortId,
+        OutputPortId, PortId, VectorComponent,
     },
     log,
     node_display_data::{get_in_port_position, get_node_dimensions, get_out_port_position},
+    spatial_index::SpatialIndex,
     store_errors::WbblWebappStoreError,
     utils::try_into_u128,
-    wbbl_graph_web_worker::{WbblGraphWebWorkerRequestMessage, WbblGraphWebWorkerResponseMessage},
+    wbbl_graph_web_worker::{
+        WbblGraphWebWorkerError, WbblGraphWebWorkerRequest, WbblGraphWebWorkerRequestMessage,
+        WbblGraphWebWorkerResponse, WbblGraphWebWorkerResponseMessage,
+    },
     yrs_utils::*,
 };
 
@@ -38,21 +52,93 @@ pub struct WbblWebappGraphStore {
     id: u128,
     next_listener_handle: u32,
     listeners: Rc<RefCell<Vec<(u32, js_sys::Function)>>>,
+    presence_listeners: Rc<RefCell<Vec<(u32, js_sys::Function)>>>,
+    presence: Rc<RefCell<WbblWebappPresence>>,
+    cursor_publisher: AnimationFrameHandler,
     undo_manager: yrs::UndoManager,
     graph: Rc<yrs::Doc>,
+    awareness: Rc<RefCell<yrs::sync::Awareness>>,
+    websockets_sync: Option<AwarenessWebsocketSync>,
     nodes: Rc<yrs::MapRef>,
     node_group_selections: Rc<yrs::MapRef>,
     edges: Rc<yrs::MapRef>,
     graph_worker: Rc<Worker>,
     worker_responder: Closure<dyn FnMut(MessageEvent)>,
-    spatial_index: Rc<RefCell<RTree<WbblWebappGraphEntity>>>,
+    pending_worker_requests: PendingWorkerRequests,
+    reset_request_id: Rc<Cell<u32>>,
+    spatial_index: Rc<RefCell<SpatialIndex>>,
     computed_types: Rc<RefCell<JsValue>>,
+    port_types: Rc<RefCell<HashMap<PortId, AbstractDataType>>>,
+    type_conflict: Rc<RefCell<JsValue>>,
+    diagnostics: Rc<RefCell<JsValue>>,
     entities: Rc<RefCell<HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>>>,
     js_entities: Rc<RefCell<HashMap<WbblWebappGraphEntityId, JsValue>>>,
     subscriptions: Vec<yrs::Subscription>,
     locally_selected_entities: Rc<RefCell<HashSet<WbblWebappGraphEntityId>>>,
 }
 
+// The worker is shared by every store, so request ids are unique across them and each
+// store can tell its own replies apart
+static NEXT_WORKER_REQUEST_ID: AtomicU32 = AtomicU32::new(0);
+
+// The request id of the reset the worker last acknowledged. Broadcasts describe the graph it was
+// reset for, so the other stores sharing the worker ignore them
+static WORKER_RESET_FOR: AtomicU32 = AtomicU32::new(u32::MAX);
+
+// Replies the store is waiting on, by request id
+type PendingWorkerRequests =
+    Rc<RefCell<HashMap<u32, CallbackFuture<WbblGraphWebWorkerResponseMessage>>>>;
+
+fn post_worker_request(
+    graph_worker: &Worker,
+    request_id: Option<u32>,
+    message: WbblGraphWebWorkerRequestMessage,
+) -> Result<(), WbblGraphWebWorkerError> {
+    let request = WbblGraphWebWorkerRequest {
+        request_id,
+        message,
+    };
+    let request = serde_wasm_bindgen::to_value(&request)
+        .map_err(|_| WbblGraphWebWorkerError::SerializationFailure)?;
+    graph_worker
+        .post_message(&request)
+        .map_err(|_| WbblGraphWebWorkerError::CouldNotPostMessage)
+}
+
+// Asks the worker to drop its graph, recording the reset's request id so that only this store
+// picks up the broadcasts that follow it
+fn post_worker_reset(
+    graph_worker: &Worker,
+    reset_request_id: &Cell<u32>,
+) -> Result<(), WbblGraphWebWorkerError> {
+    let request_id = NEXT_WORKER_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    reset_request_id.set(request_id);
+    post_worker_request(
+        graph_worker,
+        Some(request_id),
+        WbblGraphWebWorkerRequestMessage::Reset,
+    )
+}
+
+fn worker_reply_to_js(message: WbblGraphWebWorkerResponseMessage) -> Result<JsValue, JsValue> {
+    match message {
+        WbblGraphWebWorkerResponseMessage::Ready
+        | WbblGraphWebWorkerResponseMessage::Acknowledged => Ok(JsValue::UNDEFINED),
+        WbblGraphWebWorkerResponseMessage::TypesUpdated(types) => {
+            serde_wasm_bindgen::to_value(&types)
+                .map_err(|_| WbblGraphWebWorkerError::SerializationFailure.into())
+        }
+        WbblGraphWebWorkerResponseMessage::Compiled(summary) => {
+            serde_wasm_bindgen::to_value(&summary)
+                .map_err(|_| WbblGraphWebWorkerError::SerializationFailure.into())
+        }
+        WbblGraphWebWorkerResponseMessage::TypeUnificationFailure(_) => {
+            Err(WbblGraphWebWorkerError::CouldNotUnifyTypes.into())
+        }
+        WbblGraphWebWorkerResponseMessage::Failed(err) => Err(err.into()),
+    }
+}
+
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct NewWbblWebappNode {
@@ -79,6 +165,32 @@ fn encode_data(
     Ok(map.iter().map(|(k, v)| (k.clone(), v.into())).collect())
 }
 
+fn encode_node_data_entries(
+    txn: &mut TransactionMut,
+    node_id: &str,
+    nodes: &MapRef,
+    entries: HashMap<String, Any>,
+) -> Result<(), WbblWebappStoreError> {
+    let node_ref = get_map(node_id, txn, nodes)?;
+    let type_name = get_atomic_string("type", txn, &node_ref)?;
+    let node_type = from_type_name(&type_name).ok_or(WbblWebappStoreError::UnknownNodeType)?;
+    let data_ref = get_map("data", txn, &node_ref)?;
+    let mut data: HashMap<String, Any> = match data_ref.to_json(txn) {
+        yrs::Any::Map(hash_map) => Ok(hash_map
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.into()))
+            .collect()),
+        _ => Err(WbblWebappStoreError::UnexpectedStructure),
+    }?;
+    data.extend(entries.clone());
+    // Validate the merged data before writing anything, so a bad entry leaves the node untouched
+    validate_node_data(node_type, &data)?;
+    for (key, value) in entries {
+        data_ref.insert(txn, key, value.to_yrs());
+    }
+    Ok(())
+}
+
 fn encode_selection(
     txn: &mut TransactionMut,
     id: u128,
@@ -146,6 +258,64 @@ impl NewWbblWebappNode {
             WbblWebappNodeType::Or => HashMap::new(),
             WbblWebappNodeType::ShiftLeft => HashMap::new(),
             WbblWebappNodeType::ShiftRight => HashMap::new(),
+            WbblWebappNodeType::Negate => HashMap::new(),
+            WbblWebappNodeType::Not => HashMap::new(),
+            WbblWebappNodeType::Abs => HashMap::new(),
+            WbblWebappNodeType::Sign => HashMap::new(),
+            WbblWebappNodeType::Floor => HashMap::new(),
+            WbblWebappNodeType::Ceil => HashMap::new(),
+            WbblWebappNodeType::Round => HashMap::new(),
+            WbblWebappNodeType::Fract => HashMap::new(),
+            WbblWebappNodeType::Trunc => HashMap::new(),
+            WbblWebappNodeType::Sqrt => HashMap::new(),
+            WbblWebappNodeType::Exp => HashMap::new(),
+            WbblWebappNodeType::Log => HashMap::new(),
+            WbblWebappNodeType::Sin => HashMap::new(),
+            WbblWebappNodeType::Cos => HashMap::new(),
+            WbblWebappNodeType::Tan => HashMap::new(),
+            WbblWebappNodeType::Asin => HashMap::new(),
+            WbblWebappNodeType::Acos => HashMap::new(),
+            WbblWebappNodeType::Atan => HashMap::new(),
+            WbblWebappNodeType::Saturate => HashMap::new(),
+            WbblWebappNodeType::Length => HashMap::new(),
+            WbblWebappNodeType::Normalize => HashMap::new(),
+            WbblWebappNodeType::Float => HashMap::from([("value".to_owned(), Any::Number(0.0))]),
+            WbblWebappNodeType::Vec2 => HashMap::from([
+                ("x".to_owned(), Any::Number(0.0)),
+                ("y".to_owned(), Any::Number(0.0)),
+            ]),
+            WbblWebappNodeType::Vec3 => HashMap::from([
+                ("x".to_owned(), Any::Number(0.0)),
+                ("y".to_owned(), Any::Number(0.0)),
+                ("z".to_owned(), Any::Number(0.0)),
+            ]),
+            WbblWebappNodeType::Vec4 => HashMap::from([
+                ("x".to_owned(), Any::Number(0.0)),
+                ("y".to_owned(), Any::Number(0.0)),
+                ("z".to_owned(), Any::Number(0.0)),
+                ("w".to_owned(), Any::Number(0.0)),
+            ]),
+            WbblWebappNodeType::Int => HashMap::from([("value".to_owned(), Any::BigInt(0))]),
+            WbblWebappNodeType::Bool => HashMap::from([("value".to_owned(), Any::Bool(false))]),
+            WbblWebappNodeType::Color => HashMap::from([
+                ("r".to_owned(), Any::Number(1.0)),
+                ("g".to_owned(), Any::Number(1.0)),
+                ("b".to_owned(), Any::Number(1.0)),
+                ("a".to_owned(), Any::Number(1.0)),
+            ]),
+            WbblWebappNodeType::Mix => HashMap::new(),
+            WbblWebappNodeType::Clamp => HashMap::new(),
+            WbblWebappNodeType::Smoothstep => HashMap::new(),
+            WbblWebappNodeType::Step => HashMap::new(),
+            WbblWebappNodeType::Fma => HashMap::new(),
+            WbblWebappNodeType::Select => HashMap::new(),
+            WbblWebappNodeType::Time => HashMap::new(),
+            WbblWebappNodeType::Split => HashMap::new(),
+            WbblWebappNodeType::Append => HashMap::new(),
+            WbblWebappNodeType::Swizzle => HashMap::from([(
+                VectorComponent::DATA_KEY.to_owned(),
+                Any::String("xy".into()),
+            )]),
             WbblWebappNodeType::WorldPosition => HashMap::new(),
             WbblWebappNodeType::ClipPosition => HashMap::new(),
             WbblWebappNodeType::WorldNormal => HashMap::new(),
@@ -223,6 +393,141 @@ fn get_mutual_edges(
     vec![]
 }
 
+fn get_successor_nodes(
+    node_id: u128,
+    entities: &HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
+) -> Vec<u128> {
+    if let Some(WbblWebappGraphEntity::Node(node)) =
+        entities.get(&WbblWebappGraphEntityId::NodeId(node_id))
+    {
+        return node
+            .out_edges
+            .iter()
+            .filter_map(
+                |edge| match entities.get(&WbblWebappGraphEntityId::EdgeId(*edge)) {
+                    Some(WbblWebappGraphEntity::Edge(edge)) => Some(edge.target),
+                    _ => None,
+                },
+            )
+            .collect();
+    }
+    vec![]
+}
+
+// Points and sizes come from JS as plain arrays, which may be too short to hold a vector
+fn vec2_from_slice(slice: &[f32]) -> Result<Vec2, WbblWebappStoreError> {
+    match slice {
+        [x, y, ..] => Ok(Vec2::new(*x, *y)),
+        _ => Err(WbblWebappStoreError::MalformedVector),
+    }
+}
+
+fn get_nearest_port(
+    point: Vec2,
+    radius: f32,
+    spatial_index: &SpatialIndex,
+    entities: &HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
+) -> Option<PortId> {
+    let mut nearest: Option<(PortId, f32)> = None;
+    for node_id in spatial_index.nodes_near(point, radius) {
+        let node = match entities.get(&WbblWebappGraphEntityId::NodeId(node_id)) {
+            Some(WbblWebappGraphEntity::Node(node)) => node,
+            _ => continue,
+        };
+        let (input_count, output_count) = match get_declared_port_counts(node.node_type, &node.data)
+        {
+            Ok(counts) => counts,
+            Err(_) => continue,
+        };
+        let top_left = Vec2::new(node.position.x as f32, node.position.y as f32);
+        let inputs = (0..input_count).map(|index| {
+            (
+                PortId::Input(InputPortId {
+                    node_id,
+                    port_index: index,
+                }),
+                get_in_port_position(node.node_type, index),
+            )
+        });
+        let outputs = (0..output_count).map(|index| {
+            (
+                PortId::Output(OutputPortId {
+                    node_id,
+                    port_index: index,
+                }),
+                get_out_port_position(
+                    node.node_type,
+                    index,
+                    Some(node.in_edges.len() as u8),
+                    Some(node.out_edges.len() as u8),
+                ),
+            )
+        });
+        for (port, (x, y)) in inputs.chain(outputs) {
+            let distance = point.distance(top_left + Vec2::new(x as f32, y as f32));
+            if distance <= radius && !matches!(nearest, Some((_, d)) if d <= distance) {
+                nearest = Some((port, distance));
+            }
+        }
+    }
+    nearest.map(|(port, _)| port)
+}
+
+// The narrowed type of a port if the worker has computed one, otherwise the type its node declares
+fn get_port_type(
+    port: &PortId,
+    port_types: &HashMap<PortId, AbstractDataType>,
+    entities: &HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
+) -> Result<AbstractDataType, WbblWebappStoreError> {
+    match port_types.get(port) {
+        Some(port_type) => Ok(*port_type),
+        None => get_declared_port_type_from_entities(port, entities),
+    }
+}
+
+fn get_declared_port_type_from_entities(
+    port: &PortId,
+    entities: &HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
+) -> Result<AbstractDataType, WbblWebappStoreError> {
+    let node_id = match port {
+        PortId::Input(id) => id.node_id,
+        PortId::Output(id) => id.node_id,
+    };
+    match entities.get(&WbblWebappGraphEntityId::NodeId(node_id)) {
+        Some(WbblWebappGraphEntity::Node(node)) => {
+            get_declared_port_type(node.node_type, &node.data, port)?
+                .ok_or(WbblWebappStoreError::NotFound)
+        }
+        _ => Err(WbblWebappStoreError::NotFound),
+    }
+}
+
+fn get_incoming_edge(
+    node_id: u128,
+    handle: i64,
+    entities: &HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
+) -> Option<u128> {
+    if let Some(WbblWebappGraphEntity::Node(node)) =
+        entities.get(&WbblWebappGraphEntityId::NodeId(node_id))
+    {
+        return node.in_edges.iter().copied().find(|edge| {
+            matches!(
+                entities.get(&WbblWebappGraphEntityId::EdgeId(*edge)),
+                Some(WbblWebappGraphEntity::Edge(edge)) if edge.target_handle == handle
+            )
+        });
+    }
+    None
+}
+
+fn would_create_cycle(
+    source: u128,
+    target: u128,
+    entities: &HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
+) -> bool {
+    is_reachable(target, source, |n| get_successor_nodes(n, entities))
+}
+
 impl WbblWebappNode {
     pub(crate) fn decode<Txn: yrs::ReadTxn>(
         key: &u128,
@@ -377,6 +682,38 @@ fn clear_local_selections(
         Ok(())
     }
 }
+fn get_client_selections<Txn: yrs::ReadTxn>(
+    txn: &Txn,
+    client_id: u64,
+    nodes: &MapRef,
+    edges: &MapRef,
+    node_groups_selections: &MapRef,
+) -> WbblWebappGraphEntityIds {
+    let client_id = client_id.to_string();
+    let get_selected = |entities: &MapRef| -> Vec<String> {
+        let mut selected: Vec<String> = entities
+            .iter(txn)
+            .filter(|(_, entity)| match entity {
+                yrs::Value::YMap(entity) => get_map("selections", txn, entity)
+                    .is_ok_and(|selections| selections.contains_key(txn, &client_id)),
+                _ => false,
+            })
+            .map(|(key, _)| key.to_owned())
+            .collect();
+        selected.sort();
+        selected
+    };
+    let mut groups: Vec<String> = get_map(&client_id, txn, node_groups_selections)
+        .map(|groups| groups.keys(txn).map(|key| key.to_owned()).collect())
+        .unwrap_or_default();
+    groups.sort();
+    WbblWebappGraphEntityIds {
+        nodes: get_selected(nodes),
+        edges: get_selected(edges),
+        groups,
+    }
+}
+
 const INFLATE_GROUP_PATH_BY: f32 = 25.0;
 
 impl WbblWebappNodeGroup {
@@ -569,7 +906,7 @@ fn update_entity(
     entity: &WbblWebappGraphEntity,
     entities: &mut HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
     js_entities: &mut HashMap<WbblWebappGraphEntityId, JsValue>,
-    spatial_index: Option<&mut RTree<WbblWebappGraphEntity>>,
+    spatial_index: &mut SpatialIndex,
 ) {
     let js_value = match entity {
         WbblWebappGraphEntity::Node(node) => serde_wasm_bindgen::to_value(&node).unwrap(),
@@ -577,16 +914,7 @@ fn update_entity(
         WbblWebappGraphEntity::Group(group) => serde_wasm_bindgen::to_value(&group).unwrap(),
     };
     js_entities.insert(*id, js_value);
-    if let Some(spatial_index) = spatial_index {
-        if let Some(prev_entity) = entities.get(id) {
-            if entity.envelope() != prev_entity.envelope() {
-                spatial_index.remove(prev_entity);
-                spatial_index.insert(entity.clone());
-            }
-        } else {
-            spatial_index.insert(entity.clone());
-        }
-    }
+    spatial_index.insert_or_update(entity);
 
     match entity {
         WbblWebappGraphEntity::Node(entity) => {
@@ -605,15 +933,11 @@ fn remove_entity(
     id: &WbblWebappGraphEntityId,
     entities: &mut HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
     js_entities: &mut HashMap<WbblWebappGraphEntityId, JsValue>,
-    spatial_index: &mut RTree<WbblWebappGraphEntity>,
+    spatial_index: &mut SpatialIndex,
 ) -> Option<WbblWebappGraphEntity> {
     js_entities.remove(id);
-    if let Some(entity) = entities.remove(id) {
-        spatial_index.remove(&entity);
-        Some(entity)
-    } else {
-        None
-    }
+    spatial_index.remove(id);
+    entities.remove(id)
 }
 
 fn insert_or_update_node_group(
@@ -621,7 +945,7 @@ fn insert_or_update_node_group(
     entities: &mut HashMap<WbblWebappGraphEntityId, WbblWebappGraphEntity>,
     dirty_groups: &mut HashSet<u128>,
     js_entities: &mut HashMap<WbblWebappGraphEntityId, JsValue>,
-    spatial_index: &mut RTree<WbblWebappGraphEntity>,
+    spatial_index: &mut SpatialIndex,
     locally_selected_entities: &mut HashSet<WbblWebappGraphEntityId>,
     group_uuid: u128,
 ) {
@@ -650,7 +974,7 @@ fn insert_or_update_node_group(
                             &WbblWebappGraphEntity::Edge(edge),
                             entities,
                             js_entities,
-                            None,
+                            spatial_index,
                         );
                     }
                 }
@@ -666,7 +990,7 @@ fn insert_or_update_node_group(
                             &WbblWebappGraphEntity::Edge(edge),
                             entities,
                             js_entities,
-                            None,
+                            spatial_index,
                         );
                     }
                 }
@@ -675,7 +999,7 @@ fn insert_or_update_node_group(
                     &WbblWebappGraphEntity::Group(group),
                     entities,
                     js_entities,
-                    None,
+                    spatial_index,
                 );
             }
         }
@@ -698,7 +1022,7 @@ fn insert_or_update_node_group(
                 &WbblWebappGraphEntity::Group(group),
                 entities,
                 js_entities,
-                None,
+                spatial_index,
             );
         } else if let Some(WbblWebappGraphEntity::Group(mut group)) = group {
             group.nodes.insert(node.id);
@@ -718,7 +1042,7 @@ fn insert_or_update_node_group(
                         &WbblWebappGraphEntity::Edge(edge),
                         entities,
                         js_entities,
-                        None,
+                        spatial_index,
                     );
                 }
             }
@@ -729,17 +1053,37 @@ fn insert_or_update_node_group(
                 &WbblWebappGraphEntity::Group(group),
                 entities,
                 js_entities,
-                None,
+                spatial_index,
             );
         }
     }
 }
 
-#[wasm_bindgen]
+/// Publishes the local presence on the next animation frame
+struct PresencePublisher {
+    awareness: Rc<RefCell<yrs::sync::Awareness>>,
+    presence: Rc<RefCell<WbblWebappPresence>>,
+}
+
+impl AnimationFrameProcessor for PresencePublisher {
+    fn process_frame(&mut self, _timestamp: f64) -> bool {
+        self.awareness
+            .borrow_mut()
+            .set_local_state(self.presence.borrow().encode());
+        false
+    }
+}
+
 impl WbblWebappGraphStore {
-    pub fn empty(graph_worker: Worker) -> Self {
+    fn new(graph_worker: Worker, id: u128) -> Result<Self, WbblWebappStoreError> {
         let graph_worker = Rc::new(graph_worker);
         let graph = yrs::Doc::new();
+        // The awareness shares the document, so updates applied by the sync connection
+        // flow through the same observers as local edits
+        let awareness = Rc::new(RefCell::new(yrs::sync::Awareness::with_clock(
+            graph.clone(),
+            WasmPackClock,
+        )));
         let nodes = Rc::new(graph.get_or_insert_map(GRAPH_YRS_NODES_MAP_KEY.to_owned()));
         let edges = Rc::new(graph.get_or_insert_map(GRAPH_YRS_EDGES_MAP_KEY.to_owned()));
         let node_group_selections =
@@ -756,22 +1100,64 @@ impl WbblWebappGraphStore {
         );
 
         let graph = Rc::new(graph);
-        let spatial_index: Rc<RefCell<RTree<WbblWebappGraphEntity>>> =
-            Rc::new(RefCell::new(RTree::new()));
+        let spatial_index: Rc<RefCell<SpatialIndex>> = Rc::new(RefCell::new(SpatialIndex::new()));
 
         let computed_types = Rc::new(RefCell::new(JsValue::null()));
+        let type_conflict = Rc::new(RefCell::new(JsValue::null()));
+        let diagnostics = Rc::new(RefCell::new(JsValue::null()));
+        let port_types: Rc<RefCell<HashMap<PortId, AbstractDataType>>> =
+            Rc::new(RefCell::new(HashMap::new()));
         let locally_selected_entities: Rc<RefCell<HashSet<WbblWebappGraphEntityId>>> =
             Rc::new(RefCell::new(HashSet::new()));
         let listeners = Rc::new(RefCell::new(Vec::<(u32, js_sys::Function)>::new()));
+        let presence_listeners = Rc::new(RefCell::new(Vec::<(u32, js_sys::Function)>::new()));
+        let pending_worker_requests: PendingWorkerRequests = Rc::new(RefCell::new(HashMap::new()));
+        let reset_request_id = Rc::new(Cell::new(u32::MAX));
         let worker_responder = Closure::<dyn FnMut(MessageEvent)>::new({
             let computed_types = computed_types.clone();
+            let port_types = port_types.clone();
+            let type_conflict = type_conflict.clone();
+            let diagnostics = diagnostics.clone();
             let listeners: Rc<RefCell<Vec<(u32, js_sys::Function)>>> = listeners.clone();
+            let pending_worker_requests = pending_worker_requests.clone();
+            let reset_request_id = reset_request_id.clone();
             move |msg: MessageEvent| {
-                match serde_wasm_bindgen::from_value::<WbblGraphWebWorkerResponseMessage>(
+                let response = match serde_wasm_bindgen::from_value::<WbblGraphWebWorkerResponse>(
                     msg.data(),
                 ) {
-                    Ok(WbblGraphWebWorkerResponseMessage::TypesUpdated(types)) => {
-                        computed_types.replace(serde_wasm_bindgen::to_value(&types).unwrap());
+                    Ok(response) => response,
+                    Err(_) => {
+                        log!("Malformed message");
+                        return;
+                    }
+                };
+                // Replies to other stores' requests, and broadcasts while the worker holds
+                // another store's graph, describe their graphs, not this one
+                let pending = match response.request_id {
+                    Some(request_id) if request_id == reset_request_id.get() => {
+                        WORKER_RESET_FOR.store(request_id, Ordering::Relaxed);
+                        return;
+                    }
+                    Some(request_id) => {
+                        match pending_worker_requests.borrow_mut().remove(&request_id) {
+                            Some(pending) => Some(pending),
+                            None => return,
+                        }
+                    }
+                    None if WORKER_RESET_FOR.load(Ordering::Relaxed) != reset_request_id.get() => {
+                        return;
+                    }
+                    None => None,
+                };
+                match &response.message {
+                    WbblGraphWebWorkerResponseMessage::TypesUpdated(types) => {
+                        let Ok(types_js) = serde_wasm_bindgen::to_value(types) else {
+                            log!("Could not convert types");
+                            return;
+                        };
+                        computed_types.replace(types_js);
+                        port_types.replace(types.clone());
+                        type_conflict.replace(JsValue::null());
                         for (_, listener) in listeners.borrow().iter() {
                             listener
                                 .call0(&JsValue::UNDEFINED)
@@ -779,16 +1165,50 @@ impl WbblWebappGraphStore {
                                 .unwrap();
                         }
                     }
-                    Ok(WbblGraphWebWorkerResponseMessage::TypeUnificationFailure) => {
+                    WbblGraphWebWorkerResponseMessage::TypeUnificationFailure(conflict) => {
                         log!("Type unification failed");
+                        type_conflict.replace(
+                            conflict
+                                .as_ref()
+                                .and_then(|c| serde_wasm_bindgen::to_value(c).ok())
+                                .unwrap_or(JsValue::null()),
+                        );
+                        for (_, listener) in listeners.borrow().iter() {
+                            listener
+                                .call0(&JsValue::UNDEFINED)
+                                .map_err(|_| WbblWebappStoreError::FailedToEmit)
+                                .unwrap();
+                        }
                     }
-                    Ok(WbblGraphWebWorkerResponseMessage::Ready) => {}
-                    Err(_) => {
-                        log!("Malformed message");
+                    WbblGraphWebWorkerResponseMessage::DiagnosticsUpdated(node_diagnostics) => {
+                        let Ok(diagnostics_js) = serde_wasm_bindgen::to_value(node_diagnostics)
+                        else {
+                            log!("Could not convert diagnostics");
+                            return;
+                        };
+                        diagnostics.replace(diagnostics_js);
+                        for (_, listener) in listeners.borrow().iter() {
+                            listener
+                                .call0(&JsValue::UNDEFINED)
+                                .map_err(|_| WbblWebappStoreError::FailedToEmit)
+                                .unwrap();
+                        }
+                    }
+                    WbblGraphWebWorkerResponseMessage::Failed(err)
+                        if response.request_id.is_none() =>
+                    {
+                        log!("Graph worker failed {:?}", err);
                     }
+                    _ => {}
                 };
+                if let Some(pending) = pending {
+                    pending.publish(response.message);
+                }
             }
         });
+        // The worker is shared between graphs, so it may still hold the previous one
+        post_worker_reset(&graph_worker, &reset_request_id)
+            .map_err(|_| WbblWebappStoreError::WorkerFailure)?;
         graph_worker
             .add_event_listener_with_callback("message", worker_responder.as_ref().unchecked_ref())
             .unwrap();
@@ -809,7 +1229,6 @@ impl WbblWebappGraphStore {
             move |txn, evts| {
                 let mut dirty_groups: HashSet<u128> = HashSet::new();
                 // TODO: Batch group updates
-                // TODO: Only update spatial index when necessary
 
                 for evt in evts.iter() {
                     let path = evt.path();
@@ -838,7 +1257,7 @@ impl WbblWebappGraphStore {
                                                     &WbblWebappGraphEntity::Node(node.clone()),
                                                     &mut entities,
                                                     &mut js_entities,
-                                                    Some(&mut spatial_index),
+                                                    &mut spatial_index,
                                                 );
                                                 if let Some(group_id) = node.group_id {
                                                     insert_or_update_node_group(
@@ -911,7 +1330,7 @@ impl WbblWebappGraphStore {
                                                 &WbblWebappGraphEntity::Node(node.clone()),
                                                 &mut entities,
                                                 &mut js_entities,
-                                                None,
+                                                &mut spatial_index,
                                             );
                                             if let yrs::types::Event::Map(map_evt) = evt {
                                                 let keys = map_evt.keys(txn);
@@ -919,7 +1338,6 @@ impl WbblWebappGraphStore {
                                                     let group_id_evt =
                                                         keys.get("group_id").unwrap();
                                                     // TODO: Update both groups and edges
-                                                    // TODO: Update Spatial Index
                                                     match group_id_evt {
                                                         yrs::types::EntryChange::Inserted(
                                                             yrs::Value::Any(yrs::Any::String(
@@ -1009,7 +1427,7 @@ impl WbblWebappGraphStore {
                                                         &WbblWebappGraphEntity::Node(node.clone()),
                                                         &mut entities,
                                                         &mut js_entities,
-                                                        Some(&mut spatial_index),
+                                                        &mut spatial_index,
                                                     );
                                                     if let Some(group_id) = node.group_id {
                                                         dirty_groups.insert(group_id);
@@ -1043,7 +1461,7 @@ impl WbblWebappGraphStore {
                                                                 ),
                                                                 &mut entities,
                                                                 &mut js_entities,
-                                                                Some(&mut spatial_index),
+                                                                &mut spatial_index,
                                                             );
                                                         };
                                                     }
@@ -1074,7 +1492,7 @@ impl WbblWebappGraphStore {
                                                                 ),
                                                                 &mut entities,
                                                                 &mut js_entities,
-                                                                Some(&mut spatial_index),
+                                                                &mut spatial_index,
                                                             );
                                                         };
                                                     }
@@ -1088,6 +1506,7 @@ impl WbblWebappGraphStore {
                     } else if let Some(PathSegment::Key(node_id)) = path.front() {
                         let mut entities = entities.borrow_mut();
                         let mut js_entities = js_entities.borrow_mut();
+                        let mut spatial_index = spatial_index.borrow_mut();
                         if let Ok(node_id_uuid) = try_into_u128(node_id) {
                             let key = WbblWebappGraphEntityId::NodeId(node_id_uuid);
                             if let Some(WbblWebappGraphEntity::Node(prev_node)) = entities.get(&key)
@@ -1121,7 +1540,7 @@ impl WbblWebappGraphStore {
                                             &WbblWebappGraphEntity::Node(node.clone()),
                                             &mut entities,
                                             &mut js_entities,
-                                            None,
+                                            &mut spatial_index,
                                         );
                                     }
                                 }
@@ -1145,7 +1564,7 @@ impl WbblWebappGraphStore {
                             &WbblWebappGraphEntity::Group(group),
                             &mut entities,
                             &mut js_entities,
-                            Some(&mut spatial_index),
+                            &mut spatial_index,
                         );
                     }
                 }
@@ -1181,7 +1600,6 @@ impl WbblWebappGraphStore {
 
                                                 let mut source_position = Vec2::ZERO;
                                                 let mut target_position = Vec2::ZERO;
-                                                // TODO: Update Spatial Index
                                                 let mut entities = entities.borrow_mut();
                                                 let mut js_entities = js_entities.borrow_mut();
                                                 let mut spatial_index = spatial_index.borrow_mut();
@@ -1212,7 +1630,7 @@ impl WbblWebappGraphStore {
                                                         &WbblWebappGraphEntity::Node(n.clone()),
                                                         &mut entities,
                                                         &mut js_entities,
-                                                        None,
+                                                        &mut spatial_index,
                                                     );
                                                 };
 
@@ -1223,10 +1641,10 @@ impl WbblWebappGraphStore {
                                                     target_node
                                                 {
                                                     target_group_id = n.group_id;
-                                                    n.out_edges.insert(edge_uuid);
+                                                    n.in_edges.insert(edge_uuid);
                                                     let (mut x, mut y) = get_in_port_position(
                                                         n.node_type,
-                                                        edge.source_handle as u8,
+                                                        edge.target_handle as u8,
                                                     );
                                                     x += n.position.x;
                                                     y += n.position.y;
@@ -1237,7 +1655,7 @@ impl WbblWebappGraphStore {
                                                         &WbblWebappGraphEntity::Node(n.clone()),
                                                         &mut entities,
                                                         &mut js_entities,
-                                                        None,
+                                                        &mut spatial_index,
                                                     );
                                                 }
                                                 edge.source_position = source_position;
@@ -1262,7 +1680,7 @@ impl WbblWebappGraphStore {
                                                             ),
                                                             &mut entities,
                                                             &mut js_entities,
-                                                            None,
+                                                            &mut spatial_index,
                                                         );
                                                     }
                                                 }
@@ -1272,7 +1690,7 @@ impl WbblWebappGraphStore {
                                                     &WbblWebappGraphEntity::Edge(edge.clone()),
                                                     &mut entities,
                                                     &mut js_entities,
-                                                    Some(&mut spatial_index),
+                                                    &mut spatial_index,
                                                 );
                                             }
                                         }
@@ -1307,11 +1725,10 @@ impl WbblWebappGraphStore {
                                                             ),
                                                             &mut entities,
                                                             &mut js_entities,
-                                                            None,
+                                                            &mut spatial_index,
                                                         );
                                                     }
                                                 }
-                                                // TODO: Update Spatial Index
                                                 let source_node = entities.get_mut(
                                                     &WbblWebappGraphEntityId::NodeId(
                                                         prev_edge.source,
@@ -1326,7 +1743,7 @@ impl WbblWebappGraphStore {
                                                         &WbblWebappGraphEntity::Node(n.clone()),
                                                         &mut entities,
                                                         &mut js_entities,
-                                                        None,
+                                                        &mut spatial_index,
                                                     );
                                                 }
 
@@ -1338,13 +1755,13 @@ impl WbblWebappGraphStore {
                                                 if let Some(WbblWebappGraphEntity::Node(n)) =
                                                     target_node
                                                 {
-                                                    n.out_edges.remove(&edge_uuid);
+                                                    n.in_edges.remove(&edge_uuid);
                                                     update_entity(
                                                         &WbblWebappGraphEntityId::NodeId(n.id),
                                                         &WbblWebappGraphEntity::Node(n.clone()),
                                                         &mut entities,
                                                         &mut js_entities,
-                                                        None,
+                                                        &mut spatial_index,
                                                     );
                                                 }
                                             }
@@ -1397,7 +1814,7 @@ impl WbblWebappGraphStore {
                                                 &WbblWebappGraphEntity::Edge(edge.clone()),
                                                 &mut entities,
                                                 &mut js_entities,
-                                                Some(&mut spatial_index),
+                                                &mut spatial_index,
                                             );
                                         }
                                     }
@@ -1414,6 +1831,7 @@ impl WbblWebappGraphStore {
             let client_id = graph.client_id().to_string();
             let entities = entities.clone();
             let js_entities = js_entities.clone();
+            let spatial_index = spatial_index.clone();
             move |txn, evts| {
                 for evt in evts.iter() {
                     if let yrs::types::Event::Map(map_evt) = evt {
@@ -1425,6 +1843,7 @@ impl WbblWebappGraphStore {
                                     if let Ok(key) = try_into_u128(key) {
                                         let mut entities = entities.borrow_mut();
                                         let mut js_entities = js_entities.borrow_mut();
+                                        let mut spatial_index = spatial_index.borrow_mut();
                                         if let Some(WbblWebappGraphEntity::Group(group)) =
                                             entities.get_mut(&WbblWebappGraphEntityId::GroupId(key))
                                         {
@@ -1468,7 +1887,7 @@ impl WbblWebappGraphStore {
                                                 &WbblWebappGraphEntity::Group(group.clone()),
                                                 &mut entities,
                                                 &mut js_entities,
-                                                None,
+                                                &mut spatial_index,
                                             );
                                         }
                                     }
@@ -1491,40 +1910,134 @@ impl WbblWebappGraphStore {
                             .inspect_err(|err| log!("Publish error: {:?}", err));
                     }
                     let update = update.update.clone();
-                    let snapshot_js_value = serde_wasm_bindgen::to_value(
-                        &WbblGraphWebWorkerRequestMessage::ReceiveUpdate(update),
+                    let _ = post_worker_request(
+                        &graph_worker,
+                        None,
+                        WbblGraphWebWorkerRequestMessage::ReceiveUpdate(update),
                     )
-                    .unwrap();
-                    graph_worker.post_message(&snapshot_js_value).unwrap();
+                    .inspect_err(|err| log!("Failed to send update to worker {:?}", err));
                 }
             })
             .unwrap();
 
+        let local_client_id = graph.client_id();
+        let awareness_subscription = awareness.borrow().on_update({
+            let presence_listeners = presence_listeners.clone();
+            move |evt| {
+                // Collaborators exclude the local client, so publishing our own presence
+                // doesn't need to re-render them
+                if evt
+                    .added()
+                    .iter()
+                    .chain(evt.updated().iter())
+                    .chain(evt.removed().iter())
+                    .all(|client_id| *client_id == local_client_id)
+                {
+                    return;
+                }
+                // Remote presence is applied while the sync connection holds the awareness,
+                // so listeners are notified once it has been released
+                let presence_listeners = presence_listeners.clone();
+                let closure = Closure::once(Box::new(move || {
+                    for (_, listener) in presence_listeners.borrow().iter() {
+                        let _ = listener
+                            .call0(&JsValue::UNDEFINED)
+                            .inspect_err(|err| log!("Publish error: {:?}", err));
+                    }
+                }) as Box<dyn FnMut()>);
+                let _ = window()
+                    .expect("EXPECTED WINDOW")
+                    .set_timeout_with_callback_and_timeout_and_arguments_0(
+                        closure.as_ref().unchecked_ref(),
+                        0,
+                    );
+                std::mem::forget(closure);
+            }
+        });
+
         let subscriptions = vec![
             nodes_subscription,
             edges_subscription,
             node_group_selections_subscription,
             doc_subscription,
+            awareness_subscription,
         ];
 
-        let mut store = WbblWebappGraphStore {
-            id: uuid::Uuid::new_v4().as_u128(),
+        let presence = Rc::new(RefCell::new(WbblWebappPresence::default()));
+        let mut cursor_publisher = AnimationFrameHandler::new(window().expect("EXPECTED WINDOW"));
+        cursor_publisher.set_processor(Rc::new(RefCell::new(PresencePublisher {
+            awareness: awareness.clone(),
+            presence: presence.clone(),
+        })));
+
+        Ok(WbblWebappGraphStore {
+            id,
             next_listener_handle: 0,
             listeners: listeners.clone(),
+            presence_listeners,
+            presence,
+            cursor_publisher,
             undo_manager,
             graph: graph.clone(),
+            awareness,
+            websockets_sync: None,
             nodes,
             edges,
             node_group_selections,
             computed_types: computed_types.clone(),
+            port_types,
+            type_conflict,
+            diagnostics,
             locally_selected_entities,
             spatial_index: spatial_index.clone(),
             js_entities: js_entities.clone(),
             graph_worker: graph_worker.clone(),
             worker_responder,
+            pending_worker_requests,
+            reset_request_id,
             entities,
             subscriptions,
-        };
+        })
+    }
+
+    fn track_local_changes(&mut self) {
+        {
+            let mut txn_mut = self.graph.transact_mut();
+
+            let local_node_group_selections = self.node_group_selections.insert(
+                &mut txn_mut,
+                self.graph.client_id().to_string(),
+                MapPrelim::<bool>::new(),
+            );
+            self.undo_manager.expand_scope(&local_node_group_selections);
+        }
+        self.undo_manager.include_origin(self.graph.client_id()); // only track changes originating from local peer
+        self.undo_manager.expand_scope(&self.edges.as_ref());
+    }
+
+    fn request_from_worker(
+        &mut self,
+        message: WbblGraphWebWorkerRequestMessage,
+    ) -> js_sys::Promise {
+        let request_id = NEXT_WORKER_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let reply = CallbackFuture::new();
+        self.pending_worker_requests
+            .borrow_mut()
+            .insert(request_id, reply.clone());
+        if let Err(err) = post_worker_request(&self.graph_worker, Some(request_id), message) {
+            self.pending_worker_requests
+                .borrow_mut()
+                .remove(&request_id);
+            reply.publish(WbblGraphWebWorkerResponseMessage::Failed(err));
+        }
+        wasm_bindgen_futures::future_to_promise(async move { worker_reply_to_js(reply.await) })
+    }
+}
+
+#[wasm_bindgen]
+impl WbblWebappGraphStore {
+    pub fn empty(graph_worker: Worker) -> Result<WbblWebappGraphStore, WbblWebappStoreError> {
+        let mut store = WbblWebappGraphStore::new(graph_worker, uuid::Uuid::new_v4().as_u128())?;
 
         let output_node = NewWbblWebappNode::new(600.0, 500.0, WbblWebappNodeType::Output).unwrap();
         store.add_node(output_node.clone()).unwrap();
@@ -1538,25 +2051,37 @@ impl WbblWebappGraphStore {
                 &uuid::Uuid::from_u128(output_node.id).to_string(),
                 0,
                 0,
+                false,
             )
             .unwrap();
+        store.track_local_changes();
+
+        Ok(store)
+    }
+
+    pub fn connect(
+        graph_worker: Worker,
+        project_name: &str,
+        graph_id: &str,
+    ) -> Result<WbblWebappGraphStore, WbblWebappStoreError> {
+        let id = try_into_u128(graph_id)?;
+        // Unlike an empty graph, the output node is not created here, as the
+        // document's contents are received from the sync server
+        let mut store = WbblWebappGraphStore::new(graph_worker, id)?;
+        store.track_local_changes();
+        store.websockets_sync = Some(
+            AwarenessWebsocketSync::try_create(
+                store.awareness.clone(),
+                &format!(
+                    "/api/projects/{}/graphs/{}/connect",
+                    String::from(js_sys::encode_uri_component(project_name)),
+                    String::from(js_sys::encode_uri_component(graph_id))
+                ),
+            )
+            .map_err(|_| WbblWebappStoreError::SubscriptionFailure)?,
+        );
 
-        {
-            let mut txn_mut = store.graph.transact_mut();
-
-            let local_node_group_selections = store.node_group_selections.insert(
-                &mut txn_mut,
-                store.graph.client_id().to_string(),
-                MapPrelim::<bool>::new(),
-            );
-            store
-                .undo_manager
-                .expand_scope(&local_node_group_selections);
-        }
-        store.undo_manager.include_origin(store.graph.client_id()); // only track changes originating from local peer
-        store.undo_manager.expand_scope(&store.edges.as_ref());
-
-        store
+        Ok(store)
     }
 
     pub fn subscribe(&mut self, subscriber: js_sys::Function) -> u32 {
@@ -1577,6 +2102,81 @@ impl WbblWebappGraphStore {
         }
     }
 
+    pub fn subscribe_presence(&mut self, subscriber: js_sys::Function) -> u32 {
+        let handle = self.next_listener_handle;
+        self.presence_listeners
+            .borrow_mut()
+            .push((handle, subscriber));
+        self.next_listener_handle += 1;
+        handle
+    }
+
+    pub fn unsubscribe_presence(&mut self, handle: u32) {
+        let mut presence_listeners = self.presence_listeners.borrow_mut();
+        if let Some((idx, _)) = presence_listeners
+            .iter()
+            .enumerate()
+            .find(|(_, (k, _))| *k == handle)
+        {
+            let _ = presence_listeners.remove(idx);
+        }
+    }
+
+    /// Returns `None` for graphs that aren't connected to a sync server
+    pub fn get_connection_state(&self) -> Option<ConnectionState> {
+        self.websockets_sync
+            .as_ref()
+            .map(|sync| sync.connection_state())
+    }
+
+    /// Whether the graph has received its initial contents. Graphs without a sync server
+    /// are always hydrated
+    pub fn is_hydrated(&self) -> bool {
+        match self.websockets_sync.as_ref() {
+            Some(sync) => sync.is_hydrated(),
+            None => true,
+        }
+    }
+
+    pub fn subscribe_connection_state(&mut self, subscriber: js_sys::Function) -> Option<u32> {
+        self.websockets_sync
+            .as_mut()
+            .map(|sync| sync.subscribe(subscriber))
+    }
+
+    pub fn unsubscribe_connection_state(&mut self, handle: u32) {
+        if let Some(sync) = self.websockets_sync.as_mut() {
+            sync.unsubscribe(handle);
+        }
+    }
+
+    /// Compiles the graph in the worker, resolving with a summary of the compiled stages
+    pub fn compile(&mut self) -> js_sys::Promise {
+        self.request_from_worker(WbblGraphWebWorkerRequestMessage::Compile)
+    }
+
+    /// Resolves with the worker's current port types, rejecting if they can't be unified
+    pub fn get_types(&mut self) -> js_sys::Promise {
+        self.request_from_worker(WbblGraphWebWorkerRequestMessage::GetTypes)
+    }
+
+    /// Replaces the worker's copy of the graph with the store's, resolving once it has been applied
+    pub fn reset_worker(&mut self) -> js_sys::Promise {
+        if let Err(err) = post_worker_reset(&self.graph_worker, &self.reset_request_id) {
+            return js_sys::Promise::reject(&err.into());
+        }
+        let state = self
+            .graph
+            .transact()
+            .encode_state_as_update_v1(&yrs::StateVector::default());
+        self.request_from_worker(WbblGraphWebWorkerRequestMessage::ReceiveUpdate(state))
+    }
+
+    /// Stops the worker. It is shared between graphs, so this should only be done on teardown
+    pub fn shutdown_worker(&mut self) -> js_sys::Promise {
+        self.request_from_worker(WbblGraphWebWorkerRequestMessage::Shutdown)
+    }
+
     pub fn undo(&mut self) -> Result<bool, WbblWebappStoreError> {
         let result = self
             .undo_manager
@@ -1655,8 +2255,30 @@ impl WbblWebappGraphStore {
         ])
         .into();
 
+        let type_conflict: JsValue = self.type_conflict.borrow().clone();
+        let type_conflict = js_sys::Array::from_iter([
+            js_sys::JsString::from_str("type_conflict").unwrap().into(),
+            type_conflict,
+        ])
+        .into();
+
+        let diagnostics: JsValue = self.diagnostics.borrow().clone();
+        let diagnostics = js_sys::Array::from_iter([
+            js_sys::JsString::from_str("diagnostics").unwrap().into(),
+            diagnostics,
+        ])
+        .into();
+
         js_sys::Object::from_entries(
-            &js_sys::Array::from_iter([nodes, edges, node_groups, graph_types]).into(),
+            &js_sys::Array::from_iter([
+                nodes,
+                edges,
+                node_groups,
+                graph_types,
+                type_conflict,
+                diagnostics,
+            ])
+            .into(),
         )
         .map(|x| x.into())
         .map_err(|_| WbblWebappStoreError::SerializationFailure)
@@ -1766,6 +2388,115 @@ impl WbblWebappGraphStore {
         Ok(())
     }
 
+    pub fn auto_layout(
+        &mut self,
+        selection_only: bool,
+        direction: LayoutDirection,
+    ) -> Result<(), WbblWebappStoreError> {
+        let node_ids: HashSet<u128> = if selection_only {
+            self.locally_selected_entities
+                .borrow()
+                .iter()
+                .filter_map(|x| match x {
+                    WbblWebappGraphEntityId::NodeId(id) => Some(*id),
+                    _ => None,
+                })
+                .collect()
+        } else {
+            self.entities
+                .borrow()
+                .keys()
+                .filter_map(|x| match x {
+                    WbblWebappGraphEntityId::NodeId(id) => Some(*id),
+                    _ => None,
+                })
+                .collect()
+        };
+        self.apply_layout(&node_ids, direction)
+    }
+
+    pub fn auto_layout_nodes(
+        &mut self,
+        node_ids: JsValue,
+        direction: LayoutDirection,
+    ) -> Result<(), WbblWebappStoreError> {
+        let node_ids: Vec<String> = serde_wasm_bindgen::from_value(node_ids)
+            .map_err(|_| WbblWebappStoreError::SerializationFailure)?;
+        let node_ids = node_ids
+            .iter()
+            .map(|id| try_into_u128(id))
+            .collect::<Result<HashSet<u128>, WbblWebappStoreError>>()?;
+        self.apply_layout(&node_ids, direction)
+    }
+
+    fn apply_layout(
+        &mut self,
+        node_ids: &HashSet<u128>,
+        direction: LayoutDirection,
+    ) -> Result<(), WbblWebappStoreError> {
+        let snapshot = self.get_graph_snapshot();
+        let options = LayoutOptions {
+            direction,
+            ..Default::default()
+        };
+        let mut positions: Vec<(u128, Vec2)> = layout_nodes(&snapshot, node_ids, &options)?
+            .into_iter()
+            .collect();
+        positions.sort_by_key(|(node_id, _)| *node_id);
+        // A single transaction, so that the whole layout is undone in one step
+        let mut mut_transaction = self.graph.transact_mut_with(self.graph.client_id());
+        for (node_id, position) in positions {
+            let node_id = uuid::Uuid::from_u128(node_id).to_string();
+            let node_ref = get_map(&node_id, &mut_transaction, &self.nodes)?;
+            node_ref.insert(
+                &mut mut_transaction,
+                "x",
+                yrs::Any::Number(position.x as f64),
+            );
+            node_ref.insert(
+                &mut mut_transaction,
+                "y",
+                yrs::Any::Number(position.y as f64),
+            );
+            node_ref.insert(&mut mut_transaction, "dragging", yrs::Any::Bool(false));
+        }
+        Ok(())
+    }
+
+    pub fn set_node_data(
+        &mut self,
+        node_id: &str,
+        key: &str,
+        value: JsValue,
+    ) -> Result<(), WbblWebappStoreError> {
+        let value: Any = serde_wasm_bindgen::from_value(value)
+            .map_err(|_| WbblWebappStoreError::SerializationFailure)?;
+        {
+            let mut mut_transaction = self.graph.transact_mut_with(self.graph.client_id());
+            encode_node_data_entries(
+                &mut mut_transaction,
+                node_id,
+                &self.nodes,
+                HashMap::from([(key.to_owned(), value)]),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn set_node_data_batch(
+        &mut self,
+        node_id: &str,
+        entries: JsValue,
+    ) -> Result<(), WbblWebappStoreError> {
+        let entries: HashMap<String, Any> = serde_wasm_bindgen::from_value(entries)
+            .map_err(|_| WbblWebappStoreError::SerializationFailure)?;
+        {
+            let mut mut_transaction = self.graph.transact_mut_with(self.graph.client_id());
+            encode_node_data_entries(&mut mut_transaction, node_id, &self.nodes, entries)?;
+        }
+        Ok(())
+    }
+
     pub fn set_node_selections(
         &mut self,
         node_ids: JsValue,
@@ -1804,16 +2535,66 @@ impl WbblWebappGraphStore {
         Ok(())
     }
 
+    // Checks an edge can be added, returning the edge already connected to the input port which
+    // it would replace
+    fn validate_new_edge(
+        &self,
+        source: u128,
+        target: u128,
+        source_handle: i64,
+        target_handle: i64,
+        replace_existing: bool,
+    ) -> Result<Option<u128>, WbblWebappStoreError> {
+        let entities = self.entities.borrow();
+        if would_create_cycle(source, target, &entities) {
+            return Err(WbblWebappStoreError::WouldCreateCycle);
+        }
+        let replaced_edge = get_incoming_edge(target, target_handle, &entities);
+        if replaced_edge.is_some() && !replace_existing {
+            return Err(WbblWebappStoreError::InputAlreadyConnected);
+        }
+        let source_port = PortId::Output(OutputPortId {
+            node_id: source,
+            port_index: source_handle as u8,
+        });
+        let target_port = PortId::Input(InputPortId {
+            node_id: target,
+            port_index: target_handle as u8,
+        });
+        let port_types = self.port_types.borrow();
+        let source_type = get_port_type(&source_port, &port_types, &entities)?;
+        // The narrowed type of a connected input depends on the edge being replaced, so check
+        // against what the input accepts without it
+        let target_type = match replaced_edge {
+            Some(_) => get_declared_port_type_from_entities(&target_port, &entities)?,
+            None => get_port_type(&target_port, &port_types, &entities)?,
+        };
+        if !AbstractDataType::are_types_compatible(source_type, target_type) {
+            return Err(WbblWebappStoreError::IncompatiblePortTypes);
+        }
+        Ok(replaced_edge)
+    }
+
+    /// Connects an output port to an input port. If the input port is already connected, the
+    /// existing edge is replaced when `replace_existing` is set and the edge is rejected otherwise
     pub fn add_edge(
         &mut self,
         source: &str,
         target: &str,
         source_handle: i64,
         target_handle: i64,
+        replace_existing: bool,
     ) -> Result<(), WbblWebappStoreError> {
         {
             let source_uuid = try_into_u128(source)?;
             let target_uuid = try_into_u128(target)?;
+            let replaced_edge = self.validate_new_edge(
+                source_uuid,
+                target_uuid,
+                source_handle,
+                target_handle,
+                replace_existing,
+            )?;
             let edge = {
                 let entities = self.entities.borrow();
                 match (
@@ -1842,11 +2623,109 @@ impl WbblWebappGraphStore {
             }?;
 
             let mut mut_transaction = self.graph.transact_mut_with(self.graph.client_id());
+            if let Some(replaced_edge) = replaced_edge {
+                delete_edge(&mut mut_transaction, replaced_edge, &self.edges)?;
+            }
             edge.encode(&mut mut_transaction, &self.edges)?;
         }
         Ok(())
     }
 
+    /// Whether `add_edge` would accept the edge, replacing any edge already connected to the input
+    pub fn can_add_edge(
+        &self,
+        source: &str,
+        target: &str,
+        source_handle: i64,
+        target_handle: i64,
+    ) -> Result<bool, WbblWebappStoreError> {
+        let source = try_into_u128(source)?;
+        let target = try_into_u128(target)?;
+        Ok(self
+            .validate_new_edge(source, target, source_handle, target_handle, true)
+            .is_ok())
+    }
+
+    pub fn would_create_cycle(
+        &self,
+        source: &str,
+        target: &str,
+    ) -> Result<bool, WbblWebappStoreError> {
+        let source = try_into_u128(source)?;
+        let target = try_into_u128(target)?;
+        Ok(would_create_cycle(source, target, &self.entities.borrow()))
+    }
+
+    pub fn query_rect(&self, min: &[f32], max: &[f32]) -> Result<JsValue, WbblWebappStoreError> {
+        let ids: WbblWebappGraphEntityIds = self
+            .spatial_index
+            .borrow()
+            .query_rect(vec2_from_slice(min)?, vec2_from_slice(max)?)
+            .into();
+        serde_wasm_bindgen::to_value(&ids).map_err(|_| WbblWebappStoreError::SerializationFailure)
+    }
+
+    pub fn entities_in_viewport(
+        &self,
+        translation: &[f32],
+        zoom: f32,
+        size: &[f32],
+        margin: f32,
+    ) -> Result<JsValue, WbblWebappStoreError> {
+        let ids: WbblWebappGraphEntityIds = self
+            .spatial_index
+            .borrow()
+            .entities_in_viewport(
+                vec2_from_slice(translation)?,
+                zoom,
+                vec2_from_slice(size)?,
+                margin,
+            )
+            .into();
+        serde_wasm_bindgen::to_value(&ids).map_err(|_| WbblWebappStoreError::SerializationFailure)
+    }
+
+    pub fn node_at(&self, point: &[f32]) -> Result<Option<String>, WbblWebappStoreError> {
+        Ok(self
+            .spatial_index
+            .borrow()
+            .node_at(vec2_from_slice(point)?)
+            .map(|node_id| uuid::Uuid::from_u128(node_id).to_string()))
+    }
+
+    pub fn nearest_port(
+        &self,
+        point: &[f32],
+        radius: f32,
+    ) -> Result<Option<String>, WbblWebappStoreError> {
+        Ok(get_nearest_port(
+            vec2_from_slice(point)?,
+            radius,
+            &self.spatial_index.borrow(),
+            &self.entities.borrow(),
+        )
+        .map(|port| port.into()))
+    }
+
+    fn get_graph_snapshot(&self) -> WbblWebappGraphSnapshot {
+        let mut nodes: Vec<WbblWebappNode> = Vec::new();
+        let mut edges: Vec<WbblWebappEdge> = Vec::new();
+        for entity in self.entities.borrow().values() {
+            match entity {
+                WbblWebappGraphEntity::Node(node) => nodes.push(node.clone()),
+                WbblWebappGraphEntity::Edge(edge) => edges.push(edge.clone()),
+                WbblWebappGraphEntity::Group(_) => {}
+            }
+        }
+        nodes.sort_by_key(|n| n.id);
+        edges.sort_by_key(|e| e.id);
+        WbblWebappGraphSnapshot {
+            id: self.id,
+            nodes,
+            edges,
+        }
+    }
+
     fn get_selection_snapshot(&self) -> Result<WbblWebappGraphSnapshot, WbblWebappStoreError> {
         let mut nodes: Vec<WbblWebappNode> = Vec::new();
         let mut edges: Vec<WbblWebappEdge> = Vec::new();
@@ -2025,6 +2904,14 @@ impl WbblWebappGraphStore {
             if let Some(position) = position {
                 snapshot.recenter(&position);
             }
+            let snapshot_edges: Vec<(u128, u128)> = snapshot
+                .edges
+                .iter()
+                .map(|e| (e.source, e.target))
+                .collect();
+            if edges_contain_cycle(&snapshot_edges) {
+                return Err(WbblWebappStoreError::WouldCreateCycle);
+            }
             for node in snapshot.nodes.iter() {
                 node.encode(&mut mut_transaction, &self.nodes)?;
             }
@@ -2129,7 +3016,7 @@ impl WbblWebappGraphStore {
     ) -> Result<(), WbblWebappStoreError> {
         let mut snapshot: WbblWebappGraphSnapshot = serde_wasm_bindgen::from_value(value)
             .map_err(|_| WbblWebappStoreError::SerializationFailure)?;
-        let position = Vec2::from_slice(cursor_position);
+        let position = vec2_from_slice(cursor_position)?;
         self.integrate_snapshot(Some(position), &mut snapshot)?;
         Ok(())
     }
@@ -2318,6 +3205,72 @@ impl WbblWebappGraphStore {
         return self.locally_selected_entities.borrow().len() > 0;
     }
 
+    fn publish_presence(&mut self) {
+        // The presence published here includes any cursor move still waiting for a frame
+        self.cursor_publisher.cancel();
+        self.awareness
+            .borrow_mut()
+            .set_local_state(self.presence.borrow().encode());
+    }
+
+    pub fn set_cursor_position(&mut self, x: f64, y: f64) {
+        self.presence.borrow_mut().cursor = Some(WbblePosition { x, y });
+        // Mouse moves arrive far more often than collaborators can render them, so the
+        // cursor is published at most once per animation frame
+        if !self.cursor_publisher.is_scheduled() {
+            self.cursor_publisher.start();
+        }
+    }
+
+    pub fn clear_cursor_position(&mut self) {
+        self.presence.borrow_mut().cursor = None;
+        self.publish_presence();
+    }
+
+    pub fn set_viewport(&mut self, x: f64, y: f64, zoom: f64) {
+        self.presence.borrow_mut().viewport = Some(WbblWebappViewport { x, y, zoom });
+        self.publish_presence();
+    }
+
+    pub fn set_dragged_entities(
+        &mut self,
+        entity_ids: JsValue,
+    ) -> Result<(), WbblWebappStoreError> {
+        self.presence.borrow_mut().dragging = serde_wasm_bindgen::from_value(entity_ids)
+            .map_err(|_| WbblWebappStoreError::SerializationFailure)?;
+        self.publish_presence();
+        Ok(())
+    }
+
+    pub fn get_collaborators(&self) -> Result<JsValue, WbblWebappStoreError> {
+        let local_client_id = self.graph.client_id();
+        let txn = self.graph.transact();
+        let awareness = self.awareness.borrow();
+        let mut collaborators: Vec<WbblWebappCollaborator> = awareness
+            .clients()
+            .iter()
+            .filter(|(client_id, _)| **client_id != local_client_id)
+            .filter_map(|(client_id, state)| {
+                WbblWebappPresence::decode(state).map(|presence| {
+                    WbblWebappCollaborator::new(
+                        *client_id,
+                        presence,
+                        get_client_selections(
+                            &txn,
+                            *client_id,
+                            &self.nodes,
+                            &self.edges,
+                            &self.node_group_selections,
+                        ),
+                    )
+                })
+            })
+            .collect();
+        collaborators.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        serde_wasm_bindgen::to_value(&collaborators)
+            .map_err(|_| WbblWebappStoreError::SerializationFailure)
+    }
+
     pub fn are_port_types_compatible(type_a: JsValue, type_b: JsValue) -> bool {
         match (
             serde_wasm_bindgen::from_value::<AbstractDataType>(type_a),
@@ -2390,6 +3343,11 @@ impl WbblWebappGraphStore {
             let mut txn = self.graph.transact_mut_with(self.graph.client_id());
             let edge = get_map(edge_id, &txn, &self.edges)?;
             let edge = WbblWebappEdge::decode(uuid.as_u128(), &txn, &edge, self.graph.client_id())?;
+            // The junction sits between the same nodes as the edge it replaces, so it only closes a
+            // cycle if that edge was already part of one
+            if would_create_cycle(edge.source, edge.target, &self.entities.borrow()) {
+                return Err(WbblWebappStoreError::WouldCreateCycle);
+            }
             let new_node =
                 NewWbblWebappNode::new(position_x, position_y, WbblWebappNodeType::Junction)?;
             new_node.encode(&mut txn, &self.nodes)?;
@@ -1,17 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 use wasm_bindgen::prelude::*;
-use web_sys::{
-    js_sys::{self, Date},
-    window,
-};
-use yrs::{
-    sync::{Clock, Timestamp},
-    Map, Subscription, Transact,
-};
+use web_sys::{js_sys, window};
+use yrs::{Map, Subscription, Transact};
 
 use crate::{
-    awareness_sync::AwarenessWebsocketSync,
+    awareness_sync::{AwarenessWebsocketSync, ConnectionState, WasmPackClock},
     graph_transfer_types::{from_type_name, get_type_name, WbblWebappNodeType},
     store_errors::WbblWebappStoreError,
     yrs_utils::{get_atomic_string, get_bool},
@@ -214,6 +208,22 @@ fn get_default_node_keybindings() -> HashMap<String, Option<String>> {
             get_type_name(WbblWebappNodeType::Junction),
             Some("j".to_owned()),
         ),
+        (
+            get_type_name(WbblWebappNodeType::Negate),
+            Some("shift+n".to_owned()),
+        ),
+        (
+            get_type_name(WbblWebappNodeType::Fract),
+            Some("f".to_owned()),
+        ),
+        (
+            get_type_name(WbblWebappNodeType::Length),
+            Some("l".to_owned()),
+        ),
+        (
+            get_type_name(WbblWebappNodeType::Normalize),
+            Some("n".to_owned()),
+        ),
     ])
 }
 
@@ -222,15 +232,6 @@ pub struct KeybindingSnapshot {
     pub keys: HashMap<KeyboardShortcut, Option<String>>,
 }
 
-struct WasmPackClock;
-
-impl Clock for WasmPackClock {
-    fn now(&self) -> yrs::sync::Timestamp {
-        let now = Date::now();
-        now as Timestamp
-    }
-}
-
 #[wasm_bindgen]
 impl WbblWebappPreferencesStore {
     pub fn empty() -> Result<WbblWebappPreferencesStore, WbblWebappStoreError> {
@@ -318,6 +319,22 @@ impl WbblWebappPreferencesStore {
         }
     }
 
+    pub fn get_connection_state(&self) -> ConnectionState {
+        self.websockets_sync.connection_state()
+    }
+
+    pub fn is_hydrated(&self) -> bool {
+        self.websockets_sync.is_hydrated()
+    }
+
+    pub fn subscribe_connection_state(&mut self, subscriber: js_sys::Function) -> u32 {
+        self.websockets_sync.subscribe(subscriber)
+    }
+
+    pub fn unsubscribe_connection_state(&mut self, handle: u32) {
+        self.websockets_sync.unsubscribe(handle)
+    }
+
     pub fn set_base_theme(&mut self, theme: BaseTheme) -> Result<(), WbblWebappStoreError> {
         {
             let awareness = self.awareness.borrow();
@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod awareness_sync_tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use wbbl::{
+        awareness_sync::{AwarenessSync, ConnectionState, ReconnectBackoff, SYNC_DONE_MESSAGE_TAG},
+        sync_transport::{InMemoryTransport, SyncTransport},
+    };
+    use yrs::{
+        sync::{Awareness, Message},
+        updates::encoder::Encode,
+        Doc, Map, MapRef, Transact,
+    };
+
+    type Peer = AwarenessSync<InMemoryTransport>;
+
+    fn awareness() -> Rc<RefCell<Awareness>> {
+        Rc::new(RefCell::new(Awareness::new(Doc::new())))
+    }
+
+    fn nodes(awareness: &Rc<RefCell<Awareness>>) -> MapRef {
+        awareness.borrow().doc().get_or_insert_map("nodes")
+    }
+
+    fn insert(awareness: &Rc<RefCell<Awareness>>, key: &str) {
+        let map = nodes(awareness);
+        let awareness = awareness.borrow();
+        let mut txn = awareness.doc().transact_mut();
+        map.insert(&mut txn, key, true);
+    }
+
+    fn contains(awareness: &Rc<RefCell<Awareness>>, key: &str) -> bool {
+        let map = nodes(awareness);
+        let awareness = awareness.borrow();
+        let txn = awareness.doc().transact();
+        map.contains_key(&txn, key)
+    }
+
+    fn connect(a: &Rc<RefCell<Awareness>>, b: &Rc<RefCell<Awareness>>) -> (Peer, Peer) {
+        let (a_transport, b_transport) = InMemoryTransport::pair();
+        let a = AwarenessSync::new(a.clone(), a_transport);
+        let b = AwarenessSync::new(b.clone(), b_transport);
+        a.handle_open();
+        b.handle_open();
+        (a, b)
+    }
+
+    // Delivers queued messages in both directions until neither peer has anything left to send
+    fn pump(a: &Peer, b: &Peer) {
+        loop {
+            let mut delivered = false;
+            for peer in [a, b] {
+                while let Some(message) = peer.transport().receive() {
+                    peer.handle_message(&message);
+                    delivered = true;
+                }
+            }
+            if !delivered {
+                break;
+            }
+        }
+    }
+
+    fn finish_initial_sync(peer: &Peer) {
+        peer.handle_message(&Message::Custom(SYNC_DONE_MESSAGE_TAG, vec![]).encode_v1());
+    }
+
+    #[test]
+    fn test_initial_sync_exchanges_documents() {
+        let (a_awareness, b_awareness) = (awareness(), awareness());
+        insert(&a_awareness, "a");
+        insert(&b_awareness, "b");
+
+        let (a, b) = connect(&a_awareness, &b_awareness);
+        assert_eq!(a.connection_state(), ConnectionState::Syncing);
+        pump(&a, &b);
+
+        for awareness in [&a_awareness, &b_awareness] {
+            assert!(contains(awareness, "a"));
+            assert!(contains(awareness, "b"));
+        }
+        assert!(!a.is_hydrated());
+    }
+
+    #[test]
+    fn test_local_updates_wait_for_initial_sync() {
+        let (a_awareness, b_awareness) = (awareness(), awareness());
+        let (a, b) = connect(&a_awareness, &b_awareness);
+        pump(&a, &b);
+
+        insert(&a_awareness, "before_done");
+        pump(&a, &b);
+        assert!(!contains(&b_awareness, "before_done"));
+
+        let states = Rc::new(RefCell::new(Vec::new()));
+        a.on_connection_state_change({
+            let states = states.clone();
+            move |state| states.borrow_mut().push(state)
+        });
+        finish_initial_sync(&a);
+        assert!(a.is_hydrated());
+        assert_eq!(*states.borrow(), vec![ConnectionState::Synced]);
+        pump(&a, &b);
+        assert!(contains(&b_awareness, "before_done"));
+
+        // Once synced, updates are sent as they happen
+        insert(&a_awareness, "after_done");
+        pump(&a, &b);
+        assert!(contains(&b_awareness, "after_done"));
+    }
+
+    #[test]
+    fn test_awareness_is_shared_between_peers() {
+        let (a_awareness, b_awareness) = (awareness(), awareness());
+        a_awareness
+            .borrow_mut()
+            .set_local_state("{\"cursor\":null}");
+        let (a, b) = connect(&a_awareness, &b_awareness);
+        pump(&a, &b);
+
+        let a_client_id = a_awareness.borrow().client_id();
+        assert_eq!(
+            b_awareness
+                .borrow()
+                .clients()
+                .get(&a_client_id)
+                .map(|state| state.as_str()),
+            Some("{\"cursor\":null}")
+        );
+    }
+
+    #[test]
+    fn test_malformed_payload_closes_connection() {
+        let (a_awareness, b_awareness) = (awareness(), awareness());
+        let (a, b) = connect(&a_awareness, &b_awareness);
+        pump(&a, &b);
+
+        // A sync message with an unknown step
+        b.handle_message(&[0, 7]);
+        assert!(!b.transport().is_open());
+        assert_eq!(
+            a.transport().close_reason(),
+            Some((1001, "MALFORMED PAYLOAD".to_owned()))
+        );
+
+        // Nothing more is sent over a closed connection
+        finish_initial_sync(&a);
+        insert(&a_awareness, "offline");
+        assert!(b.transport().receive().is_none());
+    }
+
+    #[test]
+    fn test_reconnect_backoff_doubles_up_to_a_limit() {
+        let mut backoff = ReconnectBackoff::default();
+        assert_eq!(backoff.next_delay_millis(0.0), 250);
+        assert_eq!(backoff.next_delay_millis(0.0), 500);
+        assert_eq!(backoff.next_delay_millis(0.5), 1500);
+        for _ in 0..20 {
+            assert!(backoff.next_delay_millis(1.0) <= 30_000);
+        }
+        assert_eq!(backoff.next_delay_millis(1.0), 30_000);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay_millis(1.0), 500);
+    }
+}
@@ -0,0 +1,912 @@
+#[cfg(test)]
+mod compiler_tests {
+    use std::collections::{HashMap, HashSet};
+
+    use wgpu::naga::{BinaryOperator, Expression};
+
+    use wbbl::{
+        compiler::{compile_to_naga_ir, validate_stage},
+        constraint_solver::ConstraintSolverError,
+        data_types::{AbstractDataType, CompositeSize, ConcreteDataType, Dimensionality},
+        graph_functions::{
+            concretise_types_in_graph, decompose_branches, decompose_subgraphs,
+            edges_contain_cycle, get_upstream_subgraph, label_branches, label_computation_domains,
+            label_subgraphs, narrow_abstract_types, topologically_order_nodes, would_create_cycle,
+            IncrementalTypeNarrower,
+        },
+        graph_types::{
+            slab_inputs, BinaryOperation, BuiltIn, ConstantValue, Edge, Graph, InputPortId,
+            NaryOperation, Node, NodeType, OutputPortId, PortId, UnaryOperation, VectorComponent,
+        },
+        intermediate_compiler_types::{IntermediateOutput, PreviewVisualisation, Shader},
+    };
+
+    fn add_node(graph: &mut Graph, id: u128, node_type: NodeType) {
+        let node = Node {
+            id,
+            input_port_count: node_type.input_port_count(&[], &[]),
+            output_port_count: node_type.output_port_count(&[], &[]),
+            node_type,
+        };
+        for port in node.input_ports(&[]) {
+            graph.input_ports.insert(port.id.clone(), port);
+        }
+        for port in node.output_ports(&[]) {
+            graph.output_ports.insert(port.id.clone(), port);
+        }
+        graph.nodes.insert(id, node);
+    }
+
+    fn add_edge(graph: &mut Graph, id: u128, source: (u128, u8), target: (u128, u8)) {
+        let edge = Edge {
+            id,
+            output_port: OutputPortId {
+                node_id: source.0,
+                port_index: source.1,
+            },
+            input_port: InputPortId {
+                node_id: target.0,
+                port_index: target.1,
+            },
+        };
+        graph
+            .output_ports
+            .get_mut(&edge.output_port)
+            .unwrap()
+            .outgoing_edges
+            .push(id);
+        graph
+            .input_ports
+            .get_mut(&edge.input_port)
+            .unwrap()
+            .incoming_edge = Some(id);
+        graph.edges.insert(id, edge);
+    }
+
+    fn remove_edge(graph: &mut Graph, id: u128) {
+        let edge = graph.edges.remove(&id).unwrap();
+        graph
+            .output_ports
+            .get_mut(&edge.output_port)
+            .unwrap()
+            .outgoing_edges
+            .retain(|e| *e != id);
+        graph
+            .input_ports
+            .get_mut(&edge.input_port)
+            .unwrap()
+            .incoming_edge = None;
+    }
+
+    fn compile_graph(graph: Graph) -> IntermediateOutput {
+        let node_ordering = topologically_order_nodes(&graph);
+        let concrete_types = concretise_types_in_graph(&graph, &node_ordering).unwrap();
+        let computation_domains = label_computation_domains(&graph, &node_ordering);
+        let subgraphs = label_subgraphs(&graph);
+        let branches = label_branches(&graph);
+        let multi_graph = decompose_subgraphs(graph, &subgraphs, &node_ordering);
+        let branched_multi_graph = decompose_branches(multi_graph, &branches);
+
+        compile_to_naga_ir(&branched_multi_graph, &computation_domains, &concrete_types).unwrap()
+    }
+
+    fn validate_fragment_shader(graph: Graph) -> IntermediateOutput {
+        let output = compile_graph(graph);
+        let fragment = output
+            .0
+            .iter()
+            .find_map(|stage| match &stage.shader {
+                Shader::VertexFragment(shader) => Some(&shader.fragment),
+                _ => None,
+            })
+            .unwrap();
+
+        let m_valid = wgpu::naga::valid::Validator::new(
+            wgpu::naga::valid::ValidationFlags::all(),
+            Default::default(),
+        )
+        .validate(fragment);
+
+        println!(
+            "{}",
+            wgpu::naga::back::wgsl::write_string(
+                fragment,
+                &m_valid.unwrap(),
+                wgpu::naga::back::wgsl::WriterFlags::empty(),
+            )
+            .unwrap()
+        );
+        output
+    }
+
+    fn is_time_varying(output: &IntermediateOutput) -> bool {
+        output.0.iter().any(|stage| stage.is_time_varying())
+    }
+
+    fn empty_graph(id: u128) -> Graph {
+        Graph {
+            id,
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
+            dirty: false,
+            input_ports: HashMap::new(),
+            output_ports: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_binary_operation_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_node(
+            &mut graph,
+            4,
+            NodeType::BuiltIn(BuiltIn::TextureCoordinate2),
+        );
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 1));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_scalar_broadcast_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Subtract),
+        );
+        add_node(
+            &mut graph,
+            3,
+            NodeType::BinaryOperation(BinaryOperation::Multiply),
+        );
+        add_node(&mut graph, 4, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_node(&mut graph, 5, NodeType::Constant(ConstantValue::Float(0.5)));
+        add_edge(&mut graph, 6, (4, 0), (3, 0));
+        add_edge(&mut graph, 7, (5, 0), (3, 1));
+        add_edge(&mut graph, 8, (5, 0), (2, 0));
+        add_edge(&mut graph, 9, (3, 0), (2, 1));
+        add_edge(&mut graph, 10, (2, 0), (1, 0));
+
+        let output_port = PortId::Output(OutputPortId {
+            node_id: 2,
+            port_index: 0,
+        });
+        let abstract_types = narrow_abstract_types(&graph).unwrap();
+        assert_eq!(
+            abstract_types.get(&output_port),
+            Some(&AbstractDataType::ConcreteType(ConcreteDataType::Float(
+                CompositeSize::S3
+            )))
+        );
+        let node_ordering = topologically_order_nodes(&graph);
+        let concrete_types = concretise_types_in_graph(&graph, &node_ordering).unwrap();
+        assert_eq!(
+            concrete_types.get(&output_port),
+            Some(&ConcreteDataType::Float(CompositeSize::S3))
+        );
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_mismatched_vector_sizes() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_node(&mut graph, 4, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 1));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        let node_ordering = topologically_order_nodes(&graph);
+        assert!(concretise_types_in_graph(&graph, &node_ordering).is_err());
+        assert!(narrow_abstract_types(&graph).is_err());
+    }
+
+    #[test]
+    fn test_incremental_type_narrowing() {
+        let mut narrower = IncrementalTypeNarrower::new();
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_node(
+            &mut graph,
+            4,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 1));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        assert_eq!(
+            narrower.narrow(&graph).unwrap(),
+            narrow_abstract_types(&graph).unwrap()
+        );
+
+        // Removing an edge loosens the types it was constraining, including those of nodes past
+        // the ends of the edge
+        narrower.invalidate_removed_edge(graph.edges.get(&5).unwrap());
+        remove_edge(&mut graph, 5);
+        assert_eq!(
+            narrower.narrow(&graph).unwrap(),
+            narrow_abstract_types(&graph).unwrap()
+        );
+
+        add_node(&mut graph, 8, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        narrower.invalidate_node(&graph, 8);
+        add_edge(&mut graph, 9, (8, 0), (4, 0));
+        narrower.invalidate_edge(graph.edges.get(&9).unwrap());
+        let types = narrower.narrow(&graph).unwrap();
+        assert_eq!(types, narrow_abstract_types(&graph).unwrap());
+        assert_eq!(
+            types.get(&PortId::Output(OutputPortId {
+                node_id: 2,
+                port_index: 0
+            })),
+            Some(&AbstractDataType::ConcreteType(ConcreteDataType::Float(
+                CompositeSize::S2
+            )))
+        );
+
+        add_edge(&mut graph, 10, (3, 0), (4, 1));
+        narrower.invalidate_edge(graph.edges.get(&10).unwrap());
+        let Err(ConstraintSolverError::ConflictFound(conflict)) = narrower.narrow(&graph) else {
+            panic!("Expected a type conflict");
+        };
+        assert!(conflict.edges.contains(&10));
+
+        narrower.invalidate_removed_edge(graph.edges.get(&10).unwrap());
+        remove_edge(&mut graph, 10);
+        assert_eq!(
+            narrower.narrow(&graph).unwrap(),
+            narrow_abstract_types(&graph).unwrap()
+        );
+
+        // Nodes are invalidated before they change, so the nodes they lose edges to are too
+        narrower.invalidate_node(&graph, 4);
+        graph
+            .update_node_type(4, NodeType::BuiltIn(BuiltIn::WorldPosition))
+            .unwrap();
+        assert_eq!(
+            narrower.narrow(&graph).unwrap(),
+            narrow_abstract_types(&graph).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_incremental_type_narrowing_releases_chains() {
+        let mut narrower = IncrementalTypeNarrower::new();
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        for id in 2..=4 {
+            add_node(&mut graph, id, NodeType::Junction);
+        }
+        add_node(&mut graph, 5, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_edge(&mut graph, 6, (5, 0), (4, 0));
+        add_edge(&mut graph, 7, (4, 0), (3, 0));
+        add_edge(&mut graph, 8, (3, 0), (2, 0));
+        add_edge(&mut graph, 9, (2, 0), (1, 0));
+        let end_of_chain = PortId::Output(OutputPortId {
+            node_id: 2,
+            port_index: 0,
+        });
+        let float3 = AbstractDataType::ConcreteType(ConcreteDataType::Float(CompositeSize::S3));
+        assert_eq!(
+            narrower.narrow(&graph).unwrap().get(&end_of_chain),
+            Some(&float3)
+        );
+
+        // The junctions past the removed edge's neighbour were only Float3 because of the edge
+        narrower.invalidate_removed_edge(graph.edges.get(&6).unwrap());
+        remove_edge(&mut graph, 6);
+        let types = narrower.narrow(&graph).unwrap();
+        assert_eq!(types, narrow_abstract_types(&graph).unwrap());
+        assert_ne!(types.get(&end_of_chain), Some(&float3));
+
+        // Changing the first node of the chain releases the rest of it too
+        add_edge(&mut graph, 10, (5, 0), (4, 0));
+        narrower.invalidate_edge(graph.edges.get(&10).unwrap());
+        assert_eq!(
+            narrower.narrow(&graph).unwrap().get(&end_of_chain),
+            Some(&float3)
+        );
+        narrower.invalidate_node(&graph, 5);
+        graph
+            .update_node_type(5, NodeType::Constant(ConstantValue::Float(1.0)))
+            .unwrap();
+        let types = narrower.narrow(&graph).unwrap();
+        assert_eq!(types, narrow_abstract_types(&graph).unwrap());
+        assert_ne!(types.get(&end_of_chain), Some(&float3));
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_node(
+            &mut graph,
+            3,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (2, 0), (1, 0));
+        assert!(would_create_cycle(&graph, 2, 3));
+        assert!(would_create_cycle(&graph, 2, 2));
+        assert!(!would_create_cycle(&graph, 3, 2));
+        assert!(!edges_contain_cycle(&[(3, 2), (2, 1), (3, 1)]));
+        assert!(edges_contain_cycle(&[(3, 2), (2, 1), (1, 3)]));
+        assert!(edges_contain_cycle(&[(2, 2)]));
+    }
+
+    #[test]
+    fn test_upstream_subgraph() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_node(
+            &mut graph,
+            4,
+            NodeType::BuiltIn(BuiltIn::TextureCoordinate2),
+        );
+        add_node(&mut graph, 10, NodeType::Preview);
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 1));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        add_edge(&mut graph, 11, (3, 0), (10, 0));
+
+        let subgraph = get_upstream_subgraph(&graph, 10).unwrap();
+        assert_eq!(subgraph.id, 10);
+        assert_eq!(
+            subgraph.nodes.keys().copied().collect::<HashSet<u128>>(),
+            HashSet::from([3, 10])
+        );
+        assert_eq!(
+            subgraph
+                .output_ports
+                .get(&OutputPortId {
+                    node_id: 3,
+                    port_index: 0
+                })
+                .unwrap()
+                .outgoing_edges,
+            vec![11]
+        );
+        validate_fragment_shader(subgraph.clone());
+
+        // Changes outside of the subgraph leave it untouched
+        remove_edge(&mut graph, 6);
+        assert_eq!(get_upstream_subgraph(&graph, 10), Some(subgraph));
+        assert!(get_upstream_subgraph(&graph, 99).is_none());
+    }
+
+    #[test]
+    fn test_validation_errors_map_to_nodes() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (3, 0), (2, 1));
+        add_edge(&mut graph, 6, (2, 0), (1, 0));
+        let mut output = compile_graph(graph);
+        let stage = output.0.last_mut().unwrap();
+        assert!(validate_stage(stage).is_ok());
+
+        let add_port = PortId::Output(OutputPortId {
+            node_id: 2,
+            port_index: 0,
+        });
+        let Shader::VertexFragment(shader) = &mut stage.shader else {
+            panic!("Expected a vertex fragment stage");
+        };
+        let function = &mut shader.fragment.entry_points[0].function;
+        let (add, _) = function
+            .expressions
+            .iter()
+            .find(|(handle, expression)| {
+                matches!(expression, Expression::Binary { .. })
+                    && shader
+                        .fragment_span_origins
+                        .get(function.expressions.get_span(*handle))
+                        == Some(&add_port)
+            })
+            .unwrap();
+        // Floats can't be combined with a logical operator
+        if let Expression::Binary { op, .. } = function.expressions.get_mut(add) {
+            *op = BinaryOperator::LogicalAnd;
+        }
+
+        let diagnostics = validate_stage(stage).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].node_id, 2);
+        assert_eq!(diagnostics[0].port, Some(add_port));
+        // The store's snapshot gets ports in the same qualified form as its port types
+        assert_eq!(
+            serde_json::to_value(&diagnostics[0]).unwrap()["port"],
+            "00000000-0000-0000-0000-000000000002#s#0"
+        );
+    }
+
+    #[test]
+    fn test_type_conflict_explanation() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Add),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_node(&mut graph, 4, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 1));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        // A well typed part of the graph that isn't connected to the conflict
+        add_node(&mut graph, 8, NodeType::Preview);
+        add_node(&mut graph, 9, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_edge(&mut graph, 10, (9, 0), (8, 0));
+        let node_ordering = topologically_order_nodes(&graph);
+        for result in [
+            concretise_types_in_graph(&graph, &node_ordering).map(|_| ()),
+            narrow_abstract_types(&graph).map(|_| ()),
+        ] {
+            let Err(ConstraintSolverError::ConflictFound(conflict)) = result else {
+                panic!("Expected a type conflict");
+            };
+            assert_eq!(conflict.edges, HashSet::from([5, 6]));
+            assert_eq!(conflict.constraints.len(), 3);
+            assert!(conflict.ports.contains(&PortId::Output(OutputPortId {
+                node_id: 3,
+                port_index: 0
+            })));
+            assert!(conflict.ports.contains(&PortId::Output(OutputPortId {
+                node_id: 4,
+                port_index: 0
+            })));
+            assert!(!conflict.ports.contains(&PortId::Input(InputPortId {
+                node_id: 1,
+                port_index: 0
+            })));
+        }
+    }
+
+    #[test]
+    fn test_comparison_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Less),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_node(&mut graph, 4, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 1));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_unary_operation_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::UnaryOperation(UnaryOperation::Length),
+        );
+        add_node(
+            &mut graph,
+            3,
+            NodeType::UnaryOperation(UnaryOperation::Normalize),
+        );
+        add_node(&mut graph, 4, NodeType::UnaryOperation(UnaryOperation::Sin));
+        add_node(&mut graph, 5, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_edge(&mut graph, 6, (5, 0), (4, 0));
+        add_edge(&mut graph, 7, (4, 0), (3, 0));
+        add_edge(&mut graph, 8, (3, 0), (2, 0));
+        add_edge(&mut graph, 9, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_nary_operation_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::NaryOperation(NaryOperation::Select),
+        );
+        add_node(&mut graph, 3, NodeType::NaryOperation(NaryOperation::Mix));
+        add_node(
+            &mut graph,
+            4,
+            NodeType::NaryOperation(NaryOperation::Smoothstep),
+        );
+        add_node(&mut graph, 5, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_node(&mut graph, 6, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_node(
+            &mut graph,
+            7,
+            NodeType::Constant(ConstantValue::Float(0.25)),
+        );
+        add_node(&mut graph, 8, NodeType::Constant(ConstantValue::Bool(true)));
+        add_edge(&mut graph, 9, (5, 0), (3, 0));
+        add_edge(&mut graph, 10, (6, 0), (3, 1));
+        add_edge(&mut graph, 11, (7, 0), (3, 2));
+        add_edge(&mut graph, 12, (7, 0), (4, 0));
+        add_edge(&mut graph, 13, (5, 0), (4, 2));
+        add_edge(&mut graph, 14, (3, 0), (2, 0));
+        add_edge(&mut graph, 15, (4, 0), (2, 1));
+        add_edge(&mut graph, 16, (8, 0), (2, 2));
+        add_edge(&mut graph, 17, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::NaryOperation(NaryOperation::Step));
+        add_node(&mut graph, 3, NodeType::NaryOperation(NaryOperation::Clamp));
+        add_node(&mut graph, 4, NodeType::NaryOperation(NaryOperation::Fma));
+        add_node(&mut graph, 5, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_node(&mut graph, 6, NodeType::Constant(ConstantValue::Float(0.5)));
+        add_edge(&mut graph, 7, (5, 0), (4, 0));
+        add_edge(&mut graph, 8, (5, 0), (4, 1));
+        add_edge(&mut graph, 9, (5, 0), (4, 2));
+        add_edge(&mut graph, 10, (4, 0), (3, 0));
+        add_edge(&mut graph, 11, (6, 0), (3, 2));
+        add_edge(&mut graph, 12, (6, 0), (2, 0));
+        add_edge(&mut graph, 13, (3, 0), (2, 1));
+        add_edge(&mut graph, 14, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_mismatched_interpolant() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::NaryOperation(NaryOperation::Mix));
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_node(&mut graph, 4, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 2));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        let node_ordering = topologically_order_nodes(&graph);
+        assert!(concretise_types_in_graph(&graph, &node_ordering).is_err());
+    }
+
+    #[test]
+    fn test_constant_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::BinaryOperation(BinaryOperation::Multiply),
+        );
+        add_node(
+            &mut graph,
+            3,
+            NodeType::Constant(ConstantValue::Vector3([0.5, 0.25, 1.0])),
+        );
+        add_node(&mut graph, 4, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_edge(&mut graph, 5, (3, 0), (2, 0));
+        add_edge(&mut graph, 6, (4, 0), (2, 1));
+        add_edge(&mut graph, 7, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::Constant(ConstantValue::Int(-3)));
+        add_edge(&mut graph, 3, (2, 0), (1, 0));
+        let output = validate_fragment_shader(graph);
+        assert!(!is_time_varying(&output));
+    }
+
+    #[test]
+    fn test_time_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::Append);
+        add_node(&mut graph, 3, NodeType::Time);
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (3, 2), (2, 1));
+        add_edge(&mut graph, 6, (2, 0), (1, 0));
+        let output = validate_fragment_shader(graph);
+        assert!(is_time_varying(&output));
+    }
+
+    #[test]
+    fn test_vector_component_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::Append);
+        add_node(
+            &mut graph,
+            3,
+            NodeType::Swizzle(vec![
+                VectorComponent::Z,
+                VectorComponent::X,
+                VectorComponent::Y,
+            ]),
+        );
+        add_node(&mut graph, 4, NodeType::Split);
+        add_node(&mut graph, 5, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_node(&mut graph, 6, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_edge(&mut graph, 7, (5, 0), (3, 0));
+        add_edge(&mut graph, 8, (6, 0), (4, 0));
+        add_edge(&mut graph, 9, (3, 0), (2, 0));
+        add_edge(&mut graph, 10, (4, 1), (2, 1));
+        add_edge(&mut graph, 11, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::Swizzle(vec![VectorComponent::X; 3]),
+        );
+        add_node(&mut graph, 3, NodeType::Constant(ConstantValue::Float(0.5)));
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_swizzle_pattern_edit_keeps_edges() {
+        let swizzle_output = PortId::Output(OutputPortId {
+            node_id: 2,
+            port_index: 0,
+        });
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(
+            &mut graph,
+            2,
+            NodeType::Swizzle(vec![
+                VectorComponent::Z,
+                VectorComponent::X,
+                VectorComponent::Y,
+            ]),
+        );
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (2, 0), (1, 0));
+        assert_eq!(
+            narrow_abstract_types(&graph).unwrap().get(&swizzle_output),
+            Some(&AbstractDataType::ConcreteType(ConcreteDataType::Float(
+                CompositeSize::S3
+            )))
+        );
+
+        graph
+            .update_node_type(
+                2,
+                NodeType::Swizzle(vec![VectorComponent::Y, VectorComponent::X]),
+            )
+            .unwrap();
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!(
+            graph
+                .output_ports
+                .get(&OutputPortId {
+                    node_id: 2,
+                    port_index: 0
+                })
+                .unwrap()
+                .outgoing_edges,
+            vec![5]
+        );
+        assert_eq!(
+            graph
+                .input_ports
+                .get(&InputPortId {
+                    node_id: 1,
+                    port_index: 0
+                })
+                .unwrap()
+                .incoming_edge,
+            Some(5)
+        );
+        assert_eq!(
+            narrow_abstract_types(&graph).unwrap().get(&swizzle_output),
+            Some(&AbstractDataType::ConcreteType(ConcreteDataType::Float(
+                CompositeSize::S2
+            )))
+        );
+        validate_fragment_shader(graph.clone());
+
+        // A change of output type removes the edge from both of the ports it connected
+        graph.update_node_type(2, NodeType::Split).unwrap();
+        assert!(!graph.edges.contains_key(&5));
+        assert_eq!(
+            graph
+                .input_ports
+                .get(&InputPortId {
+                    node_id: 1,
+                    port_index: 0
+                })
+                .unwrap()
+                .incoming_edge,
+            None
+        );
+        assert!(graph.edges.contains_key(&4));
+    }
+
+    #[test]
+    fn test_not_rejects_floats() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::UnaryOperation(UnaryOperation::Not));
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (2, 0), (1, 0));
+        let node_ordering = topologically_order_nodes(&graph);
+        assert!(concretise_types_in_graph(&graph, &node_ordering).is_err());
+    }
+
+    #[test]
+    fn test_output_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Output);
+        add_node(&mut graph, 2, NodeType::Slab);
+        add_edge(&mut graph, 3, (2, 0), (1, 0));
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_preview_visualisation_codegen() {
+        for (value, visualisation) in [
+            (ConstantValue::Float(0.5), PreviewVisualisation::Greyscale),
+            (
+                ConstantValue::Vector2([-1.0, 1.0]),
+                PreviewVisualisation::RemappedColour,
+            ),
+            (
+                ConstantValue::Vector4([0.0, 1.0, 0.0, 0.5]),
+                PreviewVisualisation::RemappedColour,
+            ),
+            (ConstantValue::Bool(false), PreviewVisualisation::Checker),
+        ] {
+            let mut graph = empty_graph(1);
+            add_node(&mut graph, 1, NodeType::Preview);
+            add_node(&mut graph, 2, NodeType::Constant(value));
+            add_edge(&mut graph, 3, (2, 0), (1, 0));
+            let node_ordering = topologically_order_nodes(&graph);
+            let concrete_types = concretise_types_in_graph(&graph, &node_ordering).unwrap();
+            let t = concrete_types
+                .get(&PortId::Input(InputPortId {
+                    node_id: 1,
+                    port_index: 0,
+                }))
+                .copied()
+                .unwrap();
+            assert_eq!(PreviewVisualisation::for_type(t), visualisation);
+            validate_fragment_shader(graph);
+        }
+        assert_eq!(
+            PreviewVisualisation::for_type(ConcreteDataType::SlabMaterial),
+            PreviewVisualisation::Material
+        );
+
+        // No node produces textures or fields yet, so the junction's types are overridden
+        for t in [
+            ConcreteDataType::Texture(Dimensionality::D2, CompositeSize::S4),
+            ConcreteDataType::ProceduralField(Dimensionality::D3, CompositeSize::S1),
+        ] {
+            assert_eq!(
+                PreviewVisualisation::for_type(t),
+                PreviewVisualisation::Flat2D
+            );
+            let mut graph = empty_graph(1);
+            add_node(&mut graph, 1, NodeType::Preview);
+            add_node(&mut graph, 2, NodeType::Junction);
+            add_edge(&mut graph, 3, (2, 0), (1, 0));
+            let node_ordering = topologically_order_nodes(&graph);
+            let mut concrete_types = concretise_types_in_graph(&graph, &node_ordering).unwrap();
+            for port_type in concrete_types.values_mut() {
+                *port_type = t;
+            }
+            let computation_domains = label_computation_domains(&graph, &node_ordering);
+            let subgraphs = label_subgraphs(&graph);
+            let branches = label_branches(&graph);
+            let multi_graph = decompose_subgraphs(graph, &subgraphs, &node_ordering);
+            let branched_multi_graph = decompose_branches(multi_graph, &branches);
+            let output =
+                compile_to_naga_ir(&branched_multi_graph, &computation_domains, &concrete_types)
+                    .unwrap();
+            let fragment = output
+                .0
+                .iter()
+                .find_map(|stage| match &stage.shader {
+                    Shader::VertexFragment(shader) => Some(&shader.fragment),
+                    _ => None,
+                })
+                .unwrap();
+            assert!(wgpu::naga::valid::Validator::new(
+                wgpu::naga::valid::ValidationFlags::all(),
+                Default::default(),
+            )
+            .validate(fragment)
+            .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_split_outputs_follow_input_width() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::Split);
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::TextureCoordinate));
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (2, 2), (1, 0));
+        let node_ordering = topologically_order_nodes(&graph);
+        let Err(ConstraintSolverError::ConflictFound(conflict)) =
+            concretise_types_in_graph(&graph, &node_ordering)
+        else {
+            panic!("Expected the z output of a vec2 split to be rejected");
+        };
+        assert!(conflict.edges.contains(&5));
+
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Preview);
+        add_node(&mut graph, 2, NodeType::Split);
+        add_node(&mut graph, 3, NodeType::BuiltIn(BuiltIn::WorldPosition));
+        add_edge(&mut graph, 4, (3, 0), (2, 0));
+        add_edge(&mut graph, 5, (2, 2), (1, 0));
+        validate_fragment_shader(graph);
+    }
+
+    #[test]
+    fn test_slab_inputs_codegen() {
+        let mut graph = empty_graph(1);
+        add_node(&mut graph, 1, NodeType::Output);
+        add_node(&mut graph, 2, NodeType::Slab);
+        add_node(
+            &mut graph,
+            3,
+            NodeType::Constant(ConstantValue::Vector3([0.9, 0.1, 0.1])),
+        );
+        add_node(&mut graph, 4, NodeType::Constant(ConstantValue::Float(0.2)));
+        add_node(&mut graph, 5, NodeType::BuiltIn(BuiltIn::WorldNormal));
+        add_edge(&mut graph, 6, (2, 0), (1, 0));
+        add_edge(&mut graph, 7, (3, 0), (2, slab_inputs::BASE_COLOUR_INDEX));
+        add_edge(&mut graph, 8, (4, 0), (2, slab_inputs::ROUGHNESS_INDEX));
+        add_edge(&mut graph, 9, (4, 0), (2, slab_inputs::CLEARCOAT_INDEX));
+        add_edge(&mut graph, 10, (5, 0), (2, slab_inputs::NORMAL_INDEX));
+        validate_fragment_shader(graph);
+    }
+}
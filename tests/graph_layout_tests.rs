@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod graph_layout_tests {
+    use std::collections::{HashMap, HashSet};
+
+    use glam::Vec2;
+    use wbbl::{
+        graph_layout::{layout_nodes, LayoutDirection, LayoutOptions},
+        graph_transfer_types::{
+            WbblWebappEdge, WbblWebappGraphSnapshot, WbblWebappNode, WbblWebappNodeType,
+            WbblePosition,
+        },
+    };
+
+    fn node(id: u128, node_type: WbblWebappNodeType, x: f64, y: f64) -> WbblWebappNode {
+        WbblWebappNode {
+            id,
+            position: WbblePosition { x, y },
+            node_type,
+            data: HashMap::new(),
+            width: 0.0,
+            height: 0.0,
+            dragging: false,
+            resizing: false,
+            selected: false,
+            selections: HashSet::new(),
+            selectable: true,
+            connectable: true,
+            deletable: true,
+            group_id: None,
+            in_edges: HashSet::new(),
+            out_edges: HashSet::new(),
+        }
+    }
+
+    fn edge(source: u128, target: u128, target_handle: i64) -> WbblWebappEdge {
+        WbblWebappEdge::new(&source, &target, 0, target_handle, None)
+    }
+
+    fn all_nodes(snapshot: &WbblWebappGraphSnapshot) -> HashSet<u128> {
+        snapshot.nodes.iter().map(|n| n.id).collect()
+    }
+
+    // 1 --\
+    //      3 -> 4 -> 5
+    // 2 --/         /
+    // 6 -----------/
+    fn diamond() -> WbblWebappGraphSnapshot {
+        WbblWebappGraphSnapshot {
+            id: 100,
+            nodes: vec![
+                node(1, WbblWebappNodeType::Time, 500.0, 0.0),
+                node(2, WbblWebappNodeType::Time, -200.0, 700.0),
+                node(3, WbblWebappNodeType::Add, 0.0, 0.0),
+                node(4, WbblWebappNodeType::Sin, 100.0, 300.0),
+                node(5, WbblWebappNodeType::Add, 300.0, 100.0),
+                node(6, WbblWebappNodeType::Time, 0.0, -100.0),
+            ],
+            edges: vec![
+                edge(1, 3, 0),
+                edge(2, 3, 1),
+                edge(3, 4, 0),
+                edge(4, 5, 0),
+                edge(6, 5, 1),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_layered_layout_left_to_right() {
+        let snapshot = diamond();
+        let options = LayoutOptions::default();
+        let positions = layout_nodes(&snapshot, &all_nodes(&snapshot), &options).unwrap();
+        assert_eq!(positions.len(), 6);
+
+        // Every edge points rightwards, into a later rank
+        for e in snapshot.edges.iter() {
+            assert!(positions[&e.target].x - positions[&e.source].x > options.rank_spacing);
+        }
+        // Both inputs of 3 share a rank, one above the other in port order
+        assert_eq!(positions[&1].x, positions[&2].x);
+        assert!(positions[&1].y < positions[&2].y);
+        // The layout starts where the nodes used to be
+        let min = positions.values().fold(Vec2::INFINITY, |a, b| a.min(*b));
+        assert_eq!(min, Vec2::new(-200.0, -100.0));
+    }
+
+    #[test]
+    fn test_layered_layout_top_to_bottom() {
+        let snapshot = diamond();
+        let options = LayoutOptions {
+            direction: LayoutDirection::TopToBottom,
+            ..Default::default()
+        };
+        let positions = layout_nodes(&snapshot, &all_nodes(&snapshot), &options).unwrap();
+        for e in snapshot.edges.iter() {
+            assert!(positions[&e.target].y - positions[&e.source].y > options.rank_spacing);
+        }
+        assert_eq!(positions[&1].y, positions[&2].y);
+    }
+
+    #[test]
+    fn test_layout_reduces_crossings() {
+        // 1 feeds 4 and 2 feeds 3, but 1 starts above 2 while 3 starts above 4.
+        // 3 and 4 feed the first and second inputs of 5, so only swapping 1 and 2 avoids crossings
+        let snapshot = WbblWebappGraphSnapshot {
+            id: 100,
+            nodes: vec![
+                node(1, WbblWebappNodeType::Time, 0.0, 0.0),
+                node(2, WbblWebappNodeType::Time, 0.0, 200.0),
+                node(3, WbblWebappNodeType::Sin, 300.0, 0.0),
+                node(4, WbblWebappNodeType::Sin, 300.0, 200.0),
+                node(5, WbblWebappNodeType::Add, 600.0, 100.0),
+            ],
+            edges: vec![edge(1, 4, 0), edge(2, 3, 0), edge(3, 5, 0), edge(4, 5, 1)],
+        };
+        let positions =
+            layout_nodes(&snapshot, &all_nodes(&snapshot), &LayoutOptions::default()).unwrap();
+        assert_eq!(
+            positions[&1].y < positions[&2].y,
+            positions[&4].y < positions[&3].y
+        );
+        assert!(positions[&3].y < positions[&4].y);
+    }
+
+    #[test]
+    fn test_layout_of_selection_and_groups() {
+        let mut snapshot = diamond();
+        for n in snapshot.nodes.iter_mut() {
+            if n.id == 1 || n.id == 6 {
+                n.group_id = Some(200);
+            }
+        }
+        let selection: HashSet<u128> = HashSet::from([1, 2, 3, 6]);
+        let positions = layout_nodes(&snapshot, &selection, &LayoutOptions::default()).unwrap();
+        assert_eq!(
+            positions.keys().copied().collect::<HashSet<u128>>(),
+            selection
+        );
+        // 6 only connects to a node outside the selection, so it joins the sources of 3,
+        // and stays next to 1 as they share a group
+        assert_eq!(positions[&1].x, positions[&6].x);
+        assert_eq!(positions[&2].x, positions[&6].x);
+        let mut rank: Vec<u128> = vec![1, 2, 6];
+        rank.sort_by(|a, b| positions[a].y.total_cmp(&positions[b].y));
+        assert_ne!(rank[1], 2);
+    }
+}
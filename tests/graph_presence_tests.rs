@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod graph_presence_tests {
+    use wbbl::{
+        graph_presence::{
+            get_collaborator_colour, WbblWebappCollaborator, WbblWebappPresence,
+            WbblWebappViewport,
+        },
+        graph_transfer_types::{WbblWebappGraphEntityIds, WbblePosition},
+    };
+
+    #[test]
+    fn test_presence_round_trips_through_awareness_state() {
+        let presence = WbblWebappPresence {
+            cursor: Some(WbblePosition { x: 10.0, y: -4.5 }),
+            viewport: Some(WbblWebappViewport {
+                x: 100.0,
+                y: 50.0,
+                zoom: 1.5,
+            }),
+            dragging: WbblWebappGraphEntityIds {
+                nodes: vec!["a4bb8f66-29b2-4d3b-8f3e-7b4a6e2f4a10".to_owned()],
+                edges: vec![],
+                groups: vec![],
+            },
+        };
+        assert_eq!(
+            WbblWebappPresence::decode(&presence.encode()),
+            Some(presence)
+        );
+        assert_eq!(WbblWebappPresence::decode("{\"user\":\"someone\"}"), None);
+    }
+
+    #[test]
+    fn test_collaborator_colours_are_stable_and_distinct() {
+        assert_eq!(get_collaborator_colour(42), get_collaborator_colour(42));
+        assert_ne!(get_collaborator_colour(42), get_collaborator_colour(43));
+
+        let collaborator = WbblWebappCollaborator::new(
+            42,
+            WbblWebappPresence::default(),
+            WbblWebappGraphEntityIds::default(),
+        );
+        assert_eq!(collaborator.client_id, "42");
+        assert_eq!(collaborator.colour, get_collaborator_colour(42));
+    }
+}
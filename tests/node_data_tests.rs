@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod node_data_tests {
+    use std::collections::HashMap;
+
+    use wbbl::{
+        data_types::{AbstractDataType, CompositeSize, ConcreteDataType},
+        graph_transfer_types::{Any, WbblWebappNodeType},
+        graph_types::{
+            get_declared_port_type, validate_node_data, ConstantValue, InputPortId, OutputPortId,
+            PortId, VectorComponent,
+        },
+        store_errors::WbblWebappStoreError,
+    };
+
+    fn data(entries: &[(&str, Any)]) -> HashMap<String, Any> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_constant_data() {
+        let vec3 = data(&[
+            ("x", Any::Number(0.5)),
+            ("y", Any::Number(-1.0)),
+            ("z", Any::Number(2.0)),
+        ]);
+        assert!(validate_node_data(WbblWebappNodeType::Vec3, &vec3).is_ok());
+        assert_eq!(
+            ConstantValue::decode(WbblWebappNodeType::Vec3, &vec3).unwrap(),
+            ConstantValue::Vector3([0.5, -1.0, 2.0])
+        );
+
+        let int = data(&[("value", Any::BigInt(-7))]);
+        assert_eq!(
+            ConstantValue::decode(WbblWebappNodeType::Int, &int).unwrap(),
+            ConstantValue::Int(-7)
+        );
+        assert!(validate_node_data(WbblWebappNodeType::Add, &HashMap::new()).is_ok());
+
+        let swizzle = data(&[("pattern", Any::String("bgra".into()))]);
+        assert!(validate_node_data(WbblWebappNodeType::Swizzle, &swizzle).is_ok());
+        assert_eq!(
+            VectorComponent::parse_pattern("zxy"),
+            Some(vec![
+                VectorComponent::Z,
+                VectorComponent::X,
+                VectorComponent::Y
+            ])
+        );
+    }
+
+    #[test]
+    fn test_invalid_constant_data() {
+        let missing = data(&[("x", Any::Number(0.5))]);
+        assert!(matches!(
+            validate_node_data(WbblWebappNodeType::Vec2, &missing),
+            Err(WbblWebappStoreError::MalformedNodeData)
+        ));
+
+        let wrong_kind = data(&[("value", Any::Bool(true))]);
+        assert!(matches!(
+            validate_node_data(WbblWebappNodeType::Float, &wrong_kind),
+            Err(WbblWebappStoreError::MalformedNodeData)
+        ));
+
+        let not_finite = data(&[("value", Any::Number(f64::NAN))]);
+        assert!(matches!(
+            validate_node_data(WbblWebappNodeType::Float, &not_finite),
+            Err(WbblWebappStoreError::MalformedNodeData)
+        ));
+
+        let out_of_range = data(&[
+            ("r", Any::Number(1.5)),
+            ("g", Any::Number(0.0)),
+            ("b", Any::Number(0.0)),
+            ("a", Any::Number(1.0)),
+        ]);
+        assert!(matches!(
+            validate_node_data(WbblWebappNodeType::Color, &out_of_range),
+            Err(WbblWebappStoreError::MalformedNodeData)
+        ));
+
+        let unknown_key = data(&[("value", Any::Number(1.0)), ("w", Any::Number(1.0))]);
+        assert!(matches!(
+            validate_node_data(WbblWebappNodeType::Float, &unknown_key),
+            Err(WbblWebappStoreError::UnknownNodeDataKey)
+        ));
+        assert!(matches!(
+            validate_node_data(WbblWebappNodeType::Add, &unknown_key),
+            Err(WbblWebappStoreError::UnknownNodeDataKey)
+        ));
+
+        for pattern in ["", "xyzwx", "xq"] {
+            let swizzle = data(&[("pattern", Any::String(pattern.into()))]);
+            assert!(matches!(
+                validate_node_data(WbblWebappNodeType::Swizzle, &swizzle),
+                Err(WbblWebappStoreError::MalformedNodeData)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_declared_port_types() {
+        let output_input = PortId::Input(InputPortId {
+            node_id: 1,
+            port_index: 0,
+        });
+        let less_output = PortId::Output(OutputPortId {
+            node_id: 2,
+            port_index: 0,
+        });
+        let material =
+            get_declared_port_type(WbblWebappNodeType::Output, &HashMap::new(), &output_input)
+                .unwrap()
+                .unwrap();
+        let comparison =
+            get_declared_port_type(WbblWebappNodeType::Less, &HashMap::new(), &less_output)
+                .unwrap()
+                .unwrap();
+        assert_eq!(material, AbstractDataType::AnyMaterial);
+        assert!(!AbstractDataType::are_types_compatible(
+            comparison, material
+        ));
+
+        let swizzle = data(&[("pattern", Any::String("xyz".into()))]);
+        let swizzle_output = PortId::Output(OutputPortId {
+            node_id: 3,
+            port_index: 0,
+        });
+        assert_eq!(
+            get_declared_port_type(WbblWebappNodeType::Swizzle, &swizzle, &swizzle_output).unwrap(),
+            Some(AbstractDataType::ConcreteType(ConcreteDataType::Float(
+                CompositeSize::S3
+            )))
+        );
+        assert_eq!(
+            get_declared_port_type(
+                WbblWebappNodeType::Swizzle,
+                &swizzle,
+                &PortId::Input(InputPortId {
+                    node_id: 3,
+                    port_index: 1,
+                })
+            )
+            .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod spatial_index_tests {
+    use glam::Vec2;
+    use rstar::AABB;
+    use wbbl::{graph_transfer_types::WbblWebappGraphEntityId, spatial_index::SpatialIndex};
+
+    fn insert(index: &mut SpatialIndex, id: WbblWebappGraphEntityId, min: Vec2, max: Vec2) {
+        index.insert_or_update_envelope(id, AABB::from_corners(min.into(), max.into()));
+    }
+
+    #[test]
+    fn test_spatial_index_updates_and_removals() {
+        let mut index = SpatialIndex::new();
+        let node = WbblWebappGraphEntityId::NodeId(1);
+        let edge = WbblWebappGraphEntityId::EdgeId(2);
+        insert(&mut index, node, Vec2::ZERO, Vec2::new(100.0, 100.0));
+        insert(
+            &mut index,
+            edge,
+            Vec2::new(100.0, 50.0),
+            Vec2::new(300.0, 80.0),
+        );
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.query_rect(Vec2::new(50.0, 50.0), Vec2::new(150.0, 60.0)),
+            vec![node, edge]
+        );
+
+        // Moving an entity must not leave its old bounds behind
+        insert(
+            &mut index,
+            node,
+            Vec2::new(500.0, 500.0),
+            Vec2::new(600.0, 600.0),
+        );
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.node_at(Vec2::new(50.0, 50.0)), None);
+        assert_eq!(index.node_at(Vec2::new(550.0, 550.0)), Some(1));
+
+        assert!(index.remove(&node));
+        assert!(!index.remove(&node));
+        assert!(!index.contains(&node));
+        assert_eq!(index.node_at(Vec2::new(550.0, 550.0)), None);
+        assert_eq!(
+            index.query_rect(Vec2::ZERO, Vec2::new(1000.0, 1000.0)),
+            vec![edge]
+        );
+    }
+
+    #[test]
+    fn test_spatial_index_queries() {
+        let mut index = SpatialIndex::new();
+        let big_node = WbblWebappGraphEntityId::NodeId(1);
+        let junction = WbblWebappGraphEntityId::NodeId(2);
+        let far_node = WbblWebappGraphEntityId::NodeId(3);
+        let group = WbblWebappGraphEntityId::GroupId(4);
+        insert(&mut index, big_node, Vec2::ZERO, Vec2::new(200.0, 200.0));
+        insert(
+            &mut index,
+            junction,
+            Vec2::new(90.0, 90.0),
+            Vec2::new(110.0, 110.0),
+        );
+        insert(
+            &mut index,
+            far_node,
+            Vec2::new(2000.0, 0.0),
+            Vec2::new(2100.0, 100.0),
+        );
+        insert(
+            &mut index,
+            group,
+            Vec2::new(-10.0, -10.0),
+            Vec2::new(210.0, 210.0),
+        );
+
+        // The smallest overlapping node wins, and groups are never hit
+        assert_eq!(index.node_at(Vec2::new(100.0, 100.0)), Some(2));
+        assert_eq!(index.node_at(Vec2::new(10.0, 10.0)), Some(1));
+        assert_eq!(index.node_at(Vec2::new(205.0, 205.0)), None);
+
+        assert_eq!(index.nodes_near(Vec2::new(1990.0, 50.0), 20.0), vec![3]);
+        assert!(index.nodes_near(Vec2::new(1000.0, 50.0), 20.0).is_empty());
+
+        // A 800x600 viewport, zoomed in by 2 and panned so graph (100, 0) is at the top left
+        let visible =
+            index.entities_in_viewport(Vec2::new(-200.0, 0.0), 2.0, Vec2::new(800.0, 600.0), 0.0);
+        assert_eq!(visible, vec![big_node, junction, group]);
+        let visible = index.entities_in_viewport(Vec2::ZERO, 0.25, Vec2::new(800.0, 600.0), 0.0);
+        assert_eq!(visible, vec![big_node, junction, far_node, group]);
+        assert!(index
+            .entities_in_viewport(Vec2::ZERO, 0.0, Vec2::new(800.0, 600.0), 0.0)
+            .is_empty());
+    }
+}
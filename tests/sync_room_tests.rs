@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod sync_room_tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use wbbl::{
+        awareness_sync::{AwarenessSync, ConnectionState},
+        sync_room::{
+            decode_update_log, encode_update_log_entry, PeerId, Recipient, RoomOutput, SyncRoom,
+            SyncRoomError,
+        },
+        sync_transport::{InMemoryTransport, SyncTransport},
+    };
+    use yrs::{sync::Awareness, Doc, Map, MapRef, Transact};
+
+    struct Client {
+        peer: PeerId,
+        awareness: Rc<RefCell<Awareness>>,
+        sync: AwarenessSync<InMemoryTransport>,
+        server_end: InMemoryTransport,
+    }
+
+    fn client(peer: PeerId) -> Client {
+        let awareness = Rc::new(RefCell::new(Awareness::new(Doc::new())));
+        let (client_end, server_end) = InMemoryTransport::pair();
+        Client {
+            peer,
+            sync: AwarenessSync::new(awareness.clone(), client_end),
+            awareness,
+            server_end,
+        }
+    }
+
+    fn route(clients: &[&Client], output: RoomOutput, log: &mut Vec<u8>) {
+        for message in output.messages {
+            for client in clients {
+                let recipient = match message.recipient {
+                    Recipient::Peer(peer) => client.peer == peer,
+                    Recipient::AllExcept(peer) => client.peer != peer,
+                };
+                if recipient {
+                    client.server_end.send(&message.data).unwrap();
+                }
+            }
+        }
+        for update in output.updates {
+            log.extend(encode_update_log_entry(&update));
+        }
+    }
+
+    fn join(room: &mut SyncRoom, clients: &[&Client], joining: &Client, log: &mut Vec<u8>) {
+        let client_id = joining.awareness.borrow().client_id();
+        let output = room.join(joining.peer, client_id);
+        route(clients, output, log);
+        joining.sync.handle_open();
+    }
+
+    // Delivers queued messages between the room and its clients until nothing is left to send
+    fn pump(room: &mut SyncRoom, clients: &[&Client], log: &mut Vec<u8>) {
+        loop {
+            let mut delivered = false;
+            for client in clients {
+                while let Some(message) = client.server_end.receive() {
+                    let output = room.handle_message(client.peer, &message).unwrap();
+                    route(clients, output, log);
+                    delivered = true;
+                }
+                while let Some(message) = client.sync.transport().receive() {
+                    client.sync.handle_message(&message);
+                    delivered = true;
+                }
+            }
+            if !delivered {
+                break;
+            }
+        }
+    }
+
+    fn nodes(doc: &Doc) -> MapRef {
+        doc.get_or_insert_map("nodes")
+    }
+
+    fn insert(client: &Client, key: &str) {
+        let awareness = client.awareness.borrow();
+        let map = nodes(awareness.doc());
+        let mut txn = awareness.doc().transact_mut();
+        map.insert(&mut txn, key, true);
+    }
+
+    fn contains(doc: &Doc, key: &str) -> bool {
+        let map = nodes(doc);
+        let txn = doc.transact();
+        map.contains_key(&txn, key)
+    }
+
+    #[test]
+    fn test_clients_sync_through_room() {
+        let mut room = SyncRoom::new(Doc::new());
+        let mut log = vec![];
+        let (a, b) = (client(0), client(1));
+        insert(&a, "offline");
+
+        join(&mut room, &[&a, &b], &a, &mut log);
+        join(&mut room, &[&a, &b], &b, &mut log);
+        pump(&mut room, &[&a, &b], &mut log);
+
+        for client in [&a, &b] {
+            assert!(client.sync.is_hydrated());
+            assert_eq!(client.sync.connection_state(), ConnectionState::Synced);
+        }
+        assert!(contains(room.doc(), "offline"));
+        assert!(contains(b.awareness.borrow().doc(), "offline"));
+
+        insert(&b, "online");
+        pump(&mut room, &[&a, &b], &mut log);
+        assert!(contains(a.awareness.borrow().doc(), "online"));
+    }
+
+    #[test]
+    fn test_leaving_clears_awareness() {
+        let mut room = SyncRoom::new(Doc::new());
+        let mut log = vec![];
+        let (a, b) = (client(0), client(1));
+        a.awareness.borrow_mut().set_local_state("{}");
+        join(&mut room, &[&a, &b], &a, &mut log);
+        join(&mut room, &[&a, &b], &b, &mut log);
+        pump(&mut room, &[&a, &b], &mut log);
+
+        let a_client_id = a.awareness.borrow().client_id();
+        assert!(b.awareness.borrow().clients().contains_key(&a_client_id));
+
+        let output = room.leave(a.peer);
+        route(&[&b], output, &mut log);
+        pump(&mut room, &[&b], &mut log);
+        assert!(!b.awareness.borrow().clients().contains_key(&a_client_id));
+        assert!(!room.is_empty());
+    }
+
+    #[test]
+    fn test_room_is_restored_from_log() {
+        let mut room = SyncRoom::new(Doc::new());
+        let mut log = vec![];
+        let a = client(0);
+        join(&mut room, &[&a], &a, &mut log);
+        pump(&mut room, &[&a], &mut log);
+        // The client's reply to the room's sync step 1 has nothing new, so isn't persisted
+        assert!(log.is_empty());
+        insert(&a, "first");
+        insert(&a, "second");
+        pump(&mut room, &[&a], &mut log);
+
+        // An entry cut short by an interrupted write
+        log.extend(encode_update_log_entry(&[1, 2, 3]).into_iter().take(5));
+        let updates = decode_update_log(&log);
+        assert_eq!(updates.len(), 2);
+
+        let restored = SyncRoom::from_updates(&updates).unwrap();
+        assert!(contains(restored.doc(), "first"));
+        assert!(contains(restored.doc(), "second"));
+
+        let compacted = SyncRoom::from_updates(&[room.encode_state()]).unwrap();
+        assert!(contains(compacted.doc(), "first"));
+        assert!(contains(compacted.doc(), "second"));
+    }
+
+    #[test]
+    fn test_malformed_payload_is_rejected() {
+        let mut room = SyncRoom::new(Doc::new());
+        assert_eq!(
+            room.handle_message(0, &[0, 7]).err(),
+            Some(SyncRoomError::MalformedPayload)
+        );
+    }
+}
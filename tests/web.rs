@@ -3,7 +3,14 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
+use wbbl::{
+    graph_transfer_types::WbblWebappNodeType,
+    store_errors::WbblWebappStoreError,
+    wbbl_webapp_graph_store::{NewWbblWebappNode, WbblWebappGraphStore},
+};
+use web_sys::{js_sys, Worker};
 
 wasm_bindgen_test_configure!(run_in_browser);
 
@@ -11,3 +18,70 @@ wasm_bindgen_test_configure!(run_in_browser);
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+fn get_string(value: &JsValue, key: &str) -> String {
+    js_sys::Reflect::get(value, &key.into())
+        .unwrap()
+        .as_string()
+        .unwrap()
+}
+
+fn get_entities(store: &mut WbblWebappGraphStore, key: &str) -> Vec<JsValue> {
+    let snapshot = store.get_snapshot().unwrap();
+    js_sys::Array::from(&js_sys::Reflect::get(&snapshot, &key.into()).unwrap()).to_vec()
+}
+
+fn node_ids_of_type(store: &mut WbblWebappGraphStore, type_name: &str) -> Vec<String> {
+    get_entities(store, "nodes")
+        .iter()
+        .filter(|n| get_string(n, "type") == type_name)
+        .map(|n| get_string(n, "id"))
+        .collect()
+}
+
+// Edges as (source, target) pairs
+fn edges(store: &mut WbblWebappGraphStore) -> Vec<(String, String)> {
+    get_entities(store, "edges")
+        .iter()
+        .map(|e| (get_string(e, "source"), get_string(e, "target")))
+        .collect()
+}
+
+// An empty graph, with an output connected to a slab, and a second slab which isn't connected
+fn store_with_unconnected_slab() -> (WbblWebappGraphStore, String, String, String) {
+    // The worker is never asked to do anything the tests depend on
+    let worker = Worker::new("data:text/javascript,").unwrap();
+    let mut store = WbblWebappGraphStore::empty(worker).unwrap();
+    let connected_slab = edges(&mut store)[0].0.clone();
+    store
+        .add_node(NewWbblWebappNode::new(200.0, 800.0, WbblWebappNodeType::Slab).unwrap())
+        .unwrap();
+    let unconnected_slab = node_ids_of_type(&mut store, "slab")
+        .into_iter()
+        .find(|id| *id != connected_slab)
+        .unwrap();
+    let output = node_ids_of_type(&mut store, "output")[0].clone();
+    (store, output, connected_slab, unconnected_slab)
+}
+
+#[wasm_bindgen_test]
+fn second_connection_to_input_is_rejected() {
+    let (mut store, output, connected_slab, unconnected_slab) = store_with_unconnected_slab();
+    assert!(matches!(
+        store.add_edge(&unconnected_slab, &output, 0, 0, false),
+        Err(WbblWebappStoreError::InputAlreadyConnected)
+    ));
+    assert_eq!(edges(&mut store), vec![(connected_slab, output)]);
+}
+
+#[wasm_bindgen_test]
+fn second_connection_to_input_replaces_existing() {
+    let (mut store, output, _, unconnected_slab) = store_with_unconnected_slab();
+    assert!(store
+        .can_add_edge(&unconnected_slab, &output, 0, 0)
+        .unwrap());
+    store
+        .add_edge(&unconnected_slab, &output, 0, 0, true)
+        .unwrap();
+    assert_eq!(edges(&mut store), vec![(unconnected_slab, output)]);
+}
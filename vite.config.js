@@ -15,6 +15,15 @@ export default defineConfig({
     topLevelAwait()
   ],
   base: "/app",
+  server: {
+    proxy: {
+      // Sync connections go to the local sync server (`npm run sync-server`)
+      "^/api/.*/connect/": {
+        target: process.env.WBBL_SYNC_SERVER ?? "http://localhost:4000",
+        ws: true,
+      },
+    },
+  },
   worker: {
     // Not needed with vite-plugin-top-level-await >= 1.3.0
     format: "es",
@@ -1,25 +1,25 @@
 import { useEffect, useState } from "react";
-import Graph from "./components/Graph";
+import Graph, { GraphRootProps } from "./components/Graph";
 import { graphWorker } from "./graph-worker-reference";
 import LoadingScreen from "./components/LoadingScreen";
 import ApplicationMenu from "./components/ApplicationMenu";
 import { ShortcutScope } from "./hooks/use-shortcut";
 
-function App() {
+function App(props: GraphRootProps) {
   const [ready, setReady] = useState<boolean>(false);
   useEffect(() => {
     let timeout_handle: any = 0;
     const listener = (msg: MessageEvent) => {
-      if (msg.data === "Ready") {
+      if (msg.data?.message === "Ready") {
         clearInterval(timeout_handle);
         graphWorker.removeEventListener("message", listener);
         setReady(true);
       }
     };
     graphWorker.addEventListener("message", listener);
-    graphWorker.postMessage("Poll");
+    graphWorker.postMessage({ message: "Poll" });
     timeout_handle = setInterval(() => {
-      graphWorker.postMessage("Poll");
+      graphWorker.postMessage({ message: "Poll" });
     }, 200);
     return () => {
       graphWorker.removeEventListener("message", listener);
@@ -32,7 +32,7 @@ function App() {
       {ready ? (
         <div style={{ height: "100dvh", width: "100dvw" }}>
           <ApplicationMenu path={[]} />
-          <Graph />
+          <Graph {...props} />
         </div>
       ) : (
         <LoadingScreen />
@@ -0,0 +1,76 @@
+import { ViewportPortal } from "@xyflow/react";
+import { WbblCollaborator } from "../hooks/use-collaborators";
+import { WbblWebappGraphSnapshot } from "../hooks/use-wbbl-graph-store";
+
+type CollaboratorRendererProps = {
+  collaborators: WbblCollaborator[];
+  nodes: WbblWebappGraphSnapshot["nodes"];
+  zoom: number;
+  width: number;
+  height: number;
+};
+
+const SELECTION_OUTLINE_PADDING = 6;
+
+export function CollaboratorRenderer({
+  collaborators,
+  nodes,
+  zoom,
+  width,
+  height,
+}: CollaboratorRendererProps) {
+  const nodesById = new Map(nodes.map((n) => [n.id, n]));
+  return (
+    <ViewportPortal>
+      <svg
+        id="collaborator-renderer"
+        style={{
+          width: width,
+          overflow: "visible",
+          height: height,
+          position: "absolute",
+          pointerEvents: "none",
+          left: 0,
+          top: 0,
+          zIndex: 5,
+          transformOrigin: "0 0",
+        }}
+      >
+        {collaborators.map((c) => (
+          <g key={c.client_id}>
+            {[...c.selections.nodes, ...c.dragging.nodes].map((id) => {
+              const node = nodesById.get(id);
+              if (node === undefined) {
+                return undefined;
+              }
+              return (
+                <rect
+                  key={id}
+                  x={node.position.x - SELECTION_OUTLINE_PADDING}
+                  y={node.position.y - SELECTION_OUTLINE_PADDING}
+                  width={(node.width ?? 0) + SELECTION_OUTLINE_PADDING * 2}
+                  height={(node.height ?? 0) + SELECTION_OUTLINE_PADDING * 2}
+                  rx={SELECTION_OUTLINE_PADDING * 2}
+                  fill="none"
+                  stroke={c.colour}
+                  strokeWidth={2 / zoom}
+                />
+              );
+            })}
+            {c.cursor && (
+              // Cursors are scaled inversely to the zoom so they stay the
+              // same size on screen
+              <path
+                transform={`translate(${c.cursor.x}, ${c.cursor.y}) scale(${1 / zoom})`}
+                d="M0 0 L0 16 L4.5 12 L8 19 L10.5 18 L7 11 L12.5 11 Z"
+                fill={c.colour}
+                stroke="white"
+                strokeWidth={1}
+              />
+            )}
+          </g>
+        ))}
+      </svg>
+    </ViewportPortal>
+  );
+}
@@ -57,10 +57,14 @@ import {
 } from "../hooks/use-preferences-store";
 import { isHotkeyPressed } from "react-hotkeys-hook";
 import { transformKeybindingForReactFlow } from "../utils/transform-keybinding-for-react-flow";
-import { useElkJs } from "../hooks/use-elkjs";
+import { useAutoLayout } from "../hooks/use-auto-layout";
 import { MousePositionContext } from "../hooks/use-card-wbbl";
 import { NodeGroupRenderer } from "./NodeGroupRenderer";
 import GraphToolbar, { modes } from "./GraphToolbar";
+import { useCollaborators } from "../hooks/use-collaborators";
+import { CollaboratorRenderer } from "./CollaboratorRenderer";
+import { useIsHydrated } from "../hooks/use-is-hydrated";
+import LoadingScreen from "./LoadingScreen";
 
 const edgeTypes = {
   default: WbbleEdge,
@@ -88,6 +92,10 @@ function Graph() {
   const [nodeMenuOpen, setNodeMenuOpen] = useState<boolean>(false);
   const flow = useReactFlow();
   const viewport = useViewport();
+  const collaborators = useCollaborators(graphStore);
+  useEffect(() => {
+    graphStore.set_viewport(viewport.x, viewport.y, viewport.zoom);
+  }, [graphStore, viewport.x, viewport.y, viewport.zoom]);
 
   const [isConnecting, setIsConnecting] = useState(false);
   const [isSelecting, setIsSelecting] = useState(false);
@@ -131,9 +139,17 @@ function Graph() {
   const onMouseMove = useCallback(
     (evt: ReactMouseEvent<HTMLDivElement>) => {
       mousePos.current = [evt.clientX, evt.clientY];
+      const cursor = flow.screenToFlowPosition(
+        { x: evt.clientX, y: evt.clientY },
+        { snapToGrid: false },
+      );
+      graphStore.set_cursor_position(cursor.x, cursor.y);
     },
-    [mousePos],
+    [mousePos, flow, graphStore],
   );
+  const onPointerLeave = useCallback(() => {
+    graphStore.clear_cursor_position();
+  }, [graphStore]);
 
   const onPaneClick = useCallback(
     (evt: React.MouseEvent<Element, MouseEvent>) => {
@@ -244,6 +260,11 @@ function Graph() {
           ]),
         );
         graphStore.set_node_positions(ids, positions, dragging);
+        graphStore.set_dragged_entities({
+          nodes: position_changes.filter((x) => x.dragging).map((x) => x.id),
+          edges: [],
+          groups: [],
+        });
       }
       for (const change of changes) {
         switch (change.type) {
@@ -299,6 +320,7 @@ function Graph() {
               change.item.target,
               BigInt(change.item.sourceHandle?.replace("s#", "") ?? "0"),
               BigInt(change.item.targetHandle?.replace("t#", "") ?? "0"),
+              false,
             );
             break;
           case "remove":
@@ -321,6 +343,7 @@ function Graph() {
         newConnection.target,
         BigInt(newConnection.sourceHandle?.replace("s#", "") ?? "0"),
         BigInt(newConnection.targetHandle?.replace("t#", "") ?? "0"),
+        true,
       );
     },
     [graphStore],
@@ -344,6 +367,17 @@ function Graph() {
     [graphStore],
   );
 
+  const isValidConnection = useCallback(
+    (connection: Edge | Connection) =>
+      graphStore.can_add_edge(
+        connection.source,
+        connection.target,
+        BigInt(connection.sourceHandle?.replace("s#", "") ?? "0"),
+        BigInt(connection.targetHandle?.replace("t#", "") ?? "0"),
+      ),
+    [graphStore],
+  );
+
   const onConnect = useCallback(
     (connection: Connection) => {
       graphStore.add_edge(
@@ -351,6 +385,7 @@ function Graph() {
         connection.target,
         BigInt(connection.sourceHandle?.replace("s#", "") ?? "0"),
         BigInt(connection.targetHandle?.replace("t#", "") ?? "0"),
+        true,
       );
     },
     [graphStore],
@@ -436,13 +471,13 @@ function Graph() {
     },
   );
 
-  const elkJs = useElkJs();
+  const autoLayout = useAutoLayout();
   useScopedShortcut(
     KeyboardShortcut.AutoLayout,
     () => {
-      elkJs();
+      autoLayout();
     },
-    [elkJs],
+    [autoLayout],
     {
       disabled: snapshot.nodes.every((x) => !x.selected),
     },
@@ -549,6 +584,7 @@ function Graph() {
                 edgeTypes={edgeTypes}
                 nodeTypes={nodeTypes}
                 onConnect={onConnect}
+                isValidConnection={isValidConnection}
                 deleteKeyCode={[]}
                 panOnDrag={
                   mode === "pointer" || mode === "comment" ? true : [1]
@@ -592,6 +628,7 @@ function Graph() {
                   onMouseMove,
                 )}
                 onPointerUp={connectingHandlers.onPointerMove}
+                onPointerLeave={onPointerLeave}
                 selectionMode={SelectionMode.Partial}
                 proOptions={useMemo(() => ({ hideAttribution: true }), [])}
                 fitView
@@ -603,6 +640,13 @@ function Graph() {
                   width={width}
                   height={height}
                 />
+                <CollaboratorRenderer
+                  collaborators={collaborators}
+                  nodes={nodes}
+                  zoom={viewport.zoom}
+                  width={width}
+                  height={height}
+                />
                 <svg
                   id="edge-end-renderer"
                   style={{
@@ -642,8 +686,44 @@ function Graph() {
   );
 }
 
-export default function GraphRoot() {
-  const graphStore = useMemo(() => WbblWebappGraphStore.empty(graphWorker), []);
+export type GraphRootProps = {
+  projectName?: string;
+  graphId?: string;
+};
+
+type OwnedGraphStore = GraphRootProps & { graphStore: WbblWebappGraphStore };
+
+export default function GraphRoot({ projectName, graphId }: GraphRootProps) {
+  const [ownedStore, setOwnedStore] = useState<OwnedGraphStore | null>(null);
+  useEffect(() => {
+    const graphStore =
+      projectName !== undefined && graphId !== undefined
+        ? WbblWebappGraphStore.connect(graphWorker, projectName, graphId)
+        : WbblWebappGraphStore.empty(graphWorker);
+    setOwnedStore({ projectName, graphId, graphStore });
+    return () => {
+      setOwnedStore(null);
+      graphStore.free();
+    };
+  }, [projectName, graphId]);
+
+  // The store of the previous graph is unmounted in the same render the ids change, so nothing
+  // below uses it once it has been freed
+  if (
+    ownedStore === null ||
+    ownedStore.projectName !== projectName ||
+    ownedStore.graphId !== graphId
+  ) {
+    return <LoadingScreen />;
+  }
+  return <GraphStoreRoot graphStore={ownedStore.graphStore} />;
+}
+
+function GraphStoreRoot({ graphStore }: { graphStore: WbblWebappGraphStore }) {
+  const isHydrated = useIsHydrated(graphStore);
+  if (!isHydrated) {
+    return <LoadingScreen />;
+  }
 
   return (
     <ShortcutScope
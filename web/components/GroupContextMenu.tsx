@@ -22,7 +22,7 @@ import MicroCutIcon from "./icons/micro/MicroCutIcon";
 import MicroCopyPasteIcon from "./icons/micro/MicroCopyPasteIcon";
 import MicroDuplicateIcon from "./icons/micro/MicroDuplicateIcon";
 import { contextMenuContentPropDefs } from "@radix-ui/themes/props";
-import { useElkJs } from "../hooks/use-elkjs";
+import { useAutoLayout } from "../hooks/use-auto-layout";
 import MicroUngroupIcon from "./icons/micro/MicroUngroupIcon";
 import { useScopedShortcut } from "../hooks/use-shortcut";
 import { WbblGraphStoreContext } from "../hooks/use-wbbl-graph-store";
@@ -78,14 +78,14 @@ function GroupContextMenu(
     evt.stopPropagation();
   }, []);
 
-  const elkJs = useElkJs();
-  const onElkJs = useCallback(() => {
+  const autoLayout = useAutoLayout();
+  const onAutoLayout = useCallback(() => {
     if (props.selected) {
-      elkJs();
+      autoLayout();
     } else {
-      elkJs(new Set(props.nodes), new Set(props.edges));
+      autoLayout(new Set(props.nodes));
     }
-  }, [elkJs, props.nodes, props.edges, props.selected]);
+  }, [autoLayout, props.nodes, props.selected]);
 
   const graphStore = useContext(WbblGraphStoreContext);
 
@@ -163,7 +163,7 @@ function GroupContextMenu(
         </ContextMenu.Item>
         <ContextMenu.Separator />
         <ContextMenu.Item
-          onClick={onElkJs}
+          onClick={onAutoLayout}
           shortcut={
             autoLayoutShortcut
               ? formatKeybinding(autoLayoutShortcut)
@@ -223,7 +223,7 @@ function GroupContextMenu(
     makeJunctionShortcut,
     props.edges,
     props.nodes,
-    onElkJs,
+    onAutoLayout,
   ]);
 
   const menu = useMemo(
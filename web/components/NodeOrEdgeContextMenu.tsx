@@ -19,7 +19,7 @@ import {
 import { KeyboardShortcut, WbblWebappNodeType } from "../../pkg/wbbl";
 import formatKeybinding from "../utils/format-keybinding";
 import { useScopedShortcut } from "../hooks/use-shortcut";
-import { useElkJs } from "../hooks/use-elkjs";
+import { useAutoLayout } from "../hooks/use-auto-layout";
 import keybindingDescriptors from "../keybind-descriptors";
 import { useReactFlow } from "@xyflow/react";
 import { JUNCTION_HEIGHT, JUNCTION_WIDTH } from "./node_types/JunctionNode";
@@ -142,10 +142,10 @@ function NodeOrEdgeContextMenu(
     [props.id, graphStore, currentNodeOrEdgeExclusivelySelected, props.isEdge],
   );
 
-  const elkJs = useElkJs();
-  const onElkJs = useCallback(() => {
-    elkJs();
-  }, [elkJs]);
+  const autoLayout = useAutoLayout();
+  const onAutoLayout = useCallback(() => {
+    autoLayout();
+  }, [autoLayout]);
 
   useScopedShortcut(
     KeyboardShortcut.Delete,
@@ -352,7 +352,7 @@ function NodeOrEdgeContextMenu(
             {selectedNodesCount > 1 ? (
               <>
                 <ContextMenu.Item
-                  onClick={onElkJs}
+                  onClick={onAutoLayout}
                   shortcut={
                     autoLayoutShortcut
                       ? formatKeybinding(autoLayoutShortcut)
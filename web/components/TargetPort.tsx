@@ -2,7 +2,6 @@ import {
   Handle,
   Position,
   ReactFlowState,
-  useHandleConnections,
   useNodeId,
   useStore,
 } from "@xyflow/react";
@@ -13,6 +12,7 @@ import { getStyleForType } from "../port-type-styling";
 import { PORT_SIZE } from "../port-constants";
 import { Text } from "@radix-ui/themes";
 import { PortRefStoreContext } from "../hooks/use-port-location";
+import { WbblGraphStoreContext } from "../hooks/use-wbbl-graph-store";
 
 const selector = (s: ReactFlowState) => ({
   nodeInternals: s.nodes,
@@ -42,19 +42,17 @@ function TargetPort(props: TargetPortProps) {
     handle?.handleId as undefined | `s#${number}`,
   );
 
-  const connections = useHandleConnections({
-    type: "target",
-    id: props.id,
-  });
-
+  const graphStore = useContext(WbblGraphStoreContext);
   const isHandleConnectable = useMemo(() => {
     const result =
-      connections.length == 0 &&
       !!handlePortType &&
       !!portType &&
-      WbblWebappGraphStore.are_port_types_compatible(portType, handlePortType);
+      WbblWebappGraphStore.are_port_types_compatible(portType, handlePortType) &&
+      !!handle?.nodeId &&
+      !!nodeId &&
+      !graphStore.would_create_cycle(handle.nodeId, nodeId);
     return result;
-  }, [connections.length, portType, handlePortType]);
+  }, [portType, handlePortType, graphStore, handle?.nodeId, nodeId]);
 
   const handleClassName = useMemo(
     () => `${getStyleForType(portType)} ${isHandleConnectable ? "glow" : " "}`,
@@ -11,6 +11,7 @@ import { EdgeStyle, WbblRope, WbblWebappGraphStore } from "../../pkg/wbbl";
 import { createPortal } from "react-dom";
 import { WbblEdgeEndContext } from "../hooks/use-edge-end-portal";
 import { usePortTypeWithNodeId } from "../hooks/use-port-type";
+import { useIsEdgeInTypeConflict } from "../hooks/use-type-conflict";
 import { getStyleForType } from "../port-type-styling";
 import { HALF_PORT_SIZE, PORT_SIZE } from "../port-constants";
 import {
@@ -328,12 +329,14 @@ function WbbleEdge({
       WbblWebappGraphStore.get_edge_type(sourceType, targetType),
     [sourceType, targetType],
   );
+  const isInTypeConflict = useIsEdgeInTypeConflict(id);
   const edgeClassName = useMemo(() => {
+    const conflictClassName = isInTypeConflict ? " type-conflict" : "";
     if (targetType && sourceType) {
-      return getStyleForType(edgeType);
+      return getStyleForType(edgeType) + conflictClassName;
     }
-    return "";
-  }, [edgeType]);
+    return conflictClassName;
+  }, [edgeType, isInTypeConflict]);
 
   return (
     <>
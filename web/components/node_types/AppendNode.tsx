@@ -0,0 +1,19 @@
+import { NodeProps } from "@xyflow/react";
+import WbblNode from "./WbbleNode";
+import { memo } from "react";
+import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
+
+function AppendNode(props: NodeProps) {
+  return (
+    <WbblNode
+      deleteable
+      copyable
+      previewable
+      outputPortLabels={[null]}
+      inputPortLabels={["a", "b"]}
+      {...props}
+    />
+  );
+}
+
+export default memo(AppendNode, areNodePropsEqual);
@@ -0,0 +1,145 @@
+import { NodeProps } from "@xyflow/react";
+import WbblNode from "./WbbleNode";
+import { memo, useCallback, useContext, useEffect, useState } from "react";
+import { Checkbox, Flex, Text, TextField } from "@radix-ui/themes";
+import {
+  WbblGraphStoreContext,
+  areNodePropsEqual,
+} from "../../hooks/use-wbbl-graph-store";
+
+type ConstantField =
+  | { Number: number }
+  | { BigInt: number | bigint }
+  | { Bool: boolean };
+
+const constantFields: { [key: string]: string[] } = {
+  float: ["value"],
+  int: ["value"],
+  bool: ["value"],
+  vec2: ["x", "y"],
+  vec3: ["x", "y", "z"],
+  vec4: ["x", "y", "z", "w"],
+  color: ["r", "g", "b", "a"],
+};
+
+function formatField(field: ConstantField | undefined): string {
+  if (field === undefined) {
+    return "";
+  }
+  if ("Number" in field) {
+    return field.Number.toString();
+  }
+  if ("BigInt" in field) {
+    return field.BigInt.toString();
+  }
+  return field.Bool ? "true" : "false";
+}
+
+function parseField(type: string, text: string): ConstantField | undefined {
+  if (type === "int") {
+    const value = Number.parseInt(text, 10);
+    return Number.isSafeInteger(value) ? { BigInt: value } : undefined;
+  }
+  const value = Number.parseFloat(text);
+  return Number.isFinite(value) ? { Number: value } : undefined;
+}
+
+function ConstantFieldInput({
+  nodeId,
+  type,
+  fieldKey,
+  field,
+}: {
+  nodeId: string;
+  type: string;
+  fieldKey: string;
+  field: ConstantField | undefined;
+}) {
+  const graphStore = useContext(WbblGraphStoreContext);
+  const storedText = formatField(field);
+  const [text, setText] = useState(storedText);
+  useEffect(() => setText(storedText), [storedText]);
+
+  const commit = useCallback(() => {
+    const value = parseField(type, text);
+    try {
+      if (value === undefined) {
+        throw new Error(`Invalid value for ${fieldKey}`);
+      }
+      graphStore.set_node_data(nodeId, fieldKey, value);
+    } catch (e) {
+      // Rejected by the store, so revert to the last stored value
+      console.error(e);
+      setText(storedText);
+    }
+  }, [graphStore, nodeId, type, fieldKey, storedText, text]);
+
+  const onKeyDown = useCallback(
+    (evt: React.KeyboardEvent<HTMLInputElement>) => {
+      if (evt.key === "Enter") {
+        commit();
+      }
+    },
+    [commit],
+  );
+
+  const onCheckedChange = useCallback(
+    (checked: boolean | "indeterminate") => {
+      graphStore.set_node_data(nodeId, fieldKey, { Bool: checked === true });
+    },
+    [graphStore, nodeId, fieldKey],
+  );
+
+  return (
+    <Flex gap="2" align="center" justify="between" width="100%">
+      <Text size="2" color="gray">
+        {fieldKey}
+      </Text>
+      {type === "bool" ? (
+        <Checkbox
+          className="nodrag"
+          checked={field !== undefined && "Bool" in field && field.Bool}
+          onCheckedChange={onCheckedChange}
+        />
+      ) : (
+        <TextField.Root
+          className="nodrag"
+          size="1"
+          value={text}
+          onChange={(evt) => setText(evt.target.value)}
+          onBlur={commit}
+          onKeyDown={onKeyDown}
+        />
+      )}
+    </Flex>
+  );
+}
+
+function ConstantNode(props: NodeProps) {
+  const data = props.data as unknown as Map<string, ConstantField>;
+  const fields = constantFields[props.type] ?? [];
+  return (
+    <WbblNode
+      deleteable
+      copyable
+      previewable
+      outputPortLabels={[null]}
+      inputPortLabels={[]}
+      {...props}
+    >
+      <Flex direction="column" gap="1" px="5" width="100%">
+        {fields.map((key) => (
+          <ConstantFieldInput
+            key={key}
+            nodeId={props.id}
+            type={props.type}
+            fieldKey={key}
+            field={data.get(key)}
+          />
+        ))}
+      </Flex>
+    </WbblNode>
+  );
+}
+
+export default memo(ConstantNode, areNodePropsEqual);
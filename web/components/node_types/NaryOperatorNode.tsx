@@ -0,0 +1,28 @@
+import { NodeProps } from "@xyflow/react";
+import WbblNode from "./WbbleNode";
+import { memo } from "react";
+import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
+
+const inputPortLabels: { [key: string]: string[] } = {
+  mix: ["a", "b", "t"],
+  clamp: ["x", "min", "max"],
+  smoothstep: ["low", "high", "x"],
+  step: ["edge", "x"],
+  fma: ["a", "b", "c"],
+  select: ["false", "true", "cond"],
+};
+
+function NaryOperatorNode(props: NodeProps) {
+  return (
+    <WbblNode
+      deleteable
+      copyable
+      previewable
+      outputPortLabels={[null]}
+      inputPortLabels={inputPortLabels[props.type] ?? []}
+      {...props}
+    />
+  );
+}
+
+export default memo(NaryOperatorNode, areNodePropsEqual);
@@ -1,25 +1,12 @@
 import { NodeProps } from "@xyflow/react";
 import WbblNode from "./WbbleNode";
-import { graphWorker } from "../../graph-worker-reference";
-import { memo, useLayoutEffect, useState } from "react";
-import { DeregisterCanvas, RegisterCanvas } from "../../worker_message_types";
+import { memo } from "react";
 import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
+import { usePreviewCanvas } from "../../hooks/use-preview-canvas";
 // import { WbblGraphStoreContext } from "../../hooks/use-wbbl-graph-store";
 
 function OutputNode(props: NodeProps) {
-  const [canvasRef, setCanvasRef] = useState<HTMLCanvasElement | null>(null);
-  useLayoutEffect(() => {
-    if (canvasRef) {
-      const offscreenCanvas = canvasRef.transferControlToOffscreen();
-      const msg: RegisterCanvas = { nodeId: props.id, offscreenCanvas };
-      graphWorker.postMessage({ RegisterCanvas: msg }, [offscreenCanvas]);
-
-      return () => {
-        const deregisterMessage: DeregisterCanvas = { nodeId: props.id };
-        graphWorker.postMessage({ DeregisterCanvas: deregisterMessage });
-      };
-    }
-  }, [canvasRef, props.id]);
+  const setCanvasRef = usePreviewCanvas(props.id);
 
   return (
     <WbblNode
@@ -31,7 +18,11 @@ function OutputNode(props: NodeProps) {
       {...props}
     >
       <canvas
-        style={{ backgroundColor: "transparent" }}
+        style={{
+          backgroundColor: "transparent",
+          width: 256,
+          height: 256,
+        }}
         width={256}
         height={256}
         ref={setCanvasRef}
@@ -1,24 +1,11 @@
 import { NodeProps } from "@xyflow/react";
 import WbblNode from "./WbbleNode";
-import { graphWorker } from "../../graph-worker-reference";
-import { memo, useLayoutEffect, useState } from "react";
-import { DeregisterCanvas, RegisterCanvas } from "../../worker_message_types";
+import { memo } from "react";
 import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
+import { usePreviewCanvas } from "../../hooks/use-preview-canvas";
 
 function PreviewNode(props: NodeProps) {
-  const [canvasRef, setCanvasRef] = useState<HTMLCanvasElement | null>(null);
-  useLayoutEffect(() => {
-    if (canvasRef) {
-      const offscreenCanvas = canvasRef.transferControlToOffscreen();
-      const msg: RegisterCanvas = { nodeId: props.id, offscreenCanvas };
-      graphWorker.postMessage({ RegisterCanvas: msg }, [offscreenCanvas]);
-
-      return () => {
-        const deregisterMessage: DeregisterCanvas = { nodeId: props.id };
-        graphWorker.postMessage({ DeregisterCanvas: deregisterMessage });
-      };
-    }
-  }, [canvasRef, props.id]);
+  const setCanvasRef = usePreviewCanvas(props.id);
 
   return (
     <WbblNode
@@ -30,7 +17,11 @@ function PreviewNode(props: NodeProps) {
       {...props}
     >
       <canvas
-        style={{ backgroundColor: "transparent" }}
+        style={{
+          backgroundColor: "transparent",
+          width: 128,
+          height: 128,
+        }}
         width={128}
         height={128}
         ref={setCanvasRef}
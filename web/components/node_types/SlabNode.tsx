@@ -3,6 +3,17 @@ import WbblNode from "./WbbleNode";
 import { memo } from "react";
 import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
 
+const slabInputPortLabels = [
+  "base colour",
+  "metallic",
+  "roughness",
+  "normal",
+  "emission",
+  "occlusion",
+  "alpha",
+  "clearcoat",
+];
+
 function SlabNode(props: NodeProps) {
   return (
     <WbblNode
@@ -10,7 +21,7 @@ function SlabNode(props: NodeProps) {
       copyable
       previewable
       outputPortLabels={[null]}
-      inputPortLabels={[]}
+      inputPortLabels={slabInputPortLabels}
       {...props}
     />
   );
@@ -0,0 +1,19 @@
+import { NodeProps } from "@xyflow/react";
+import WbblNode from "./WbbleNode";
+import { memo } from "react";
+import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
+
+function SplitNode(props: NodeProps) {
+  return (
+    <WbblNode
+      deleteable
+      copyable
+      previewable
+      outputPortLabels={["x", "y", "z", "w"]}
+      inputPortLabels={[null]}
+      {...props}
+    />
+  );
+}
+
+export default memo(SplitNode, areNodePropsEqual);
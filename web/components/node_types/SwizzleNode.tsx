@@ -0,0 +1,62 @@
+import { NodeProps } from "@xyflow/react";
+import WbblNode from "./WbbleNode";
+import { memo, useCallback, useContext, useEffect, useState } from "react";
+import { Flex, TextField } from "@radix-ui/themes";
+import {
+  WbblGraphStoreContext,
+  areNodePropsEqual,
+} from "../../hooks/use-wbbl-graph-store";
+
+function SwizzleNode(props: NodeProps) {
+  const graphStore = useContext(WbblGraphStoreContext);
+  const data = props.data as unknown as Map<string, { String: string }>;
+  const storedPattern = data.get("pattern")?.String ?? "";
+  const [pattern, setPattern] = useState(storedPattern);
+  useEffect(() => setPattern(storedPattern), [storedPattern]);
+
+  const commit = useCallback(() => {
+    try {
+      graphStore.set_node_data(props.id, "pattern", {
+        String: pattern.trim().toLowerCase(),
+      });
+    } catch (e) {
+      // Rejected by the store, so revert to the last stored pattern
+      console.error(e);
+      setPattern(storedPattern);
+    }
+  }, [graphStore, props.id, pattern, storedPattern]);
+
+  const onKeyDown = useCallback(
+    (evt: React.KeyboardEvent<HTMLInputElement>) => {
+      if (evt.key === "Enter") {
+        commit();
+      }
+    },
+    [commit],
+  );
+
+  return (
+    <WbblNode
+      deleteable
+      copyable
+      previewable
+      outputPortLabels={[null]}
+      inputPortLabels={[null]}
+      {...props}
+    >
+      <Flex px="5" width="100%">
+        <TextField.Root
+          className="nodrag"
+          size="1"
+          placeholder="xyzw"
+          value={pattern}
+          onChange={(evt) => setPattern(evt.target.value)}
+          onBlur={commit}
+          onKeyDown={onKeyDown}
+        />
+      </Flex>
+    </WbblNode>
+  );
+}
+
+export default memo(SwizzleNode, areNodePropsEqual);
@@ -0,0 +1,19 @@
+import { NodeProps } from "@xyflow/react";
+import WbblNode from "./WbbleNode";
+import { memo } from "react";
+import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
+
+function TimeNode(props: NodeProps) {
+  return (
+    <WbblNode
+      deleteable
+      copyable
+      previewable
+      outputPortLabels={["seconds", "delta", "sine"]}
+      inputPortLabels={[]}
+      {...props}
+    />
+  );
+}
+
+export default memo(TimeNode, areNodePropsEqual);
@@ -0,0 +1,19 @@
+import { NodeProps } from "@xyflow/react";
+import WbblNode from "./WbbleNode";
+import { memo } from "react";
+import { areNodePropsEqual } from "../../hooks/use-wbbl-graph-store";
+
+function UnaryOperatorNode(props: NodeProps) {
+  return (
+    <WbblNode
+      deleteable
+      copyable
+      previewable
+      outputPortLabels={[null]}
+      inputPortLabels={["x"]}
+      {...props}
+    />
+  );
+}
+
+export default memo(UnaryOperatorNode, areNodePropsEqual);
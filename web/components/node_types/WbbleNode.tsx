@@ -5,10 +5,11 @@ import SourcePort from "../SourcePort";
 import { HALF_PORT_SIZE, PORT_SIZE } from "../../port-constants";
 import { nodeMetaData } from ".";
 import NodeContextMenu from "../NodeOrEdgeContextMenu";
-import { Card, Heading, Flex } from "@radix-ui/themes";
+import { Card, Heading, Flex, Tooltip } from "@radix-ui/themes";
 import { Box } from "@radix-ui/themes";
 import { ShortcutScope } from "../../hooks/use-shortcut";
 import { useCardWbbl } from "../../hooks/use-card-wbbl";
+import { useNodeDiagnosticMessage } from "../../hooks/use-node-diagnostics";
 
 function positionSelector(id: string) {
   return (store: ReactFlowStore) => store.nodeLookup.get(id)!.position;
@@ -47,6 +48,7 @@ function WbblNode({
     dragging,
     selected: !!selected,
   });
+  const diagnosticMessage = useNodeDiagnosticMessage(id);
   const outputPorts = useMemo(
     () =>
       outputPortLabels.map((x: string | null, idx: number) => (
@@ -87,7 +89,7 @@ function WbblNode({
         <Card
           ref={contentsRef}
           aria-selected={selected}
-          className={`node-contents ${selected ? "selected" : ""} category-${nodeMetaData[type as keyof typeof nodeMetaData].category}`}
+          className={`node-contents ${selected ? "selected" : ""} ${diagnosticMessage ? "has-diagnostics" : ""} category-${nodeMetaData[type as keyof typeof nodeMetaData].category}`}
           style={{
             width: width!,
             height: height!,
@@ -101,6 +103,13 @@ function WbblNode({
           >
             {type}
           </Heading>
+          {diagnosticMessage && (
+            <Tooltip content={diagnosticMessage}>
+              <span className="node-diagnostic-badge" aria-label="Error">
+                !
+              </span>
+            </Tooltip>
+          )}
           <Flex justify={"center"} align={"center"}>
             {children}
           </Flex>
@@ -109,7 +118,15 @@ function WbblNode({
         </Card>
       </Box>
     ),
-    [selected, outputPorts, inputPorts, children, type, contentsRef],
+    [
+      selected,
+      outputPorts,
+      inputPorts,
+      children,
+      type,
+      contentsRef,
+      diagnosticMessage,
+    ],
   );
 
   return (
@@ -1,10 +1,17 @@
 import BinaryOperatorNode from "./BinaryOperatorNode";
+import UnaryOperatorNode from "./UnaryOperatorNode";
+import NaryOperatorNode from "./NaryOperatorNode";
 import BuiltInNode from "./BuiltInNode";
 import OutputNode from "./OutputNode";
 import PreviewNode from "./PreviewNode";
 import SlabNode from "./SlabNode";
 import { WbblWebappNodeType } from "../../../pkg/wbbl";
 import JunctionNode from "./JunctionNode";
+import ConstantNode from "./ConstantNode";
+import SplitNode from "./SplitNode";
+import AppendNode from "./AppendNode";
+import SwizzleNode from "./SwizzleNode";
+import TimeNode from "./TimeNode";
 
 export const nodeTypes = {
   output: OutputNode,
@@ -25,6 +32,43 @@ export const nodeTypes = {
   or: BinaryOperatorNode,
   "<<": BinaryOperatorNode,
   ">>": BinaryOperatorNode,
+  negate: UnaryOperatorNode,
+  not: UnaryOperatorNode,
+  abs: UnaryOperatorNode,
+  sign: UnaryOperatorNode,
+  floor: UnaryOperatorNode,
+  ceil: UnaryOperatorNode,
+  round: UnaryOperatorNode,
+  fract: UnaryOperatorNode,
+  trunc: UnaryOperatorNode,
+  sqrt: UnaryOperatorNode,
+  exp: UnaryOperatorNode,
+  log: UnaryOperatorNode,
+  sin: UnaryOperatorNode,
+  cos: UnaryOperatorNode,
+  tan: UnaryOperatorNode,
+  asin: UnaryOperatorNode,
+  acos: UnaryOperatorNode,
+  atan: UnaryOperatorNode,
+  saturate: UnaryOperatorNode,
+  length: UnaryOperatorNode,
+  normalize: UnaryOperatorNode,
+  mix: NaryOperatorNode,
+  clamp: NaryOperatorNode,
+  smoothstep: NaryOperatorNode,
+  step: NaryOperatorNode,
+  fma: NaryOperatorNode,
+  select: NaryOperatorNode,
+  split: SplitNode,
+  append: AppendNode,
+  swizzle: SwizzleNode,
+  float: ConstantNode,
+  vec2: ConstantNode,
+  vec3: ConstantNode,
+  vec4: ConstantNode,
+  int: ConstantNode,
+  bool: ConstantNode,
+  color: ConstantNode,
   position: BuiltInNode,
   normal: BuiltInNode,
   tangent: BuiltInNode,
@@ -32,6 +76,7 @@ export const nodeTypes = {
   clip_pos: BuiltInNode,
   tex_coord: BuiltInNode,
   tex_coord_2: BuiltInNode,
+  time: TimeNode,
   junction: JunctionNode,
 };
 
@@ -41,6 +86,7 @@ export type NodeCategory =
   | "math"
   | "material-category"
   | "logic"
+  | "constants"
   | "builtins";
 
 export const nodeMetaData: {
@@ -145,6 +191,218 @@ export const nodeMetaData: {
     type: WbblWebappNodeType.ShiftRight,
     description: "Returns the bits of x bit shifted right y times",
   },
+  negate: {
+    category: "math",
+    type: WbblWebappNodeType.Negate,
+    description: "Negates x",
+  },
+  not: {
+    category: "logic",
+    type: WbblWebappNodeType.Not,
+    description:
+      "If x is a boolean, returns its logical negation, else if a number, computes the bitwise complement of its bits",
+  },
+  abs: {
+    nodeMenuName: "Absolute",
+    category: "math",
+    type: WbblWebappNodeType.Abs,
+    description: "Returns the absolute value of x",
+  },
+  sign: {
+    category: "math",
+    type: WbblWebappNodeType.Sign,
+    description: "Returns -1, 0 or 1 depending on the sign of x",
+  },
+  floor: {
+    category: "math",
+    type: WbblWebappNodeType.Floor,
+    description: "Rounds x down to the nearest integer",
+  },
+  ceil: {
+    nodeMenuName: "Ceiling",
+    category: "math",
+    type: WbblWebappNodeType.Ceil,
+    description: "Rounds x up to the nearest integer",
+  },
+  round: {
+    category: "math",
+    type: WbblWebappNodeType.Round,
+    description: "Rounds x to the nearest integer",
+  },
+  fract: {
+    nodeMenuName: "Fractional Part",
+    category: "math",
+    type: WbblWebappNodeType.Fract,
+    description: "Returns the fractional part of x",
+  },
+  trunc: {
+    nodeMenuName: "Truncate",
+    category: "math",
+    type: WbblWebappNodeType.Trunc,
+    description: "Returns the integer part of x",
+  },
+  sqrt: {
+    nodeMenuName: "Square Root",
+    category: "math",
+    type: WbblWebappNodeType.Sqrt,
+    description: "Returns the square root of x",
+  },
+  exp: {
+    nodeMenuName: "Exponent",
+    category: "math",
+    type: WbblWebappNodeType.Exp,
+    description: "Returns the natural exponent of x",
+  },
+  log: {
+    nodeMenuName: "Logarithm",
+    category: "math",
+    type: WbblWebappNodeType.Log,
+    description: "Returns the natural logarithm of x",
+  },
+  sin: {
+    nodeMenuName: "Sine",
+    category: "math",
+    type: WbblWebappNodeType.Sin,
+    description: "Returns the sine of x",
+  },
+  cos: {
+    nodeMenuName: "Cosine",
+    category: "math",
+    type: WbblWebappNodeType.Cos,
+    description: "Returns the cosine of x",
+  },
+  tan: {
+    nodeMenuName: "Tangent",
+    category: "math",
+    type: WbblWebappNodeType.Tan,
+    description: "Returns the tangent of x",
+  },
+  asin: {
+    nodeMenuName: "Arc Sine",
+    category: "math",
+    type: WbblWebappNodeType.Asin,
+    description: "Returns the arc sine of x",
+  },
+  acos: {
+    nodeMenuName: "Arc Cosine",
+    category: "math",
+    type: WbblWebappNodeType.Acos,
+    description: "Returns the arc cosine of x",
+  },
+  atan: {
+    nodeMenuName: "Arc Tangent",
+    category: "math",
+    type: WbblWebappNodeType.Atan,
+    description: "Returns the arc tangent of x",
+  },
+  saturate: {
+    category: "math",
+    type: WbblWebappNodeType.Saturate,
+    description: "Clamps x between 0 and 1",
+  },
+  length: {
+    category: "math",
+    type: WbblWebappNodeType.Length,
+    description: "Returns the length of the vector x",
+  },
+  normalize: {
+    category: "math",
+    type: WbblWebappNodeType.Normalize,
+    description:
+      "Returns a vector in the same direction as x with a length of 1",
+  },
+  mix: {
+    category: "math",
+    type: WbblWebappNodeType.Mix,
+    description: "Linearly interpolates from a to b by t",
+  },
+  clamp: {
+    category: "math",
+    type: WbblWebappNodeType.Clamp,
+    description: "Restricts x to lie between min and max",
+  },
+  smoothstep: {
+    category: "math",
+    type: WbblWebappNodeType.Smoothstep,
+    description:
+      "Smoothly interpolates from 0 to 1 as x moves between low and high",
+  },
+  step: {
+    category: "math",
+    type: WbblWebappNodeType.Step,
+    description:
+      "Returns 1 where x is greater than or equal to edge, otherwise 0",
+  },
+  fma: {
+    nodeMenuName: "Fused Multiply Add",
+    category: "math",
+    type: WbblWebappNodeType.Fma,
+    description: "Returns a * b + c",
+  },
+  select: {
+    category: "logic",
+    type: WbblWebappNodeType.Select,
+    description:
+      "Returns the true input when cond is true, otherwise the false input",
+  },
+  split: {
+    category: "math",
+    type: WbblWebappNodeType.Split,
+    description:
+      "Splits a vector into its components. The z and w outputs need an input with that many components",
+  },
+  append: {
+    category: "math",
+    type: WbblWebappNodeType.Append,
+    description:
+      "Combines a and b into a single vector with the components of both",
+  },
+  swizzle: {
+    category: "math",
+    type: WbblWebappNodeType.Swizzle,
+    description:
+      "Reorders or repeats the components of a vector, using a pattern such as xzy or rgba",
+  },
+  float: {
+    category: "constants",
+    type: WbblWebappNodeType.Float,
+    description: "A constant floating point number",
+  },
+  vec2: {
+    nodeMenuName: "Vector 2",
+    category: "constants",
+    type: WbblWebappNodeType.Vec2,
+    description: "A constant two component vector",
+  },
+  vec3: {
+    nodeMenuName: "Vector 3",
+    category: "constants",
+    type: WbblWebappNodeType.Vec3,
+    description: "A constant three component vector",
+  },
+  vec4: {
+    nodeMenuName: "Vector 4",
+    category: "constants",
+    type: WbblWebappNodeType.Vec4,
+    description: "A constant four component vector",
+  },
+  int: {
+    nodeMenuName: "Integer",
+    category: "constants",
+    type: WbblWebappNodeType.Int,
+    description: "A constant signed integer",
+  },
+  bool: {
+    nodeMenuName: "Boolean",
+    category: "constants",
+    type: WbblWebappNodeType.Bool,
+    description: "A constant true or false value",
+  },
+  color: {
+    category: "constants",
+    type: WbblWebappNodeType.Color,
+    description: "A constant linear RGBA colour",
+  },
   position: {
     nodeMenuName: "World Position",
     category: "builtins",
@@ -188,6 +446,12 @@ export const nodeMetaData: {
     description:
       "Returns the 2nd texture coordinate for this model, if present",
   },
+  time: {
+    category: "builtins",
+    type: WbblWebappNodeType.Time,
+    description:
+      "Returns the seconds elapsed, the seconds since the last frame and the sine of the seconds elapsed",
+  },
   junction: {
     nodeMenuName: "Junction",
     category: "utility",
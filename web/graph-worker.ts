@@ -1,5 +1,9 @@
 import { WbblGraphWebWorkerJsWrapper } from "../pkg/wbbl";
-import type { DeregisterCanvas, RegisterCanvas } from "./worker_message_types";
+import type {
+  DeregisterCanvas,
+  RegisterCanvas,
+  ResizeCanvas,
+} from "./worker_message_types";
 
 async function run_in_worker() {
   const web_worker_main = await WbblGraphWebWorkerJsWrapper.new(self, self);
@@ -18,6 +22,13 @@ async function run_in_worker() {
       // message.
       const deregister_msg = msg.data.DeregisterCanvas as DeregisterCanvas;
       web_worker_main.deregister_canvas(deregister_msg.nodeId);
+    } else if (msg.data.ResizeCanvas) {
+      const resize_msg = msg.data.ResizeCanvas as ResizeCanvas;
+      web_worker_main.resize_canvas(
+        resize_msg.nodeId,
+        resize_msg.width,
+        resize_msg.height,
+      );
     } else {
       try {
         web_worker_main.handle_message(msg.data);
@@ -0,0 +1,37 @@
+import { useCallback, useContext } from "react";
+import { useStoreApi } from "@xyflow/react";
+import { WbblGraphStoreContext } from "./use-wbbl-graph-store";
+import { LayoutDirection } from "../../pkg/wbbl";
+
+export function useAutoLayout() {
+  const graphStore = useContext(WbblGraphStoreContext);
+  const storeApi = useStoreApi();
+
+  return useCallback(
+    (
+      nodes?: Set<string>,
+      direction: LayoutDirection = LayoutDirection.LeftToRight,
+    ) => {
+      let laidOutNodes: string[];
+      if (nodes !== undefined) {
+        laidOutNodes = [...nodes];
+        graphStore.auto_layout_nodes(laidOutNodes, direction);
+      } else {
+        laidOutNodes = graphStore.get_locally_selected_nodes();
+        graphStore.auto_layout(true, direction);
+      }
+
+      setTimeout(() => {
+        const state = storeApi.getState();
+        const zoom = state.panZoom?.getViewport().zoom;
+        state.fitView({
+          nodes: laidOutNodes.map((id) => ({ id })),
+          duration: 1000,
+          minZoom: zoom,
+          maxZoom: zoom,
+        });
+      }, 30);
+    },
+    [graphStore, storeApi],
+  );
+}
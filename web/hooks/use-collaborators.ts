@@ -0,0 +1,49 @@
+import { useCallback, useRef, useSyncExternalStore } from "react";
+import { WbblWebappGraphStore } from "../../pkg/wbbl";
+
+export type WbblGraphEntityIds = {
+  nodes: string[];
+  edges: string[];
+  groups: string[];
+};
+
+export type WbblCollaborator = {
+  client_id: string;
+  colour: string;
+  cursor: { x: number; y: number } | null;
+  viewport: { x: number; y: number; zoom: number } | null;
+  dragging: WbblGraphEntityIds;
+  selections: WbblGraphEntityIds;
+};
+
+export function useCollaborators(
+  store: WbblWebappGraphStore,
+): WbblCollaborator[] {
+  const data = useRef<WbblCollaborator[]>();
+  const subscribe = useCallback(
+    (subscriber: () => void) => {
+      const invalidate = () => {
+        data.current = undefined;
+        subscriber();
+      };
+      // Selections live in the graph document, while everything else is
+      // published through awareness
+      const presenceHandle = store.subscribe_presence(invalidate);
+      const graphHandle = store.subscribe(invalidate);
+      return () => {
+        store.unsubscribe_presence(presenceHandle);
+        store.unsubscribe(graphHandle);
+      };
+    },
+    [store],
+  );
+
+  const getSnapshot = useCallback(() => {
+    if (data.current == undefined) {
+      data.current = store.get_collaborators() as WbblCollaborator[];
+    }
+    return data.current!;
+  }, [store]);
+
+  return useSyncExternalStore(subscribe, getSnapshot, getSnapshot);
+}
@@ -0,0 +1,19 @@
+import { useCallback, useSyncExternalStore } from "react";
+import { WbblWebappGraphStore } from "../../pkg/wbbl";
+
+export function useIsHydrated(store: WbblWebappGraphStore): boolean {
+  const subscribe = useCallback(
+    (subscriber: () => void) => {
+      const handle = store.subscribe_connection_state(subscriber);
+      return () => {
+        if (handle !== undefined) {
+          store.unsubscribe_connection_state(handle);
+        }
+      };
+    },
+    [store],
+  );
+  const getSnapshot = useCallback(() => store.is_hydrated(), [store]);
+
+  return useSyncExternalStore(subscribe, getSnapshot, getSnapshot);
+}
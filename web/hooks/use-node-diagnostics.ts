@@ -0,0 +1,18 @@
+import { useCallback } from "react";
+import {
+  WbblWebappGraphSnapshot,
+  useWbblGraphDataWithSelector,
+} from "./use-wbbl-graph-store";
+
+export function useNodeDiagnosticMessage(nodeId: string): string | null {
+  const diagnosticMessage = useCallback(
+    (snapshot: WbblWebappGraphSnapshot) => {
+      const messages = (snapshot.diagnostics ?? [])
+        .filter((diagnostic) => diagnostic.node_id === nodeId)
+        .map((diagnostic) => diagnostic.message);
+      return messages.length > 0 ? messages.join("\n") : null;
+    },
+    [nodeId],
+  );
+  return useWbblGraphDataWithSelector(diagnosticMessage) ?? null;
+}
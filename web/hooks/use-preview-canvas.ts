@@ -0,0 +1,48 @@
+import { useLayoutEffect, useState } from "react";
+import { graphWorker } from "../graph-worker-reference";
+import {
+  DeregisterCanvas,
+  RegisterCanvas,
+  ResizeCanvas,
+} from "../worker_message_types";
+
+// Hands the canvas over to the graph worker to draw the node's preview into, keeping its
+// resolution in step with the canvas's size on screen
+export function usePreviewCanvas(
+  nodeId: string,
+): (canvas: HTMLCanvasElement | null) => void {
+  const [canvasRef, setCanvasRef] = useState<HTMLCanvasElement | null>(null);
+  useLayoutEffect(() => {
+    if (canvasRef) {
+      const offscreenCanvas = canvasRef.transferControlToOffscreen();
+      const msg: RegisterCanvas = { nodeId, offscreenCanvas };
+      graphWorker.postMessage({ RegisterCanvas: msg }, [offscreenCanvas]);
+
+      const observer = new ResizeObserver((entries) => {
+        for (const entry of entries) {
+          const size = entry.devicePixelContentBoxSize?.[0];
+          const resizeMessage: ResizeCanvas = {
+            nodeId,
+            width: Math.round(
+              size?.inlineSize ??
+                entry.contentRect.width * window.devicePixelRatio,
+            ),
+            height: Math.round(
+              size?.blockSize ??
+                entry.contentRect.height * window.devicePixelRatio,
+            ),
+          };
+          graphWorker.postMessage({ ResizeCanvas: resizeMessage });
+        }
+      });
+      observer.observe(canvasRef);
+
+      return () => {
+        observer.disconnect();
+        const deregisterMessage: DeregisterCanvas = { nodeId };
+        graphWorker.postMessage({ DeregisterCanvas: deregisterMessage });
+      };
+    }
+  }, [canvasRef, nodeId]);
+  return setCanvasRef;
+}
@@ -0,0 +1,14 @@
+import { useCallback } from "react";
+import {
+  WbblWebappGraphSnapshot,
+  useWbblGraphDataWithSelector,
+} from "./use-wbbl-graph-store";
+
+export function useIsEdgeInTypeConflict(edgeId: string): boolean {
+  const isInConflict = useCallback(
+    (snapshot: WbblWebappGraphSnapshot) =>
+      !!snapshot.type_conflict?.edges.includes(edgeId),
+    [edgeId],
+  );
+  return !!useWbblGraphDataWithSelector(isInConflict);
+}
@@ -25,6 +25,17 @@ export type WbblWebappGraphSnapshot = {
     bounds: [number, number][];
   }[];
   computed_types: null | Map<string, unknown>;
+  type_conflict: null | {
+    ports: string[];
+    edges: string[];
+    constraints: unknown[];
+  };
+  diagnostics: null | {
+    node_id: string;
+    // Qualified like the keys of `computed_types`, e.g. `${nodeId}#s#0`
+    port: `${string}#${"s" | "t"}#${number}` | null;
+    message: string;
+  }[];
 };
 
 export function useWbblGraphData(
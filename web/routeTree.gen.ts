@@ -21,6 +21,9 @@ const AppTestLazyImport = createFileRoute('/app/test')()
 const AppProjectsProjectNameIndexLazyImport = createFileRoute(
   '/app/projects/$projectName/',
 )()
+const AppProjectsProjectNameGraphsGraphIdLazyImport = createFileRoute(
+  '/app/projects/$projectName/graphs/$graphId',
+)()
 
 // Create/Update Routes
 
@@ -44,6 +47,16 @@ const AppProjectsProjectNameIndexLazyRoute =
     ),
   )
 
+const AppProjectsProjectNameGraphsGraphIdLazyRoute =
+  AppProjectsProjectNameGraphsGraphIdLazyImport.update({
+    path: '/app/projects/$projectName/graphs/$graphId',
+    getParentRoute: () => rootRoute,
+  } as any).lazy(() =>
+    import('./routes/app/projects/$projectName/graphs/$graphId.lazy').then(
+      (d) => d.Route,
+    ),
+  )
+
 // Populate the FileRoutesByPath interface
 
 declare module '@tanstack/react-router' {
@@ -69,6 +82,13 @@ declare module '@tanstack/react-router' {
       preLoaderRoute: typeof AppProjectsProjectNameIndexLazyImport
       parentRoute: typeof rootRoute
     }
+    '/app/projects/$projectName/graphs/$graphId': {
+      id: '/app/projects/$projectName/graphs/$graphId'
+      path: '/app/projects/$projectName/graphs/$graphId'
+      fullPath: '/app/projects/$projectName/graphs/$graphId'
+      preLoaderRoute: typeof AppProjectsProjectNameGraphsGraphIdLazyImport
+      parentRoute: typeof rootRoute
+    }
   }
 }
 
@@ -78,6 +98,7 @@ export const routeTree = rootRoute.addChildren({
   AppTestLazyRoute,
   AppIndexLazyRoute,
   AppProjectsProjectNameIndexLazyRoute,
+  AppProjectsProjectNameGraphsGraphIdLazyRoute,
 })
 
 /* prettier-ignore-end */
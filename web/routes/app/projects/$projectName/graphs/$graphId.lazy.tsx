@@ -0,0 +1,14 @@
+import { createLazyFileRoute } from "@tanstack/react-router";
+import App from "../../../../../App";
+
+export const Route = createLazyFileRoute(
+  "/app/projects/$projectName/graphs/$graphId",
+)({
+  component: ProjectGraph,
+});
+
+function ProjectGraph() {
+  const { projectName, graphId } = Route.useParams();
+
+  return <App projectName={projectName} graphId={graphId} />;
+}
@@ -6,3 +6,9 @@ export type RegisterCanvas = {
 export type DeregisterCanvas = {
   nodeId: string;
 };
+
+export type ResizeCanvas = {
+  nodeId: string;
+  width: number;
+  height: number;
+};